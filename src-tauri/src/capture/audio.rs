@@ -3,17 +3,28 @@
 //! This module provides microphone capture functionality using the cpal crate.
 //! System audio capture is handled separately by platform-specific modules.
 
+use crate::capture::audio_devices::{
+    remap_channels, silence_frames_due, subscribe_audio_device_changes, MicrophoneFallback,
+};
+use crate::capture::encoder::{ffmpeg_backend, AudioSink, AudioSpec, EncoderBackend};
+use crate::capture::levels::LevelMeter;
 use crate::capture::traits::AudioDeviceInfo;
-use crate::recorder::channel::{ChannelType, RecordingChannel, RecordingError, RecordingResult};
+use crate::recorder::channel::{
+    ChannelErrorReporter, ChannelType, RecordingChannel, RecordingError, RecordingResult,
+};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
+use crate::recorder::segment::FRAGMENTED_MP4_ARGS;
 use async_trait::async_trait;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, SampleFormat, StreamConfig};
 use parking_lot::Mutex as ParkingMutex;
-use std::io::Write;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::TryRecvError;
 
 /// Get list of available audio input devices
 pub fn get_audio_input_devices() -> Vec<AudioDeviceInfo> {
@@ -63,105 +74,708 @@ pub fn get_input_device_by_name(name: &str) -> Option<Device> {
     None
 }
 
-/// FFmpeg encoder for audio
+/// Codec and container of a recorded audio track
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "codec", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum AudioFormat {
+    /// AAC in fragmented MP4 (`.m4a`)
+    Aac { bitrate_kbps: u32 },
+    /// Opus in Ogg (`.opus`)
+    Opus { bitrate_kbps: u32 },
+    /// Lossless FLAC (`.flac`)
+    Flac,
+    /// Uncompressed 32-bit float PCM in WAV (`.wav`)
+    Wav,
+    /// Uncompressed 32-bit float PCM in Core Audio Format (`.caf`)
+    Caf,
+}
+
+/// File extensions of every `AudioFormat`, for finding recorded tracks
+pub const AUDIO_EXTENSIONS: [&str; 5] = ["m4a", "opus", "flac", "wav", "caf"];
+
+impl Default for AudioFormat {
+    fn default() -> Self {
+        AudioFormat::Aac { bitrate_kbps: 192 }
+    }
+}
+
+impl AudioFormat {
+    /// Default for microphone tracks, so export processing starts from the original samples
+    pub fn lossless() -> Self {
+        AudioFormat::Flac
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            AudioFormat::Aac { .. } => "m4a",
+            AudioFormat::Opus { .. } => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Caf => "caf",
+        }
+    }
+
+    pub fn is_lossless(&self) -> bool {
+        !matches!(self, AudioFormat::Aac { .. } | AudioFormat::Opus { .. })
+    }
+
+    /// FFmpeg encoder used for this format
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            AudioFormat::Aac { .. } => "aac",
+            AudioFormat::Opus { .. } => "libopus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav | AudioFormat::Caf => "pcm_f32le",
+        }
+    }
+
+    /// FFmpeg muxer writing the format's file (`.m4a` is the "ipod" muxer)
+    pub fn muxer(&self) -> &'static str {
+        match self {
+            AudioFormat::Aac { .. } => "ipod",
+            AudioFormat::Opus { .. } => "opus",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Caf => "caf",
+        }
+    }
+
+    /// Rough bitrate of a 48kHz stereo track, for the disk space guard
+    pub fn estimated_kbps(&self) -> u64 {
+        match *self {
+            AudioFormat::Aac { bitrate_kbps } | AudioFormat::Opus { bitrate_kbps } => {
+                bitrate_kbps as u64
+            }
+            // About 60% of 16-bit PCM
+            AudioFormat::Flac => 1_000,
+            // 32-bit float samples
+            AudioFormat::Wav | AudioFormat::Caf => 3_072,
+        }
+    }
+
+    /// Check the bitrate is one the encoder accepts
+    pub fn validate(&self) -> RecordingResult<()> {
+        let (name, bitrate, range) = match *self {
+            AudioFormat::Aac { bitrate_kbps } => ("AAC", bitrate_kbps, 32..=512),
+            AudioFormat::Opus { bitrate_kbps } => ("Opus", bitrate_kbps, 6..=510),
+            _ => return Ok(()),
+        };
+        if range.contains(&bitrate) {
+            Ok(())
+        } else {
+            Err(RecordingError::ConfigurationError(format!(
+                "{} bitrate must be between {} and {} kbps, got {}",
+                name,
+                range.start(),
+                range.end(),
+                bitrate
+            )))
+        }
+    }
+
+    /// FFmpeg output arguments for this format
+    pub(crate) fn ffmpeg_args(&self) -> Vec<String> {
+        let mut args: Vec<String> = vec!["-c:a".into(), self.encoder().into()];
+        if let AudioFormat::Aac { bitrate_kbps } | AudioFormat::Opus { bitrate_kbps } = self {
+            args.extend(["-b:a".into(), format!("{}k", bitrate_kbps)]);
+        }
+        // Playable after a crash; Ogg, FLAC and PCM are written sequentially anyway
+        if let AudioFormat::Aac { .. } = self {
+            args.extend(FRAGMENTED_MP4_ARGS.iter().map(|s| s.to_string()));
+        }
+        args
+    }
+}
+
+/// Find the audio track of a session, whichever format it was recorded in
+pub fn find_audio_track(output_dir: &Path, session_index: usize, suffix: &str) -> Option<PathBuf> {
+    AUDIO_EXTENSIONS
+        .iter()
+        .map(|ext| output_dir.join(format!("recording-{}-{}.{}", session_index, suffix, ext)))
+        .find(|path| path.exists())
+}
+
+/// Audio encoder of a channel for one session
+///
+/// Feeds samples to the backend's `AudioSink`, counting them on the channel's
+/// health and publishing their levels.
 pub struct AudioEncoder {
-    process: ParkingMutex<Option<Child>>,
+    sink: ParkingMutex<Option<Box<dyn AudioSink>>>,
     sample_count: AtomicU64,
     running: AtomicBool,
     output_path: PathBuf,
+    sample_rate: u32,
+    channels: u16,
+    session_index: usize,
+    /// Clock and channel ID to mark the first sample on
+    first_sample_clock: Option<(RecordingClock, String)>,
+    /// Where to report the sink failing mid-recording
+    error_reporter: ChannelErrorReporter,
+    /// Counters for written samples and write latency
+    health: ChannelHealth,
+    /// Meter publishing the levels of the samples passing through
+    levels: Option<ParkingMutex<LevelMeter>>,
 }
 
 impl AudioEncoder {
     pub fn new(
+        encoders: &dyn EncoderBackend,
         sample_rate: u32,
         channels: u16,
         output_dir: &Path,
         session_index: usize,
         suffix: &str,
+        format: AudioFormat,
     ) -> Result<Self, std::io::Error> {
-        std::fs::create_dir_all(output_dir)?;
-
-        let output_path = output_dir.join(format!("recording-{}-{}.m4a", session_index, suffix));
-
-        // Start FFmpeg process for audio encoding
-        // Input: 32-bit float PCM from cpal
-        // Output: AAC in M4A container
-        let process = Command::new("ffmpeg")
-            .args([
-                "-y",                            // Overwrite output
-                "-f", "f32le",                   // 32-bit float little-endian PCM
-                "-ar", &sample_rate.to_string(), // Sample rate
-                "-ac", &channels.to_string(),   // Channel count
-                "-i", "-",                       // Read from stdin
-                "-c:a", "aac",                   // AAC codec
-                "-b:a", "192k",                  // 192kbps bitrate
-                "-movflags", "+faststart",       // For streaming
-                output_path.to_str().unwrap(),
-            ])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()?;
-
-        tracing::info!(
-            "Started audio encoder: {}Hz {}ch, output: {:?}",
+        let output_path = output_dir.join(format!(
+            "recording-{}-{}.{}",
+            session_index,
+            suffix,
+            format.extension()
+        ));
+
+        let sink = encoders.audio_sink(&AudioSpec {
             sample_rate,
             channels,
-            output_path
-        );
+            format,
+            output_file: output_path.clone(),
+        })?;
 
         Ok(Self {
-            process: ParkingMutex::new(Some(process)),
+            sink: ParkingMutex::new(Some(sink)),
             sample_count: AtomicU64::new(0),
             running: AtomicBool::new(true),
             output_path,
+            sample_rate,
+            channels,
+            session_index,
+            first_sample_clock: None,
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
+            levels: None,
         })
     }
 
+    /// Mark the capture time of the first written sample on the recording clock
+    pub fn with_clock(mut self, clock: RecordingClock, channel_id: &str) -> Self {
+        self.first_sample_clock = Some((clock, channel_id.to_string()));
+        self
+    }
+
+    /// Report when the sink stops accepting samples mid-recording
+    pub fn with_error_reporter(mut self, reporter: ChannelErrorReporter) -> Self {
+        self.error_reporter = reporter;
+        self
+    }
+
+    /// Count written samples and write latency on the channel's health counters
+    pub fn with_health(mut self, health: ChannelHealth) -> Self {
+        self.health = health;
+        self
+    }
+
+    /// Publish the levels of written samples under the channel's ID
+    pub fn with_levels(mut self, channel_id: &str) -> Self {
+        self.levels = Some(ParkingMutex::new(LevelMeter::new(
+            channel_id,
+            self.channels,
+            self.sample_rate,
+        )));
+        self
+    }
+
     pub fn write_samples(&self, data: &[u8]) -> bool {
+        // Metered even if the sink died, so the meters show the input is alive
+        if let Some(ref levels) = self.levels {
+            levels.lock().push_f32le(data);
+        }
+
         if !self.running.load(Ordering::Relaxed) {
             return false;
         }
 
-        let mut guard = self.process.lock();
-        if let Some(ref mut process) = *guard {
-            if let Some(ref mut stdin) = process.stdin {
-                if stdin.write_all(data).is_ok() {
-                    self.sample_count.fetch_add((data.len() / 4) as u64, Ordering::Relaxed);
-                    return true;
+        if let Some(ref mut sink) = *self.sink.lock() {
+            let started = Instant::now();
+            let accepted = sink.write_samples(data).is_ok();
+            self.health.record_write(data.len(), started.elapsed(), accepted);
+            if accepted {
+                let written = self.sample_count.fetch_add((data.len() / 4) as u64, Ordering::Relaxed);
+                if written == 0 {
+                    self.mark_first_sample(data.len());
                 }
+                let frames = data.len() / 4 / self.channels.max(1) as usize;
+                self.health.record_audio(frames as u64, self.sample_rate);
+                return true;
             }
         }
+
+        // The sink died under us; report it once and drop further samples
+        if self.running.swap(false, Ordering::Relaxed) {
+            self.error_reporter
+                .report(format!("Audio encoder for {:?} exited", self.output_path));
+        }
         false
     }
 
+    /// The first buffer was captured one buffer duration before it arrived
+    fn mark_first_sample(&self, buffer_bytes: usize) {
+        let Some((ref clock, ref channel_id)) = self.first_sample_clock else {
+            return;
+        };
+
+        let frames = buffer_bytes / 4 / self.channels.max(1) as usize;
+        let buffer_ms = frames as f64 * 1000.0 / self.sample_rate.max(1) as f64;
+        clock.mark_first_sample(channel_id, self.session_index, clock.now_ms() - buffer_ms);
+    }
+
     pub fn sample_count(&self) -> u64 {
         self.sample_count.load(Ordering::Relaxed)
     }
 
+    /// Finish encoding, returning the file written (None if no samples arrived)
     pub fn finish(&self) -> Result<Option<String>, std::io::Error> {
         self.running.store(false, Ordering::Relaxed);
-        let mut guard = self.process.lock();
-        if let Some(mut process) = guard.take() {
-            drop(process.stdin.take());
-            let output = process.wait_with_output()?;
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                tracing::warn!("FFmpeg audio exited with status {}: {}", output.status, stderr);
+        let files = match self.sink.lock().take() {
+            Some(sink) => sink.finish()?,
+            None => Vec::new(),
+        };
+
+        match files.into_iter().next() {
+            Some(file) if self.sample_count() > 0 => {
+                tracing::info!(
+                    "Audio encoding finished: {} samples, output: {}",
+                    self.sample_count(),
+                    file
+                );
+                Ok(Some(file))
             }
+            _ => Ok(None),
         }
+    }
+}
 
-        if self.output_path.exists() && self.sample_count() > 0 {
-            tracing::info!(
-                "Audio encoding finished: {} samples, output: {:?}",
-                self.sample_count(),
-                self.output_path
-            );
-            Ok(Some(self.output_path.to_string_lossy().to_string()))
-        } else {
-            Ok(None)
+/// Meters an input device without recording it
+///
+/// Used before a recording starts so a muted or wrong microphone shows up
+/// right away. Levels are published under `AudioMonitor::SOURCE_ID`; no files
+/// are written. Capture runs on its own thread (cpal::Stream is not Send)
+/// until the monitor is stopped or dropped.
+pub struct AudioMonitor {
+    running: Arc<AtomicBool>,
+    handle: Option<std::thread::JoinHandle<()>>,
+}
+
+impl AudioMonitor {
+    /// Source ID of the monitor's `AudioLevels`
+    pub const SOURCE_ID: &'static str = "monitor";
+
+    /// Start metering an input device; None meters the default device
+    pub fn start(device_id: Option<String>) -> RecordingResult<Self> {
+        let running = Arc::new(AtomicBool::new(true));
+        let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+
+        let thread_running = running.clone();
+        let handle = std::thread::spawn(move || {
+            let stream = match build_monitor_stream(device_id.as_deref()) {
+                Ok(stream) => {
+                    let _ = ready_tx.send(Ok(()));
+                    stream
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+
+            while thread_running.load(Ordering::SeqCst) {
+                std::thread::sleep(std::time::Duration::from_millis(100));
+            }
+            drop(stream);
+        });
+
+        // Report a missing device or failed stream to the caller
+        match ready_rx.recv() {
+            Ok(Ok(())) => {
+                tracing::info!("Audio monitor started");
+                Ok(Self {
+                    running,
+                    handle: Some(handle),
+                })
+            }
+            Ok(Err(e)) => {
+                let _ = handle.join();
+                Err(e)
+            }
+            Err(_) => Err(RecordingError::CaptureError(
+                "Audio monitor thread exited".to_string(),
+            )),
+        }
+    }
+
+    /// Stop metering and release the device
+    pub fn stop(self) {
+        drop(self);
+    }
+}
+
+impl Drop for AudioMonitor {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+            tracing::info!("Audio monitor stopped");
+        }
+    }
+}
+
+fn build_monitor_stream(device_id: Option<&str>) -> RecordingResult<cpal::Stream> {
+    let device = match device_id {
+        Some(name) => get_input_device_by_name(name).ok_or_else(|| {
+            RecordingError::DeviceNotFound(format!("Audio device '{}' not found", name))
+        })?,
+        None => get_default_input_device().ok_or_else(|| {
+            RecordingError::DeviceNotFound("No default audio input device".to_string())
+        })?,
+    };
+
+    let config = device.default_input_config().map_err(|e| {
+        RecordingError::ConfigurationError(format!("Failed to get audio config: {}", e))
+    })?;
+    let sample_format = config.sample_format();
+    let stream_config: StreamConfig = config.into();
+    let meter = LevelMeter::new(
+        AudioMonitor::SOURCE_ID,
+        stream_config.channels,
+        stream_config.sample_rate.0,
+    );
+
+    let stream = match sample_format {
+        SampleFormat::F32 => metering_stream(&device, &stream_config, meter, |s: f32| s),
+        SampleFormat::I16 => metering_stream(&device, &stream_config, meter, |s: i16| {
+            s as f32 / i16::MAX as f32
+        }),
+        SampleFormat::U16 => metering_stream(&device, &stream_config, meter, |s: u16| {
+            (s as f32 / u16::MAX as f32) * 2.0 - 1.0
+        }),
+        other => {
+            return Err(RecordingError::ConfigurationError(format!(
+                "Unsupported sample format: {:?}",
+                other
+            )))
         }
     }
+    .map_err(|e| RecordingError::CaptureError(format!("Failed to build audio stream: {}", e)))?;
+
+    stream.play().map_err(|e| {
+        RecordingError::CaptureError(format!("Failed to start audio stream: {}", e))
+    })?;
+    Ok(stream)
+}
+
+/// Input stream that only feeds a level meter
+fn metering_stream<T: cpal::SizedSample + 'static>(
+    device: &Device,
+    config: &StreamConfig,
+    mut meter: LevelMeter,
+    convert: fn(T) -> f32,
+) -> Result<cpal::Stream, cpal::BuildStreamError> {
+    let mut samples = Vec::new();
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            samples.clear();
+            samples.extend(data.iter().map(|&s| convert(s)));
+            meter.push(&samples);
+        },
+        |err| tracing::warn!("Audio monitor stream error: {}", err),
+        None,
+    )
+}
+
+/// How often the microphone thread checks its stream
+const MIC_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Delay between attempts to reopen a missing microphone
+const MIC_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A stream without data for this long is treated as disconnected
+const MIC_STALL_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Delivers microphone samples of one session to its encoder
+#[derive(Clone)]
+struct MicSink {
+    encoder: Arc<AudioEncoder>,
+    is_recording: Arc<AtomicBool>,
+    /// Audio frames written this session, including silence
+    frames_written: Arc<AtomicU64>,
+    /// Channel count of the encoder
+    channels: u16,
+    session_start: Instant,
+}
+
+impl MicSink {
+    /// Write interleaved samples from a device with `device_channels` channels
+    fn write(&self, samples: &[f32], device_channels: u16, mapped: &mut Vec<f32>) {
+        if !self.is_recording.load(Ordering::Relaxed) {
+            return;
+        }
+
+        remap_channels(samples, device_channels, self.channels, mapped);
+        let bytes: Vec<u8> = mapped.iter().flat_map(|s| s.to_le_bytes()).collect();
+        self.encoder.write_samples(&bytes);
+        let frames = mapped.len() / self.channels.max(1) as usize;
+        self.frames_written.fetch_add(frames as u64, Ordering::Relaxed);
+    }
+
+    /// Write silence up to the current time
+    fn pad_silence(&self, sample_rate: u32) {
+        let due = silence_frames_due(
+            self.session_start.elapsed(),
+            sample_rate,
+            self.frames_written.load(Ordering::Relaxed),
+        );
+        if due > 0 {
+            let bytes = vec![0u8; due as usize * self.channels.max(1) as usize * 4];
+            self.encoder.write_samples(&bytes);
+            self.frames_written.fetch_add(due, Ordering::Relaxed);
+        }
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.session_start.elapsed().as_millis() as u64
+    }
+}
+
+/// Shared between a microphone stream's callbacks and the thread watching it
+struct MicStreamStatus {
+    /// Set when the device reports it's no longer available
+    failed: AtomicBool,
+    /// Time of the last data callback, in ms since the session start
+    last_data_ms: AtomicU64,
+}
+
+/// Capture the microphone until recording stops, surviving disconnects
+///
+/// If the device disappears (stream error, removed from the device list or no
+/// data for `MIC_STALL_TIMEOUT`), the stream is dropped and silence is written
+/// while reopening is retried, following `fallback`.
+fn run_microphone(
+    device_id: Option<String>,
+    fallback: MicrophoneFallback,
+    sample_rate: u32,
+    sink: MicSink,
+    reporter: ChannelErrorReporter,
+) {
+    let mut device_changes = subscribe_audio_device_changes();
+    let mut active: Option<(cpal::Stream, String, Arc<MicStreamStatus>)> = None;
+    let mut next_attempt = Instant::now();
+    let mut unavailable_reported = false;
+
+    while sink.is_recording.load(Ordering::SeqCst) {
+        let mut devices = None;
+        loop {
+            match device_changes.try_recv() {
+                Ok(list) => devices = Some(list),
+                Err(TryRecvError::Lagged(_)) => continue,
+                Err(_) => break,
+            }
+        }
+
+        if let Some((_, ref name, ref status)) = active {
+            let removed = devices
+                .as_ref()
+                .is_some_and(|list| !list.iter().any(|d| &d.id == name));
+            let idle_ms = sink
+                .elapsed_ms()
+                .saturating_sub(status.last_data_ms.load(Ordering::Relaxed));
+            let stalled = idle_ms > MIC_STALL_TIMEOUT.as_millis() as u64;
+
+            if status.failed.load(Ordering::Relaxed) || removed || stalled {
+                reporter.report(format!(
+                    "Microphone '{}' disconnected; recording silence until it's back",
+                    name
+                ));
+                unavailable_reported = true;
+                active = None;
+                next_attempt = Instant::now();
+            }
+        }
+
+        if active.is_none() {
+            // Keep the track as long as the video while there is no device
+            sink.pad_silence(sample_rate);
+
+            if Instant::now() >= next_attempt {
+                match open_microphone(device_id.as_deref(), fallback, sample_rate, &sink, &reporter) {
+                    Ok((stream, name, status)) => {
+                        if unavailable_reported {
+                            tracing::info!("Microphone recording resumed on '{}'", name);
+                        } else {
+                            tracing::info!("Microphone audio stream started on '{}'", name);
+                        }
+                        unavailable_reported = false;
+                        active = Some((stream, name, status));
+                    }
+                    Err(e) => {
+                        if !unavailable_reported {
+                            reporter.report(format!(
+                                "Microphone unavailable, recording silence: {}",
+                                e
+                            ));
+                            unavailable_reported = true;
+                        }
+                        next_attempt = Instant::now() + MIC_RETRY_INTERVAL;
+                    }
+                }
+            }
+        }
+
+        std::thread::sleep(MIC_POLL_INTERVAL);
+    }
+
+    if active.is_none() {
+        sink.pad_silence(sample_rate);
+    }
+    // Stream is dropped here, stopping capture
+    tracing::info!("Microphone audio stream stopped");
+}
+
+/// Open the selected microphone, or the default one if `fallback` allows
+///
+/// The stream runs at the encoder's sample rate; channels are remapped.
+fn open_microphone(
+    device_id: Option<&str>,
+    fallback: MicrophoneFallback,
+    sample_rate: u32,
+    sink: &MicSink,
+    reporter: &ChannelErrorReporter,
+) -> RecordingResult<(cpal::Stream, String, Arc<MicStreamStatus>)> {
+    let device = match device_id {
+        Some(name) => match get_input_device_by_name(name) {
+            Some(device) => Some(device),
+            None if fallback == MicrophoneFallback::DefaultDevice => get_default_input_device(),
+            None => None,
+        },
+        None => get_default_input_device(),
+    }
+    .ok_or_else(|| {
+        RecordingError::DeviceNotFound(format!(
+            "Audio device '{}' not found",
+            device_id.unwrap_or("default")
+        ))
+    })?;
+    let name = device.name().unwrap_or_else(|_| "Unknown".to_string());
+
+    let config = input_config_at_rate(&device, sample_rate)?;
+    let sample_format = config.sample_format();
+    let stream_config: StreamConfig = config.into();
+
+    tracing::info!(
+        "Microphone stream config: device={}, format={:?}, sample_rate={}, channels={}",
+        name,
+        sample_format,
+        stream_config.sample_rate.0,
+        stream_config.channels
+    );
+
+    let status = Arc::new(MicStreamStatus {
+        failed: AtomicBool::new(false),
+        last_data_ms: AtomicU64::new(sink.elapsed_ms()),
+    });
+    let stream = match sample_format {
+        SampleFormat::F32 => mic_stream(&device, &stream_config, sink, &status, reporter, |s: f32| s),
+        SampleFormat::I16 => mic_stream(&device, &stream_config, sink, &status, reporter, |s: i16| {
+            s as f32 / i16::MAX as f32
+        }),
+        SampleFormat::U16 => mic_stream(&device, &stream_config, sink, &status, reporter, |s: u16| {
+            (s as f32 / u16::MAX as f32) * 2.0 - 1.0
+        }),
+        other => {
+            return Err(RecordingError::ConfigurationError(format!(
+                "Unsupported microphone sample format: {:?}",
+                other
+            )))
+        }
+    }
+    .map_err(|e| RecordingError::CaptureError(format!("Failed to build audio stream: {}", e)))?;
+
+    stream.play().map_err(|e| {
+        RecordingError::CaptureError(format!("Failed to start microphone stream: {}", e))
+    })?;
+    Ok((stream, name, status))
+}
+
+/// Input config of a device at the given sample rate, preferring its default
+fn input_config_at_rate(
+    device: &Device,
+    sample_rate: u32,
+) -> RecordingResult<cpal::SupportedStreamConfig> {
+    let default = device.default_input_config().map_err(|e| {
+        RecordingError::ConfigurationError(format!("Failed to get audio config: {}", e))
+    })?;
+    if default.sample_rate().0 == sample_rate {
+        return Ok(default);
+    }
+
+    device
+        .supported_input_configs()
+        .map_err(|e| {
+            RecordingError::ConfigurationError(format!("Failed to get audio configs: {}", e))
+        })?
+        .find(|range| {
+            range.min_sample_rate().0 <= sample_rate && sample_rate <= range.max_sample_rate().0
+        })
+        .map(|range| range.with_sample_rate(cpal::SampleRate(sample_rate)))
+        .ok_or_else(|| {
+            RecordingError::ConfigurationError(format!(
+                "Device can't record at {}Hz",
+                sample_rate
+            ))
+        })
+}
+
+/// Input stream converting samples to f32 and writing them to the sink
+fn mic_stream<T: cpal::SizedSample + 'static>(
+    device: &Device,
+    config: &StreamConfig,
+    sink: &MicSink,
+    status: &Arc<MicStreamStatus>,
+    reporter: &ChannelErrorReporter,
+    convert: fn(T) -> f32,
+) -> Result<cpal::Stream, cpal::BuildStreamError> {
+    let device_channels = config.channels;
+    let sink = sink.clone();
+    let data_status = status.clone();
+    let error_status = status.clone();
+    let reporter = reporter.clone();
+    let mut callback_count = 0u64;
+    let mut samples = Vec::new();
+    let mut mapped = Vec::new();
+
+    device.build_input_stream(
+        config,
+        move |data: &[T], _: &cpal::InputCallbackInfo| {
+            // Log first callback and then every 500th to confirm mic is working
+            if callback_count == 0 {
+                tracing::info!("Microphone: first callback received - capture working!");
+            } else if callback_count.is_multiple_of(500) {
+                tracing::debug!("Microphone: {} callbacks, {} samples this batch", callback_count, data.len());
+            }
+            callback_count += 1;
+
+            data_status.last_data_ms.store(sink.elapsed_ms(), Ordering::Relaxed);
+            samples.clear();
+            samples.extend(data.iter().map(|&s| convert(s)));
+            sink.write(&samples, device_channels, &mut mapped);
+        },
+        move |err| match err {
+            cpal::StreamError::DeviceNotAvailable => {
+                error_status.failed.store(true, Ordering::Relaxed);
+            }
+            err => reporter.report(format!("Microphone stream error: {}", err)),
+        },
+        None,
+    )
 }
 
 /// Microphone capture channel
@@ -173,11 +787,17 @@ pub struct MicrophoneCaptureChannel {
     is_recording: Arc<AtomicBool>,
     output_dir: Option<PathBuf>,
     session_index: usize,
+    clock: RecordingClock,
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
     output_files: Arc<ParkingMutex<Vec<String>>>,
     encoder: Arc<ParkingMutex<Option<Arc<AudioEncoder>>>>,
     stream_handle: Arc<ParkingMutex<Option<std::thread::JoinHandle<()>>>>,
     sample_rate: u32,
     channels: u16,
+    fallback: MicrophoneFallback,
+    format: AudioFormat,
+    encoders: Arc<dyn EncoderBackend>,
 }
 
 impl MicrophoneCaptureChannel {
@@ -190,14 +810,38 @@ impl MicrophoneCaptureChannel {
             is_recording: Arc::new(AtomicBool::new(false)),
             output_dir: None,
             session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
             output_files: Arc::new(ParkingMutex::new(Vec::new())),
             encoder: Arc::new(ParkingMutex::new(None)),
             stream_handle: Arc::new(ParkingMutex::new(None)),
             sample_rate: 48000,
             channels: 2,
+            fallback: MicrophoneFallback::default(),
+            format: AudioFormat::lossless(),
+            encoders: ffmpeg_backend(),
         }
     }
 
+    /// Codec to record the microphone with (lossless FLAC by default)
+    pub fn with_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Encode through another backend (FFmpeg by default)
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
+    /// What to do if the device disappears while recording
+    pub fn with_fallback(mut self, fallback: MicrophoneFallback) -> Self {
+        self.fallback = fallback;
+        self
+    }
+
     fn get_device(&self) -> RecordingResult<Device> {
         match &self.device_id {
             Some(name) => get_input_device_by_name(name).ok_or_else(|| {
@@ -220,13 +864,13 @@ impl RecordingChannel for MicrophoneCaptureChannel {
         ChannelType::Microphone
     }
 
-    async fn initialize(&mut self, output_dir: &Path, session_index: usize) -> RecordingResult<()> {
-        // Check if FFmpeg is available
-        if Command::new("ffmpeg").arg("-version").output().is_err() {
-            return Err(RecordingError::ConfigurationError(
-                "FFmpeg not found. Please install FFmpeg.".to_string(),
-            ));
-        }
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
+        self.encoders.check_audio(&self.format)?;
 
         // Verify device exists
         let device = self.get_device()?;
@@ -241,6 +885,7 @@ impl RecordingChannel for MicrophoneCaptureChannel {
         self.channels = config.channels();
         self.output_dir = Some(output_dir.to_path_buf());
         self.session_index = session_index;
+        self.clock = clock.clone();
 
         tracing::info!(
             "Microphone channel initialized: {} ({}Hz, {}ch)",
@@ -263,169 +908,42 @@ impl RecordingChannel for MicrophoneCaptureChannel {
         // Create encoder
         let encoder = Arc::new(
             AudioEncoder::new(
+                self.encoders.as_ref(),
                 self.sample_rate,
                 self.channels,
                 &output_dir,
                 self.session_index,
                 "mic",
+                self.format,
             )
+            .map(|encoder| {
+                encoder
+                    .with_clock(self.clock.clone(), &self.id)
+                    .with_error_reporter(self.error_reporter.clone())
+                    .with_health(self.health.clone())
+                    .with_levels(&self.id)
+            })
             .map_err(|e| RecordingError::CaptureError(format!("Failed to start audio encoder: {}", e)))?,
         );
         *self.encoder.lock() = Some(encoder.clone());
 
         self.is_recording.store(true, Ordering::SeqCst);
 
-        // Clone values for the thread
+        let sink = MicSink {
+            encoder,
+            is_recording: self.is_recording.clone(),
+            frames_written: Arc::new(AtomicU64::new(0)),
+            channels: self.channels,
+            session_start: Instant::now(),
+        };
         let device_id = self.device_id.clone();
-        let is_recording = self.is_recording.clone();
+        let fallback = self.fallback;
+        let sample_rate = self.sample_rate;
+        let reporter = self.error_reporter.clone();
 
         // Spawn a thread to handle the audio stream (cpal::Stream is not Send)
         let handle = std::thread::spawn(move || {
-            let device = match &device_id {
-                Some(name) => get_input_device_by_name(name),
-                None => get_default_input_device(),
-            };
-
-            let device = match device {
-                Some(d) => d,
-                None => {
-                    tracing::error!("Failed to get audio device in thread");
-                    return;
-                }
-            };
-
-            let config = match device.default_input_config() {
-                Ok(c) => c,
-                Err(e) => {
-                    tracing::error!("Failed to get audio config: {}", e);
-                    return;
-                }
-            };
-
-            let sample_format = config.sample_format();
-            let stream_config: StreamConfig = config.into();
-
-            // Log the actual stream configuration for debugging
-            tracing::info!(
-                "Microphone stream config: format={:?}, sample_rate={}, channels={}",
-                sample_format,
-                stream_config.sample_rate.0,
-                stream_config.channels
-            );
-
-            // Callback counter for diagnostic logging
-            let callback_count = Arc::new(AtomicU64::new(0));
-
-            let stream = match sample_format {
-                SampleFormat::F32 => {
-                    let encoder_clone = encoder.clone();
-                    let is_rec = is_recording.clone();
-                    let cc = callback_count.clone();
-                    device.build_input_stream(
-                        &stream_config,
-                        move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                            let count = cc.fetch_add(1, Ordering::Relaxed);
-                            // Log first callback and then every 500th to confirm mic is working
-                            if count == 0 {
-                                tracing::info!("Microphone: first callback received - capture working!");
-                            } else if count % 500 == 0 {
-                                tracing::debug!("Microphone: {} callbacks, {} samples this batch", count, data.len());
-                            }
-                            
-                            if is_rec.load(Ordering::Relaxed) {
-                                let bytes: Vec<u8> = data
-                                    .iter()
-                                    .flat_map(|&sample| sample.to_le_bytes())
-                                    .collect();
-                                encoder_clone.write_samples(&bytes);
-                            }
-                        },
-                        |err| tracing::error!("Microphone stream error: {}", err),
-                        None,
-                    )
-                }
-                SampleFormat::I16 => {
-                    let encoder_clone = encoder.clone();
-                    let is_rec = is_recording.clone();
-                    let cc = callback_count.clone();
-                    device.build_input_stream(
-                        &stream_config,
-                        move |data: &[i16], _: &cpal::InputCallbackInfo| {
-                            let count = cc.fetch_add(1, Ordering::Relaxed);
-                            if count == 0 {
-                                tracing::info!("Microphone: first callback received - capture working!");
-                            } else if count % 500 == 0 {
-                                tracing::debug!("Microphone: {} callbacks, {} samples this batch", count, data.len());
-                            }
-                            
-                            if is_rec.load(Ordering::Relaxed) {
-                                let bytes: Vec<u8> = data
-                                    .iter()
-                                    .map(|&sample| sample as f32 / i16::MAX as f32)
-                                    .flat_map(|sample| sample.to_le_bytes())
-                                    .collect();
-                                encoder_clone.write_samples(&bytes);
-                            }
-                        },
-                        |err| tracing::error!("Microphone stream error: {}", err),
-                        None,
-                    )
-                }
-                SampleFormat::U16 => {
-                    let encoder_clone = encoder.clone();
-                    let is_rec = is_recording.clone();
-                    let cc = callback_count.clone();
-                    device.build_input_stream(
-                        &stream_config,
-                        move |data: &[u16], _: &cpal::InputCallbackInfo| {
-                            let count = cc.fetch_add(1, Ordering::Relaxed);
-                            if count == 0 {
-                                tracing::info!("Microphone: first callback received - capture working!");
-                            } else if count % 500 == 0 {
-                                tracing::debug!("Microphone: {} callbacks, {} samples this batch", count, data.len());
-                            }
-                            
-                            if is_rec.load(Ordering::Relaxed) {
-                                let bytes: Vec<u8> = data
-                                    .iter()
-                                    .map(|&sample| (sample as f32 / u16::MAX as f32) * 2.0 - 1.0)
-                                    .flat_map(|sample| sample.to_le_bytes())
-                                    .collect();
-                                encoder_clone.write_samples(&bytes);
-                            }
-                        },
-                        |err| tracing::error!("Microphone stream error: {}", err),
-                        None,
-                    )
-                }
-                _ => {
-                    tracing::error!("Unsupported microphone sample format: {:?}", sample_format);
-                    return;
-                }
-            };
-
-            let stream = match stream {
-                Ok(s) => s,
-                Err(e) => {
-                    tracing::error!("Failed to build audio stream: {}", e);
-                    return;
-                }
-            };
-
-            if let Err(e) = stream.play() {
-                tracing::error!("Failed to start microphone stream: {}", e);
-                return;
-            }
-
-            tracing::info!("Microphone audio stream started successfully");
-
-            // Keep thread alive while recording
-            while is_recording.load(Ordering::SeqCst) {
-                std::thread::sleep(std::time::Duration::from_millis(100));
-            }
-
-            // Stream is dropped here, stopping capture
-            tracing::info!("Microphone audio stream stopped");
+            run_microphone(device_id, fallback, sample_rate, sink, reporter);
         });
 
         *self.stream_handle.lock() = Some(handle);
@@ -474,4 +992,12 @@ impl RecordingChannel for MicrophoneCaptureChannel {
     fn output_files(&self) -> Vec<String> {
         self.output_files.lock().clone()
     }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
 }
@@ -0,0 +1,114 @@
+//! Audio device changes
+//!
+//! A background watcher polls the input device list and broadcasts it when
+//! devices are plugged in or removed. The UI refreshes its device menu from
+//! it, and the microphone channel uses it to notice its device disappearing
+//! mid-recording. While a microphone is gone the channel either switches to
+//! the default input device or writes silence until it's back, so the audio
+//! track stays as long as the video.
+
+use super::audio::get_audio_input_devices;
+use super::traits::AudioDeviceInfo;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::broadcast;
+
+/// How often the device list is checked for changes
+pub const DEVICE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// What the microphone channel does when its device disappears
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MicrophoneFallback {
+    /// Record silence until the selected device is back
+    #[default]
+    Silence,
+    /// Switch to the default input device (silence if there is none)
+    DefaultDevice,
+}
+
+fn device_bus() -> &'static broadcast::Sender<Vec<AudioDeviceInfo>> {
+    static BUS: OnceLock<broadcast::Sender<Vec<AudioDeviceInfo>>> = OnceLock::new();
+    BUS.get_or_init(|| {
+        let (tx, _) = broadcast::channel(8);
+        let watcher_tx = tx.clone();
+        std::thread::spawn(move || watch_devices(watcher_tx));
+        tx
+    })
+}
+
+fn watch_devices(tx: broadcast::Sender<Vec<AudioDeviceInfo>>) {
+    let mut known = get_audio_input_devices();
+    loop {
+        std::thread::sleep(DEVICE_POLL_INTERVAL);
+        let devices = get_audio_input_devices();
+        if devices != known {
+            tracing::info!("Audio input devices changed: {} available", devices.len());
+            let _ = tx.send(devices.clone());
+            known = devices;
+        }
+    }
+}
+
+/// Receive the input device list whenever it changes
+///
+/// The first call starts the watcher thread.
+pub fn subscribe_audio_device_changes() -> broadcast::Receiver<Vec<AudioDeviceInfo>> {
+    device_bus().subscribe()
+}
+
+/// Map interleaved samples from one channel count to another
+///
+/// Downmixing to mono averages the channels; otherwise output channels take
+/// the input channel at the same position, wrapping around (mono to stereo
+/// duplicates).
+pub fn remap_channels(samples: &[f32], from: u16, to: u16, out: &mut Vec<f32>) {
+    let (from, to) = (from.max(1) as usize, to.max(1) as usize);
+    out.clear();
+    if from == to {
+        out.extend_from_slice(samples);
+        return;
+    }
+
+    for frame in samples.chunks_exact(from) {
+        if to == 1 {
+            out.push(frame.iter().sum::<f32>() / from as f32);
+        } else {
+            out.extend((0..to).map(|c| frame[c % from]));
+        }
+    }
+}
+
+/// Frames of silence needed for the track to reach `elapsed`
+pub fn silence_frames_due(elapsed: Duration, sample_rate: u32, frames_written: u64) -> u64 {
+    let expected = (elapsed.as_secs_f64() * sample_rate as f64) as u64;
+    expected.saturating_sub(frames_written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remap_channels() {
+        let mut out = Vec::new();
+
+        remap_channels(&[0.5, -0.5], 1, 2, &mut out);
+        assert_eq!(out, vec![0.5, 0.5, -0.5, -0.5]);
+
+        remap_channels(&[1.0, 0.0, 0.5, 0.5], 2, 1, &mut out);
+        assert_eq!(out, vec![0.5, 0.5]);
+
+        remap_channels(&[0.1, 0.2], 2, 2, &mut out);
+        assert_eq!(out, vec![0.1, 0.2]);
+    }
+
+    #[test]
+    fn test_silence_fills_the_gap() {
+        let elapsed = Duration::from_millis(1500);
+        assert_eq!(silence_frames_due(elapsed, 48_000, 48_000), 24_000);
+        // Real audio ahead of the clock needs no padding
+        assert_eq!(silence_frames_due(elapsed, 48_000, 80_000), 0);
+    }
+}
@@ -0,0 +1,459 @@
+//! Encoder backends
+//!
+//! Capture channels produce raw BGRA frames and interleaved f32le samples
+//! and hand them to the `EncoderBackend` they were given with
+//! `with_encoders`. The backend opens one `VideoEncoder` or `AudioSink` per
+//! session:
+//!
+//! - `FfmpegBackend` pipes into an FFmpeg subprocess (the default)
+//! - `RawDumpBackend` writes the raw data to `.bgra` / `.f32le` files, so
+//!   channels can be tested without FFmpeg
+//! - `NullBackend` discards everything
+//!
+//! Frame counting, first-sample marking and error reporting stay with the
+//! channel side (`VideoOutput`, `AudioEncoder`), so a new backend only has to
+//! move bytes.
+
+use crate::capture::audio::AudioFormat;
+use crate::capture::media_tools::{self, MediaTool};
+use crate::capture::video::VideoSettings;
+use crate::recorder::channel::{ChannelErrorReporter, RecordingResult};
+use parking_lot::Mutex as ParkingMutex;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process::{Child, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// A video stream to encode
+#[derive(Debug, Clone)]
+pub struct VideoSpec {
+    /// Size of the raw BGRA frames
+    pub width: u32,
+    pub height: u32,
+    pub settings: VideoSettings,
+    /// File the settings ask for; other backends derive their file from it
+    pub output_file: PathBuf,
+}
+
+/// An audio stream to encode
+#[derive(Debug, Clone)]
+pub struct AudioSpec {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub format: AudioFormat,
+    /// File the format asks for; other backends derive their file from it
+    pub output_file: PathBuf,
+}
+
+/// Destination of one session's raw video frames
+pub trait VideoEncoder: Send {
+    /// Write one BGRA frame of the spec's size
+    fn write_frame(&mut self, frame: &[u8]) -> std::io::Result<()>;
+
+    /// Flush and close, returning the files written
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<String>>;
+}
+
+/// Destination of one session's raw audio samples
+pub trait AudioSink: Send {
+    /// Write interleaved 32-bit float little-endian samples
+    fn write_samples(&mut self, data: &[u8]) -> std::io::Result<()>;
+
+    /// Flush and close, returning the files written
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<String>>;
+}
+
+/// Opens encoders for capture channels
+pub trait EncoderBackend: Send + Sync {
+    /// Check display recordings with these settings can be encoded
+    fn check_video(&self, _settings: &VideoSettings) -> RecordingResult<()> {
+        Ok(())
+    }
+
+    /// Check audio tracks in this format can be encoded
+    fn check_audio(&self, _format: &AudioFormat) -> RecordingResult<()> {
+        Ok(())
+    }
+
+    fn video_encoder(&self, spec: &VideoSpec) -> std::io::Result<Box<dyn VideoEncoder>>;
+
+    fn audio_sink(&self, spec: &AudioSpec) -> std::io::Result<Box<dyn AudioSink>>;
+}
+
+/// The default backend
+pub fn ffmpeg_backend() -> Arc<dyn EncoderBackend> {
+    Arc::new(FfmpegBackend)
+}
+
+fn create_parent_dir(path: &std::path::Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(dir) => std::fs::create_dir_all(dir),
+        None => Ok(()),
+    }
+}
+
+// =============================================================================
+// FFmpeg
+// =============================================================================
+
+/// Encodes through FFmpeg subprocesses reading raw data from stdin
+pub struct FfmpegBackend;
+
+impl EncoderBackend for FfmpegBackend {
+    fn check_video(&self, settings: &VideoSettings) -> RecordingResult<()> {
+        media_tools::toolchain().check_video(settings)
+    }
+
+    fn check_audio(&self, format: &AudioFormat) -> RecordingResult<()> {
+        media_tools::toolchain().check_audio(format)
+    }
+
+    fn video_encoder(&self, spec: &VideoSpec) -> std::io::Result<Box<dyn VideoEncoder>> {
+        create_parent_dir(&spec.output_file)?;
+
+        // Input: raw BGRA frames from stdin. stderr is only read once FFmpeg
+        // exits, so keep it to errors or a full pipe would stall the encoder
+        let process = media_tools::command(MediaTool::Ffmpeg)
+            .args([
+                "-y",
+                "-nostats",
+                "-loglevel",
+                "error",
+                "-f",
+                "rawvideo",
+                "-pixel_format",
+                "bgra",
+                "-video_size",
+                &format!("{}x{}", spec.width, spec.height),
+                "-framerate",
+                &spec.settings.fps.to_string(),
+                "-i",
+                "-",
+            ])
+            .args(spec.settings.ffmpeg_args(spec.width, spec.height))
+            .arg(&spec.output_file)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        tracing::info!(
+            "Started FFmpeg encoder: {}x{} @ {}fps ({:?}), output: {:?}",
+            spec.width,
+            spec.height,
+            spec.settings.fps,
+            spec.settings.codec,
+            spec.output_file
+        );
+
+        Ok(Box::new(FfmpegProcess {
+            process,
+            output_file: spec.output_file.clone(),
+        }))
+    }
+
+    fn audio_sink(&self, spec: &AudioSpec) -> std::io::Result<Box<dyn AudioSink>> {
+        create_parent_dir(&spec.output_file)?;
+
+        // Input: 32-bit float PCM from stdin, errors only on stderr
+        let process = media_tools::command(MediaTool::Ffmpeg)
+            .args([
+                "-y",
+                "-nostats",
+                "-loglevel",
+                "error",
+                "-f",
+                "f32le",
+                "-ar",
+                &spec.sample_rate.to_string(),
+                "-ac",
+                &spec.channels.to_string(),
+                "-i",
+                "-",
+            ])
+            .args(spec.format.ffmpeg_args())
+            .arg(&spec.output_file)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        tracing::info!(
+            "Started audio encoder: {}Hz {}ch {:?}, output: {:?}",
+            spec.sample_rate,
+            spec.channels,
+            spec.format,
+            spec.output_file
+        );
+
+        Ok(Box::new(FfmpegProcess {
+            process,
+            output_file: spec.output_file.clone(),
+        }))
+    }
+}
+
+/// An FFmpeg process fed through stdin
+struct FfmpegProcess {
+    process: Child,
+    output_file: PathBuf,
+}
+
+impl FfmpegProcess {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<()> {
+        match self.process.stdin {
+            Some(ref mut stdin) => stdin.write_all(data),
+            None => Err(std::io::ErrorKind::BrokenPipe.into()),
+        }
+    }
+
+    /// Close stdin and wait for FFmpeg to write the file
+    fn finish(mut self) -> std::io::Result<Vec<String>> {
+        drop(self.process.stdin.take());
+        let output = self.process.wait_with_output()?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            tracing::warn!("FFmpeg exited with status {}: {}", output.status, stderr);
+        }
+
+        Ok(if self.output_file.exists() {
+            vec![self.output_file.to_string_lossy().to_string()]
+        } else {
+            Vec::new()
+        })
+    }
+}
+
+impl VideoEncoder for FfmpegProcess {
+    fn write_frame(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.write(frame)
+    }
+
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<String>> {
+        FfmpegProcess::finish(*self)
+    }
+}
+
+impl AudioSink for FfmpegProcess {
+    fn write_samples(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.write(data)
+    }
+
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<String>> {
+        FfmpegProcess::finish(*self)
+    }
+}
+
+// =============================================================================
+// Raw dump and null
+// =============================================================================
+
+/// Writes frames to `<basename>.bgra` and samples to `<basename>.f32le`
+///
+/// The files hold the data exactly as the channel produced it, back to back
+/// with no header; the size and rate are in the spec (and the frames sidecar).
+pub struct RawDumpBackend;
+
+impl EncoderBackend for RawDumpBackend {
+    fn video_encoder(&self, spec: &VideoSpec) -> std::io::Result<Box<dyn VideoEncoder>> {
+        Ok(Box::new(RawDump::create(
+            spec.output_file.with_extension("bgra"),
+        )?))
+    }
+
+    fn audio_sink(&self, spec: &AudioSpec) -> std::io::Result<Box<dyn AudioSink>> {
+        Ok(Box::new(RawDump::create(
+            spec.output_file.with_extension("f32le"),
+        )?))
+    }
+}
+
+struct RawDump {
+    writer: BufWriter<File>,
+    path: PathBuf,
+}
+
+impl RawDump {
+    fn create(path: PathBuf) -> std::io::Result<Self> {
+        create_parent_dir(&path)?;
+        Ok(Self {
+            writer: BufWriter::new(File::create(&path)?),
+            path,
+        })
+    }
+
+    fn finish(mut self) -> std::io::Result<Vec<String>> {
+        self.writer.flush()?;
+        Ok(vec![self.path.to_string_lossy().to_string()])
+    }
+}
+
+impl VideoEncoder for RawDump {
+    fn write_frame(&mut self, frame: &[u8]) -> std::io::Result<()> {
+        self.writer.write_all(frame)
+    }
+
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<String>> {
+        RawDump::finish(*self)
+    }
+}
+
+impl AudioSink for RawDump {
+    fn write_samples(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.writer.write_all(data)
+    }
+
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<String>> {
+        RawDump::finish(*self)
+    }
+}
+
+/// Accepts and discards everything, writing no files
+pub struct NullBackend;
+
+impl EncoderBackend for NullBackend {
+    fn video_encoder(&self, _spec: &VideoSpec) -> std::io::Result<Box<dyn VideoEncoder>> {
+        Ok(Box::new(Discard))
+    }
+
+    fn audio_sink(&self, _spec: &AudioSpec) -> std::io::Result<Box<dyn AudioSink>> {
+        Ok(Box::new(Discard))
+    }
+}
+
+struct Discard;
+
+impl VideoEncoder for Discard {
+    fn write_frame(&mut self, _frame: &[u8]) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+impl AudioSink for Discard {
+    fn write_samples(&mut self, _data: &[u8]) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    fn finish(self: Box<Self>) -> std::io::Result<Vec<String>> {
+        Ok(Vec::new())
+    }
+}
+
+// =============================================================================
+// Channel side
+// =============================================================================
+
+/// A display channel's encoder for one session
+///
+/// Shared between the capture loop and the channel. Counts frames, and when
+/// the encoder fails reports it once and drops further frames.
+pub struct VideoOutput {
+    encoder: ParkingMutex<Option<Box<dyn VideoEncoder>>>,
+    frame_count: AtomicU64,
+    running: AtomicBool,
+    error_reporter: ChannelErrorReporter,
+}
+
+impl VideoOutput {
+    pub fn new(encoder: Box<dyn VideoEncoder>) -> Self {
+        Self {
+            encoder: ParkingMutex::new(Some(encoder)),
+            frame_count: AtomicU64::new(0),
+            running: AtomicBool::new(true),
+            error_reporter: ChannelErrorReporter::default(),
+        }
+    }
+
+    /// Report when the encoder stops accepting frames mid-recording
+    pub fn with_error_reporter(mut self, reporter: ChannelErrorReporter) -> Self {
+        self.error_reporter = reporter;
+        self
+    }
+
+    pub fn write_frame(&self, frame: &[u8]) -> bool {
+        if !self.running.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        if let Some(ref mut encoder) = *self.encoder.lock() {
+            if encoder.write_frame(frame).is_ok() {
+                self.frame_count.fetch_add(1, Ordering::Relaxed);
+                return true;
+            }
+        }
+
+        // The encoder died under us; report it once and drop further frames
+        if self.running.swap(false, Ordering::Relaxed) {
+            self.error_reporter
+                .report("Video encoder exited while recording");
+        }
+        false
+    }
+
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count.load(Ordering::Relaxed)
+    }
+
+    /// Finish encoding, returning the files written
+    pub fn finish(&self) -> std::io::Result<Vec<String>> {
+        self.running.store(false, Ordering::Relaxed);
+        let files = match self.encoder.lock().take() {
+            Some(encoder) => encoder.finish()?,
+            None => Vec::new(),
+        };
+        tracing::info!(
+            "Video encoding finished: {} frames, output: {:?}",
+            self.frame_count(),
+            files
+        );
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_dump_writes_frames_back_to_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec = VideoSpec {
+            width: 4,
+            height: 2,
+            settings: VideoSettings::default(),
+            output_file: dir.path().join("recording-0.mp4"),
+        };
+        let output = VideoOutput::new(RawDumpBackend.video_encoder(&spec).unwrap());
+        for _ in 0..3 {
+            assert!(output.write_frame(&[0u8; 4 * 2 * 4]));
+        }
+
+        let files = output.finish().unwrap();
+        assert_eq!(output.frame_count(), 3);
+        assert!(files[0].ends_with("recording-0.bgra"));
+        assert_eq!(std::fs::metadata(&files[0]).unwrap().len(), 3 * 32);
+        // Finished outputs drop further frames
+        assert!(!output.write_frame(&[0u8; 32]));
+    }
+
+    #[test]
+    fn test_null_backend_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let spec = AudioSpec {
+            sample_rate: 48000,
+            channels: 2,
+            format: AudioFormat::lossless(),
+            output_file: dir.path().join("recording-0-mic.flac"),
+        };
+        let mut sink = NullBackend.audio_sink(&spec).unwrap();
+        sink.write_samples(&[0u8; 64]).unwrap();
+        assert!(sink.finish().unwrap().is_empty());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
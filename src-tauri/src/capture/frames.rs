@@ -0,0 +1,279 @@
+//! Per-frame capture timing
+//!
+//! Screen capture doesn't deliver frames on a fixed schedule: a slow grab
+//! delays the next one, and the encoder reads raw frames at a fixed
+//! `-framerate`. To keep video time equal to wall-clock time, each captured
+//! frame is placed on the output frame grid by its capture time. Grid slots
+//! that were skipped are filled by repeating the previous frame, and frames
+//! landing in an already-filled slot are dropped. The resulting constant frame
+//! rate stream lines up with audio and input events.
+//!
+//! The capture time of every frame, and where it ended up in the output, is
+//! written to `recording-N-frames.json` (`recording-N-display-<id>-frames.json`
+//! for additional displays). Times are on the recording clock, the same time
+//! base as input events.
+
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+
+/// Capture time of a single frame
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameTimestamp {
+    /// Index of the captured frame within the session
+    pub index: u64,
+    /// Output frame showing this capture, None if it was dropped
+    pub output_frame: Option<u64>,
+    /// Recording clock time
+    pub process_time_ms: f64,
+    pub unix_time_ms: u64,
+}
+
+/// Capture timeline of one session, serialized as the frames sidecar
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrameTimeline {
+    /// Output frame rate
+    pub fps: u32,
+    /// Recording clock time of output frame 0
+    pub start_ms: f64,
+    /// Unix time of output frame 0
+    pub start_unix_time_ms: u64,
+    /// Frames in the encoded video, including repeats
+    pub output_frame_count: u64,
+    pub frames: Vec<FrameTimestamp>,
+    #[serde(skip)]
+    clock: RecordingClock,
+}
+
+impl FrameTimeline {
+    /// Start a timeline; output frame 0 is shown now
+    pub fn new(fps: u32, clock: &RecordingClock) -> Self {
+        Self::starting_at(fps, clock, clock.now_ms())
+    }
+
+    /// Start a timeline whose output frame 0 is shown at the given clock time
+    pub fn starting_at(fps: u32, clock: &RecordingClock, start_ms: f64) -> Self {
+        Self {
+            fps: fps.max(1),
+            start_ms,
+            start_unix_time_ms: clock.start_unix_ms() + start_ms as u64,
+            output_frame_count: 0,
+            frames: Vec::new(),
+            clock: clock.clone(),
+        }
+    }
+
+    /// Stamp a frame captured now
+    ///
+    /// Returns how many times the previous frame must be written before this
+    /// one to fill skipped slots, or None if the frame should be dropped.
+    pub fn stamp(&mut self) -> Option<u64> {
+        let process_time_ms = self.clock.now_ms();
+        let unix_time_ms = self.clock.start_unix_ms() + process_time_ms as u64;
+        self.stamp_at(process_time_ms, unix_time_ms)
+    }
+
+    /// Stamp a frame captured at the given clock time
+    pub fn stamp_at(&mut self, process_time_ms: f64, unix_time_ms: u64) -> Option<u64> {
+        let slot = self.slot_at(process_time_ms);
+        let placement = (slot >= self.output_frame_count).then(|| {
+            let repeats = slot - self.output_frame_count;
+            self.output_frame_count = slot + 1;
+            repeats
+        });
+
+        self.frames.push(FrameTimestamp {
+            index: self.frames.len() as u64,
+            output_frame: placement.map(|_| slot),
+            process_time_ms,
+            unix_time_ms,
+        });
+
+        placement
+    }
+
+    /// Number of times to repeat the last frame so the video lasts until now
+    pub fn pad_to_now(&mut self) -> u64 {
+        let process_time_ms = self.clock.now_ms();
+        self.pad_to(process_time_ms)
+    }
+
+    /// Number of times to repeat the last frame so the video lasts until the given clock time
+    pub fn pad_to(&mut self, process_time_ms: f64) -> u64 {
+        if self.output_frame_count == 0 {
+            return 0;
+        }
+
+        // The last slot is shown until the next one would start
+        let end = self.slot_at(process_time_ms);
+        let repeats = end.saturating_sub(self.output_frame_count);
+        self.output_frame_count += repeats;
+        repeats
+    }
+
+    /// Captured frames that didn't make it into the output
+    pub fn dropped_count(&self) -> usize {
+        self.frames
+            .iter()
+            .filter(|f| f.output_frame.is_none())
+            .count()
+    }
+
+    /// Write the timeline as `{basename}-frames.json`, e.g. `recording-0-frames.json`
+    pub fn write_sidecar(&self, output_dir: &Path, basename: &str) -> std::io::Result<String> {
+        let path = output_dir.join(format!("{}-frames.json", basename));
+        let data = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(&path, data)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Output slot whose display interval contains the given time
+    fn slot_at(&self, process_time_ms: f64) -> u64 {
+        ((process_time_ms - self.start_ms).max(0.0) * self.fps as f64 / 1000.0).floor() as u64
+    }
+}
+
+/// Feeds captured frames to an encoder on the output frame grid
+///
+/// Marks the clock time of output frame 0 as the channel's first frame once
+/// something has been written, and counts placements and encoder writes on
+/// the channel's health counters.
+pub struct FramePacer {
+    timeline: FrameTimeline,
+    last_frame: Vec<u8>,
+    channel_id: String,
+    session_index: usize,
+    health: ChannelHealth,
+}
+
+impl FramePacer {
+    /// Start pacing; output frame 0 is shown now
+    pub fn new(fps: u32, clock: &RecordingClock, channel_id: &str, session_index: usize) -> Self {
+        Self::starting_at(fps, clock, channel_id, session_index, clock.now_ms())
+    }
+
+    /// Start pacing with output frame 0 shown at the given clock time
+    pub fn starting_at(
+        fps: u32,
+        clock: &RecordingClock,
+        channel_id: &str,
+        session_index: usize,
+        start_ms: f64,
+    ) -> Self {
+        Self {
+            timeline: FrameTimeline::starting_at(fps, clock, start_ms),
+            last_frame: Vec::new(),
+            channel_id: channel_id.to_string(),
+            session_index,
+            health: ChannelHealth::default(),
+        }
+    }
+
+    /// Count frames and encoder writes on the channel's health counters
+    pub fn with_health(mut self, health: ChannelHealth) -> Self {
+        health.set_target_fps(self.timeline.fps);
+        self.health = health;
+        self
+    }
+
+    /// Write one frame, timing how long the encoder blocked
+    fn write_timed(health: &ChannelHealth, frame: &[u8], write: &mut impl FnMut(&[u8]) -> bool) {
+        let started = Instant::now();
+        let accepted = write(frame);
+        health.record_write(frame.len(), started.elapsed(), accepted);
+    }
+
+    /// Stamp a frame captured now and write it, after any repeats of the previous frame
+    pub fn push(&mut self, frame: &[u8], mut write: impl FnMut(&[u8]) -> bool) {
+        let placement = self.timeline.stamp();
+        self.health.record_frame(placement);
+        let Some(repeats) = placement else {
+            return;
+        };
+
+        // Before the first frame there is nothing older to repeat
+        let filler = if self.last_frame.is_empty() {
+            self.timeline.clock.mark_first_sample(
+                &self.channel_id,
+                self.session_index,
+                self.timeline.start_ms,
+            );
+            frame
+        } else {
+            &self.last_frame
+        };
+        for _ in 0..repeats {
+            Self::write_timed(&self.health, filler, &mut write);
+        }
+        Self::write_timed(&self.health, frame, &mut write);
+
+        self.last_frame.clear();
+        self.last_frame.extend_from_slice(frame);
+    }
+
+    /// Extend the video to the current time and return the session's timeline
+    pub fn finish(mut self, mut write: impl FnMut(&[u8]) -> bool) -> FrameTimeline {
+        let repeats = self.timeline.pad_to_now();
+        self.health.record_frames(0, 0, repeats);
+        for _ in 0..repeats {
+            Self::write_timed(&self.health, &self.last_frame, &mut write);
+        }
+        self.timeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_on_time_frames_are_not_repeated() {
+        let mut timeline = FrameTimeline::starting_at(10, &RecordingClock::new(), 0.0);
+
+        for i in 0..5 {
+            assert_eq!(timeline.stamp_at(i as f64 * 100.0 + 3.0, 0), Some(0));
+        }
+        assert_eq!(timeline.output_frame_count, 5);
+        assert_eq!(timeline.dropped_count(), 0);
+    }
+
+    #[test]
+    fn test_slow_capture_repeats_previous_frame() {
+        let mut timeline = FrameTimeline::starting_at(10, &RecordingClock::new(), 0.0);
+
+        assert_eq!(timeline.stamp_at(0.0, 0), Some(0));
+        // A 350ms stall: slots 1 and 2 show frame 0 again
+        assert_eq!(timeline.stamp_at(350.0, 0), Some(2));
+        assert_eq!(timeline.frames[1].output_frame, Some(3));
+        assert_eq!(timeline.output_frame_count, 4);
+    }
+
+    #[test]
+    fn test_early_frame_is_dropped() {
+        let mut timeline = FrameTimeline::starting_at(10, &RecordingClock::new(), 0.0);
+
+        assert_eq!(timeline.stamp_at(0.0, 0), Some(0));
+        assert_eq!(timeline.stamp_at(40.0, 0), None);
+        assert_eq!(timeline.stamp_at(110.0, 0), Some(0));
+        assert_eq!(timeline.dropped_count(), 1);
+        assert_eq!(timeline.frames[1].output_frame, None);
+    }
+
+    #[test]
+    fn test_pad_to_session_end() {
+        let mut timeline = FrameTimeline::starting_at(10, &RecordingClock::new(), 0.0);
+        assert_eq!(timeline.pad_to(1000.0), 0);
+
+        timeline.stamp_at(0.0, 0);
+        timeline.stamp_at(100.0, 0);
+
+        // One second of video is 10 frames
+        assert_eq!(timeline.pad_to(1000.0), 8);
+        assert_eq!(timeline.output_frame_count, 10);
+        assert_eq!(timeline.pad_to(1000.0), 0);
+    }
+}
@@ -1,5 +1,13 @@
-use crate::capture::input::types::{CursorInfo, MouseClick, MouseMove};
+use crate::capture::input::drags::detect_drags;
+use crate::capture::input::journal::EventJournal;
+use crate::capture::input::types::{
+    CursorInfo, KeyEvent, KeystrokeMode, MouseClick, MouseMove, MouseScroll,
+};
+use crate::capture::region::CaptureRegion;
+use crate::capture::synthetic::{self, ScriptedMousePath};
 use crate::recorder::channel::{ChannelType, RecordingChannel, RecordingError, RecordingResult};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::sources::file_basename;
 use async_trait::async_trait;
 use parking_lot::Mutex as ParkingMutex;
 use std::collections::HashMap;
@@ -14,41 +22,271 @@ use crate::capture::macos::input as platform;
 #[cfg(target_os = "windows")]
 use crate::capture::windows::input as platform;
 
+#[cfg(target_os = "linux")]
+use crate::capture::linux::input as platform;
+
+/// How often new events are appended to the session's journals
+const JOURNAL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Events positioned in display space
+trait Positioned {
+    /// Shift from display space into the cropped video's space
+    fn shift_into(&mut self, region: &CaptureRegion);
+}
+
+impl Positioned for MouseMove {
+    fn shift_into(&mut self, region: &CaptureRegion) {
+        let (x, y) = region.origin_at(self.unix_time_ms);
+        self.x -= x;
+        self.y -= y;
+    }
+}
+
+impl Positioned for MouseClick {
+    fn shift_into(&mut self, region: &CaptureRegion) {
+        let (x, y) = region.origin_at(self.unix_time_ms);
+        self.x -= x;
+        self.y -= y;
+    }
+}
+
+impl Positioned for MouseScroll {
+    fn shift_into(&mut self, region: &CaptureRegion) {
+        let (x, y) = region.origin_at(self.unix_time_ms);
+        self.x -= x;
+        self.y -= y;
+    }
+}
+
+/// Copy of an event in the video's space, for the journals
+fn shifted<T: Positioned + Clone>(event: &T, region: Option<&CaptureRegion>) -> T {
+    let mut event = event.clone();
+    if let Some(region) = region {
+        event.shift_into(region);
+    }
+    event
+}
+
+/// Where input events come from
+enum InputSource {
+    /// The platform's input tracking backend
+    Platform,
+    /// A scripted mouse path (synthetic test source)
+    Scripted(ScriptedMousePath),
+}
+
 pub struct InputTrackingChannel {
     id: String,
+    source: InputSource,
     display_id: u32,
     is_recording: Arc<AtomicBool>,
     output_dir: Option<PathBuf>,
     session_index: usize,
+    clock: RecordingClock,
     output_files: Arc<ParkingMutex<Vec<String>>>,
 
     mouse_moves: Arc<ParkingMutex<Vec<MouseMove>>>,
     mouse_clicks: Arc<ParkingMutex<Vec<MouseClick>>>,
+    mouse_scrolls: Arc<ParkingMutex<Vec<MouseScroll>>>,
+    keystrokes: Arc<ParkingMutex<Vec<KeyEvent>>>,
     cursors: Arc<ParkingMutex<HashMap<String, CursorInfo>>>,
 
     thread_handle: Arc<ParkingMutex<Option<std::thread::JoinHandle<()>>>>,
+    journal_handle: Arc<ParkingMutex<Option<std::thread::JoinHandle<Vec<EventJournal>>>>>,
     start_time: Arc<ParkingMutex<Option<Instant>>>,
+
+    /// Crop applied by the display channel (window/region capture)
+    capture_region: Option<Arc<CaptureRegion>>,
+
+    keystroke_mode: KeystrokeMode,
+
+    /// Suffix of the file names when tracking for an additional display
+    file_suffix: Option<String>,
 }
 
 impl InputTrackingChannel {
     pub fn new(display_id: u32) -> Self {
         Self {
             id: "input".to_string(),
+            source: InputSource::Platform,
             display_id,
             is_recording: Arc::new(AtomicBool::new(false)),
             output_dir: None,
             session_index: 0,
+            clock: RecordingClock::default(),
             output_files: Arc::new(ParkingMutex::new(Vec::new())),
             mouse_moves: Arc::new(ParkingMutex::new(Vec::new())),
             mouse_clicks: Arc::new(ParkingMutex::new(Vec::new())),
+            mouse_scrolls: Arc::new(ParkingMutex::new(Vec::new())),
+            keystrokes: Arc::new(ParkingMutex::new(Vec::new())),
             cursors: Arc::new(ParkingMutex::new(HashMap::new())),
             thread_handle: Arc::new(ParkingMutex::new(None)),
+            journal_handle: Arc::new(ParkingMutex::new(None)),
             start_time: Arc::new(ParkingMutex::new(None)),
+            capture_region: None,
+            keystroke_mode: KeystrokeMode::default(),
+            file_suffix: None,
+        }
+    }
+
+    /// Replay a scripted mouse path instead of tracking real input
+    pub fn scripted(path: ScriptedMousePath) -> Self {
+        Self {
+            source: InputSource::Scripted(path),
+            ..Self::new(0)
+        }
+    }
+
+    /// Express coordinates relative to the crop of a window/region capture
+    pub fn with_capture_region(mut self, capture_region: Arc<CaptureRegion>) -> Self {
+        self.capture_region = Some(capture_region);
+        self
+    }
+
+    /// Choose which keystrokes are written to the recording
+    pub fn with_keystroke_mode(mut self, keystroke_mode: KeystrokeMode) -> Self {
+        self.keystroke_mode = keystroke_mode;
+        self
+    }
+
+    /// Shift recorded coordinates from display space into the cropped video's space
+    fn apply_capture_region(&self) {
+        let Some(ref region) = self.capture_region else {
+            return;
+        };
+
+        for event in self.mouse_moves.lock().iter_mut() {
+            event.shift_into(region);
+        }
+
+        for event in self.mouse_clicks.lock().iter_mut() {
+            event.shift_into(region);
+        }
+
+        for event in self.mouse_scrolls.lock().iter_mut() {
+            event.shift_into(region);
+        }
+    }
+
+    /// Append new events to the session's journals until recording stops
+    ///
+    /// Returns the journals when done so they can be removed once the final
+    /// files are written.
+    fn start_journal(
+        &self,
+        output_dir: &Path,
+    ) -> RecordingResult<std::thread::JoinHandle<Vec<EventJournal>>> {
+        let base = self.session_basename();
+        let journal = |suffix: &str| {
+            EventJournal::create(&output_dir.join(format!("{}-{}.json", base, suffix)))
+        };
+
+        let mut moves = journal("mouse-moves")?;
+        let mut clicks = journal("mouse-clicks")?;
+        let mut scrolls = journal("mouse-scrolls")?;
+        let mut keys = match self.keystroke_mode {
+            KeystrokeMode::Off => None,
+            _ => Some(journal("keystrokes")?),
+        };
+        let mask_keys = self.keystroke_mode == KeystrokeMode::Privacy;
+        let cursors_json_path = output_dir.join(format!("{}-cursors.json", base));
+
+        let is_recording = self.is_recording.clone();
+        let mouse_moves = self.mouse_moves.clone();
+        let mouse_clicks = self.mouse_clicks.clone();
+        let mouse_scrolls = self.mouse_scrolls.clone();
+        let keystrokes = self.keystrokes.clone();
+        let cursors = self.cursors.clone();
+        let region = self.capture_region.clone();
+
+        let handle = std::thread::spawn(move || {
+            let mut cursor_count = 0;
+
+            loop {
+                let running = is_recording.load(Ordering::SeqCst);
+
+                let result = (|| -> std::io::Result<()> {
+                    let region = region.as_deref();
+                    moves.encode_new(&mouse_moves.lock(), |e| shifted(e, region))?;
+                    clicks.encode_new(&mouse_clicks.lock(), |e| shifted(e, region))?;
+                    scrolls.encode_new(&mouse_scrolls.lock(), |e| shifted(e, region))?;
+                    if let Some(ref mut keys) = keys {
+                        keys.encode_new(&keystrokes.lock(), |e| {
+                            if mask_keys {
+                                e.clone().masked()
+                            } else {
+                                e.clone()
+                            }
+                        })?;
+                    }
+
+                    moves.flush()?;
+                    clicks.flush()?;
+                    scrolls.flush()?;
+                    if let Some(ref mut keys) = keys {
+                        keys.flush()?;
+                    }
+
+                    // Cursor images are already on disk; keep their index current
+                    let snapshot = {
+                        let cursors = cursors.lock();
+                        (cursors.len() != cursor_count).then(|| {
+                            cursor_count = cursors.len();
+                            serde_json::to_vec_pretty(&*cursors)
+                        })
+                    };
+                    if let Some(data) = snapshot {
+                        std::fs::write(&cursors_json_path, data.map_err(std::io::Error::other)?)?;
+                    }
+                    Ok(())
+                })();
+
+                if let Err(e) = result {
+                    tracing::warn!("Failed to append to input journal: {}", e);
+                }
+
+                if !running {
+                    break;
+                }
+                let next = Instant::now() + JOURNAL_INTERVAL;
+                while Instant::now() < next && is_recording.load(Ordering::SeqCst) {
+                    std::thread::sleep(Duration::from_millis(50));
+                }
+            }
+
+            let mut journals = vec![moves, clicks, scrolls];
+            journals.extend(keys);
+            journals
+        });
+
+        Ok(handle)
+    }
+
+    /// Write `recording-N-{suffix}` files in an additional display's coordinates
+    ///
+    /// The suffix is appended to the channel ID as well, since every display
+    /// gets its own input channel.
+    pub fn with_file_suffix(mut self, suffix: String) -> Self {
+        self.id = format!("{}-{}", self.id, suffix);
+        self.file_suffix = Some(suffix);
+        self
+    }
+
+    /// Mark the earliest recorded event as this channel's first sample
+    fn mark_first_event(&self) {
+        let first_move = self.mouse_moves.lock().first().map(|e| e.process_time_ms);
+        let first_click = self.mouse_clicks.lock().first().map(|e| e.process_time_ms);
+        let first = match (first_move, first_click) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        if let Some(clock_ms) = first {
+            self.clock.mark_first_sample(&self.id, self.session_index, clock_ms);
         }
     }
 
     fn session_basename(&self) -> String {
-        format!("recording-{}", self.session_index)
+        file_basename(self.session_index, self.file_suffix.as_deref())
     }
 
     fn now_unix_ms() -> u64 {
@@ -76,21 +314,46 @@ impl InputTrackingChannel {
 
         let mouse_moves_path = output_dir.join(format!("{}-mouse-moves.json", base));
         let mouse_clicks_path = output_dir.join(format!("{}-mouse-clicks.json", base));
+        let mouse_scrolls_path = output_dir.join(format!("{}-mouse-scrolls.json", base));
+        let mouse_drags_path = output_dir.join(format!("{}-mouse-drags.json", base));
         let cursors_json_path = output_dir.join(format!("{}-cursors.json", base));
+        let keystrokes_path = output_dir.join(format!("{}-keystrokes.json", base));
         let cursors_dir = output_dir.join(format!("{}-cursors", base));
 
         std::fs::create_dir_all(&cursors_dir)?;
 
+        self.apply_capture_region();
+        self.mark_first_event();
+
         // Write event JSON files
         Self::write_json(&mouse_moves_path, &*self.mouse_moves.lock())?;
         Self::write_json(&mouse_clicks_path, &*self.mouse_clicks.lock())?;
+        Self::write_json(&mouse_scrolls_path, &*self.mouse_scrolls.lock())?;
+        let mouse_drags = detect_drags(&self.mouse_moves.lock(), &self.mouse_clicks.lock());
+        Self::write_json(&mouse_drags_path, &mouse_drags)?;
         Self::write_json(&cursors_json_path, &*self.cursors.lock())?;
 
+        // Typed text only reaches the disk in full mode
+        match self.keystroke_mode {
+            KeystrokeMode::Off => {}
+            KeystrokeMode::Privacy => {
+                let masked: Vec<KeyEvent> =
+                    self.keystrokes.lock().iter().cloned().map(KeyEvent::masked).collect();
+                Self::write_json(&keystrokes_path, &masked)?;
+            }
+            KeystrokeMode::Full => Self::write_json(&keystrokes_path, &*self.keystrokes.lock())?,
+        }
+
         // Cursor PNGs are saved during capture (platform impl)
 
         self.output_files.lock().push(mouse_moves_path.to_string_lossy().to_string());
         self.output_files.lock().push(mouse_clicks_path.to_string_lossy().to_string());
+        self.output_files.lock().push(mouse_scrolls_path.to_string_lossy().to_string());
+        self.output_files.lock().push(mouse_drags_path.to_string_lossy().to_string());
         self.output_files.lock().push(cursors_json_path.to_string_lossy().to_string());
+        if self.keystroke_mode != KeystrokeMode::Off {
+            self.output_files.lock().push(keystrokes_path.to_string_lossy().to_string());
+        }
 
         Ok(())
     }
@@ -106,9 +369,23 @@ impl RecordingChannel for InputTrackingChannel {
         ChannelType::Input
     }
 
-    async fn initialize(&mut self, output_dir: &Path, session_index: usize) -> RecordingResult<()> {
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
         self.output_dir = Some(output_dir.to_path_buf());
         self.session_index = session_index;
+        self.clock = clock.clone();
+        // Files of earlier sessions stay listed until the next recording
+        self.output_files.lock().clear();
+
+        // Fail before recording starts rather than lose clicks and drags
+        #[cfg(target_os = "linux")]
+        if matches!(self.source, InputSource::Platform) {
+            platform::check_input_support()?;
+        }
 
         tracing::info!(
             "Input tracking channel initialized (display_id={}, session={})",
@@ -130,14 +407,16 @@ impl RecordingChannel for InputTrackingChannel {
         // Clear previous buffers
         self.mouse_moves.lock().clear();
         self.mouse_clicks.lock().clear();
+        self.mouse_scrolls.lock().clear();
+        self.keystrokes.lock().clear();
         self.cursors.lock().clear();
-        self.output_files.lock().clear();
 
         let base = self.session_basename();
         let cursors_dir = output_dir.join(format!("{}-cursors", base));
         std::fs::create_dir_all(&cursors_dir)?;
 
-        let start_time = Instant::now();
+        // Event times are measured on the shared recording clock
+        let start_time = self.clock.start_instant();
         *self.start_time.lock() = Some(start_time);
 
         let is_recording = self.is_recording.clone();
@@ -145,22 +424,51 @@ impl RecordingChannel for InputTrackingChannel {
 
         let mouse_moves = self.mouse_moves.clone();
         let mouse_clicks = self.mouse_clicks.clone();
+        let mouse_scrolls = self.mouse_scrolls.clone();
+        let keystrokes = self.keystrokes.clone();
         let cursors = self.cursors.clone();
 
-        let handle = platform::start_input_tracking(
-            is_recording.clone(),
-            mouse_moves,
-            mouse_clicks,
-            cursors,
-            cursors_dir,
-            start_time,
-            Duration::from_micros(8_333),
-            Self::now_unix_ms,
-            self.display_id,
-        )?;
+        let handle = match self.source {
+            InputSource::Platform => platform::start_input_tracking(
+                is_recording.clone(),
+                mouse_moves,
+                mouse_clicks,
+                mouse_scrolls,
+                keystrokes,
+                self.keystroke_mode != KeystrokeMode::Off,
+                cursors,
+                cursors_dir,
+                start_time,
+                Duration::from_micros(8_333),
+                Self::now_unix_ms,
+                self.display_id,
+            ),
+            InputSource::Scripted(ref path) => synthetic::start_scripted_input(
+                is_recording.clone(),
+                mouse_moves,
+                mouse_clicks,
+                start_time,
+                Duration::from_micros(8_333),
+                Self::now_unix_ms,
+                path.clone(),
+            ),
+        };
+
+        let handle = match handle {
+            Ok(handle) => handle,
+            Err(e) => {
+                is_recording.store(false, Ordering::SeqCst);
+                return Err(e);
+            }
+        };
 
         *self.thread_handle.lock() = Some(handle);
 
+        match self.start_journal(&output_dir) {
+            Ok(handle) => *self.journal_handle.lock() = Some(handle),
+            Err(e) => tracing::warn!("Input events will only be written at stop: {}", e),
+        }
+
         tracing::info!("Input tracking started");
         Ok(())
     }
@@ -175,13 +483,28 @@ impl RecordingChannel for InputTrackingChannel {
         if let Some(handle) = self.thread_handle.lock().take() {
             let _ = handle.join();
         }
+        let journals = self
+            .journal_handle
+            .lock()
+            .take()
+            .and_then(|handle| handle.join().ok())
+            .unwrap_or_default();
 
         self.flush_to_disk()?;
 
+        // The final files now hold everything the journals did
+        for journal in journals {
+            if let Err(e) = journal.remove() {
+                tracing::warn!("Failed to remove input journal: {}", e);
+            }
+        }
+
         tracing::info!(
-            "Input tracking stopped (moves={}, clicks={}, cursors={})",
+            "Input tracking stopped (moves={}, clicks={}, scrolls={}, keys={}, cursors={})",
             self.mouse_moves.lock().len(),
             self.mouse_clicks.lock().len(),
+            self.mouse_scrolls.lock().len(),
+            self.keystrokes.lock().len(),
             self.cursors.lock().len()
         );
         Ok(())
@@ -0,0 +1,184 @@
+//! Drag detection
+//!
+//! Drags are derived from the recorded clicks and moves rather than tracked
+//! by each platform backend: a drag begins once the pointer travels more than
+//! `DRAG_THRESHOLD_PX` from where a button was pressed, updates on every move
+//! while the button is held, and ends when it is released.
+
+use crate::capture::input::types::{MouseClick, MouseDrag, MouseMove};
+
+/// Pointer travel needed before a press turns into a drag
+pub const DRAG_THRESHOLD_PX: f64 = 4.0;
+
+/// Button held down, waiting to see whether it becomes a drag
+struct Press {
+    button: String,
+    x: f64,
+    y: f64,
+    dragging: bool,
+}
+
+/// Derive drag begin/update/end events from moves and clicks
+///
+/// Both inputs must be in recording order. A drag still in progress when the
+/// session ends has no "end" event.
+pub fn detect_drags(moves: &[MouseMove], clicks: &[MouseClick]) -> Vec<MouseDrag> {
+    let mut drags = Vec::new();
+    let mut press: Option<Press> = None;
+    let mut moves = moves.iter().peekable();
+
+    let drag =
+        |press: &Press, x, y, phase: &str, modifiers: &[String], t: f64, unix: u64| MouseDrag {
+            x,
+            y,
+            start_x: press.x,
+            start_y: press.y,
+            button: press.button.clone(),
+            phase: phase.to_string(),
+            active_modifiers: modifiers.to_vec(),
+            process_time_ms: t,
+            unix_time_ms: unix,
+        };
+
+    for click in clicks.iter().map(Some).chain(std::iter::once(None)) {
+        // Moves up to (and including) this click's time happened before it
+        while let Some(mv) = moves
+            .next_if(|mv| click.is_none_or(|click| mv.process_time_ms <= click.process_time_ms))
+        {
+            let Some(ref mut p) = press else {
+                continue;
+            };
+
+            let phase = if p.dragging {
+                "update"
+            } else if (mv.x - p.x).hypot(mv.y - p.y) > DRAG_THRESHOLD_PX {
+                p.dragging = true;
+                "begin"
+            } else {
+                continue;
+            };
+
+            drags.push(drag(
+                p,
+                mv.x,
+                mv.y,
+                phase,
+                &mv.active_modifiers,
+                mv.process_time_ms,
+                mv.unix_time_ms,
+            ));
+        }
+
+        let Some(click) = click else {
+            break;
+        };
+
+        match (click.event_type.as_str(), &press) {
+            // Other buttons pressed during a drag don't start a new one
+            ("down", None) => {
+                press = Some(Press {
+                    button: click.button.clone(),
+                    x: click.x,
+                    y: click.y,
+                    dragging: false,
+                });
+            }
+            ("up", Some(p)) if p.button == click.button => {
+                if p.dragging {
+                    drags.push(drag(
+                        p,
+                        click.x,
+                        click.y,
+                        "end",
+                        &click.active_modifiers,
+                        click.process_time_ms,
+                        click.unix_time_ms,
+                    ));
+                }
+                press = None;
+            }
+            _ => {}
+        }
+    }
+
+    drags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mv(t: f64, x: f64, y: f64) -> MouseMove {
+        MouseMove {
+            x,
+            y,
+            cursor_id: "arrow".to_string(),
+            active_modifiers: Vec::new(),
+            process_time_ms: t,
+            unix_time_ms: t as u64,
+        }
+    }
+
+    fn click(t: f64, x: f64, y: f64, button: &str, event_type: &str) -> MouseClick {
+        MouseClick {
+            x,
+            y,
+            button: button.to_string(),
+            event_type: event_type.to_string(),
+            click_count: 1,
+            active_modifiers: Vec::new(),
+            process_time_ms: t,
+            unix_time_ms: t as u64,
+        }
+    }
+
+    fn phases(drags: &[MouseDrag]) -> Vec<&str> {
+        drags.iter().map(|d| d.phase.as_str()).collect()
+    }
+
+    #[test]
+    fn test_drag_begin_update_end() {
+        let moves = [
+            mv(0.0, 10.0, 10.0),
+            mv(20.0, 12.0, 10.0),
+            mv(30.0, 30.0, 10.0),
+            mv(40.0, 50.0, 20.0),
+            mv(60.0, 60.0, 20.0),
+        ];
+        let clicks = [
+            click(10.0, 10.0, 10.0, "left", "down"),
+            click(50.0, 50.0, 20.0, "left", "up"),
+        ];
+
+        let drags = detect_drags(&moves, &clicks);
+        assert_eq!(phases(&drags), vec!["begin", "update", "end"]);
+        assert_eq!((drags[0].x, drags[0].start_x), (30.0, 10.0));
+        assert_eq!(drags[2].process_time_ms, 50.0);
+        assert!(drags.iter().all(|d| d.button == "left"));
+    }
+
+    #[test]
+    fn test_click_without_travel_is_not_a_drag() {
+        let moves = [mv(0.0, 10.0, 10.0), mv(20.0, 12.0, 11.0)];
+        let clicks = [
+            click(10.0, 10.0, 10.0, "left", "down"),
+            click(30.0, 12.0, 11.0, "left", "up"),
+        ];
+
+        assert!(detect_drags(&moves, &clicks).is_empty());
+    }
+
+    #[test]
+    fn test_other_button_does_not_end_drag() {
+        let moves = [mv(20.0, 40.0, 0.0), mv(40.0, 80.0, 0.0)];
+        let clicks = [
+            click(10.0, 0.0, 0.0, "left", "down"),
+            click(30.0, 40.0, 0.0, "right", "down"),
+            click(35.0, 40.0, 0.0, "right", "up"),
+        ];
+
+        // Still dragging when the session ends, so there is no end event
+        let drags = detect_drags(&moves, &clicks);
+        assert_eq!(phases(&drags), vec!["begin", "update"]);
+    }
+}
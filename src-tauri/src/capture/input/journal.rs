@@ -0,0 +1,96 @@
+//! Incremental input event journals
+//!
+//! The final event files are only written when a session stops. While
+//! recording, new events are also appended to `recording-N-<kind>.jsonl`
+//! journals (one JSON object per line), so a crash loses at most the last
+//! flush interval. The journals are deleted once the final files exist;
+//! `recover_recording` converts any that are left behind.
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// File extension of event journals
+pub const JOURNAL_EXTENSION: &str = "jsonl";
+
+/// Append-only journal of one event kind
+pub struct EventJournal {
+    path: PathBuf,
+    file: File,
+    /// Events of the source buffer already encoded
+    written: usize,
+    /// Encoded lines not yet written
+    pending: Vec<u8>,
+}
+
+impl EventJournal {
+    /// Create (or truncate) the journal for the given final JSON file
+    pub fn create(json_path: &Path) -> std::io::Result<Self> {
+        let path = json_path.with_extension(JOURNAL_EXTENSION);
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)?;
+        Ok(Self {
+            path,
+            file,
+            written: 0,
+            pending: Vec::new(),
+        })
+    }
+
+    /// Queue the events added to `events` since the last call
+    ///
+    /// `events` is the whole session buffer, which only grows while recording.
+    /// Each new event goes through `map` before being encoded. Nothing reaches
+    /// the file until `flush`, so callers can hold the buffer's lock for this
+    /// part only.
+    pub fn encode_new<T, U: Serialize>(
+        &mut self,
+        events: &[T],
+        mut map: impl FnMut(&T) -> U,
+    ) -> std::io::Result<()> {
+        for event in events.iter().skip(self.written) {
+            serde_json::to_writer(&mut self.pending, &map(event)).map_err(std::io::Error::other)?;
+            self.pending.push(b'\n');
+        }
+        self.written = self.written.max(events.len());
+        Ok(())
+    }
+
+    /// Write queued events and sync them to disk
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        self.file.write_all(&self.pending)?;
+        self.file.sync_data()?;
+        self.pending.clear();
+        Ok(())
+    }
+
+    /// Delete the journal once the final file has been written
+    pub fn remove(self) -> std::io::Result<()> {
+        drop(self.file);
+        std::fs::remove_file(&self.path)
+    }
+}
+
+/// Read the events of a journal
+///
+/// A line cut off by a crash is skipped, as is anything after it.
+pub fn read_journal<T: DeserializeOwned>(path: &Path) -> std::io::Result<Vec<T>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut events = Vec::new();
+    for line in reader.split(b'\n') {
+        let line = line?;
+        match serde_json::from_slice(&line) {
+            Ok(event) => events.push(event),
+            Err(_) => break,
+        }
+    }
+    Ok(events)
+}
@@ -1,11 +1,15 @@
-//! Input tracking (mouse, cursor) capture
+//! Input tracking (mouse, keyboard, cursor) capture
 //!
 //! Implements a `RecordingChannel` that records high-frequency mouse movement,
-//! mouse clicks, and cursor metadata for later processing (cursor smoothing,
-//! auto-zoom, etc.).
+//! mouse clicks, scrolling, drags, keystrokes, and cursor metadata for later
+//! processing (cursor smoothing, auto-zoom, shortcut overlays, etc.).
 
 pub mod channel;
+pub mod drags;
+pub mod journal;
 pub mod types;
 
 pub use channel::InputTrackingChannel;
-pub use types::{CursorInfo, MouseClick, MouseMove};
+pub use types::{
+    CursorInfo, KeyEvent, KeystrokeMode, MouseClick, MouseDrag, MouseMove, MouseScroll,
+};
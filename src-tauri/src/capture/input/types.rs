@@ -24,6 +24,41 @@ pub struct MouseClick {
     pub unix_time_ms: u64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MouseScroll {
+    pub x: f64,
+    pub y: f64,
+    /// Horizontal delta, positive when scrolling right
+    pub delta_x: f64,
+    /// Vertical delta, positive when scrolling down (DOM `WheelEvent` convention)
+    pub delta_y: f64,
+    /// "pixel" for continuous (trackpad) scrolling, "line" for wheel notches
+    pub delta_mode: String,
+    /// Gesture phase for continuous scrolling
+    /// ("mayBegin", "began", "changed", "ended", "cancelled", "momentum")
+    pub phase: Option<String>,
+    pub active_modifiers: Vec<String>,
+    pub process_time_ms: f64,
+    pub unix_time_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MouseDrag {
+    pub x: f64,
+    pub y: f64,
+    /// Where the button was pressed
+    pub start_x: f64,
+    pub start_y: f64,
+    pub button: String,
+    /// "begin", "update" or "end"
+    pub phase: String,
+    pub active_modifiers: Vec<String>,
+    pub process_time_ms: f64,
+    pub unix_time_ms: u64,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CursorInfo {
@@ -34,3 +69,102 @@ pub struct CursorInfo {
     pub width: u32,
     pub height: u32,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyEvent {
+    /// Platform key code (X11 keycode, macOS virtual key code)
+    pub key_code: u32,
+    /// Key name, following the DOM `KeyboardEvent.key` convention
+    /// ("a", "Enter", "ArrowUp", "Meta", ...)
+    pub key: String,
+    /// Text produced by the key, if it is printable
+    pub character: Option<String>,
+    pub event_type: String,
+    pub active_modifiers: Vec<String>,
+    pub process_time_ms: f64,
+    pub unix_time_ms: u64,
+}
+
+/// Placeholder for masked keys in privacy mode
+pub const MASKED_KEY: &str = "•";
+
+/// Modifiers that turn a key press into a shortcut rather than typing
+const SHORTCUT_MODIFIERS: [&str; 2] = ["control", "meta"];
+
+impl KeyEvent {
+    /// Whether the key is a modifier on its own
+    pub fn is_modifier(&self) -> bool {
+        matches!(
+            self.key.as_str(),
+            "Shift" | "Control" | "Alt" | "Meta" | "CapsLock"
+        )
+    }
+
+    /// Whether the key was pressed as part of a shortcut (e.g. ⌘⇧P)
+    pub fn is_shortcut(&self) -> bool {
+        self.active_modifiers
+            .iter()
+            .any(|m| SHORTCUT_MODIFIERS.contains(&m.as_str()))
+    }
+
+    /// Hide what was typed, keeping modifiers, shortcuts and non-printable keys
+    pub fn masked(mut self) -> Self {
+        if self.character.is_some() && !self.is_shortcut() && !self.is_modifier() {
+            self.key_code = 0;
+            self.key = MASKED_KEY.to_string();
+            self.character = None;
+        }
+        self
+    }
+}
+
+/// Which keystrokes are written to the recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum KeystrokeMode {
+    /// Keyboard input is not recorded
+    Off,
+    /// Printable characters are masked; shortcuts and modifiers are kept
+    #[default]
+    Privacy,
+    /// Every key is recorded as typed
+    Full,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: &str, character: Option<&str>, modifiers: &[&str]) -> KeyEvent {
+        KeyEvent {
+            key_code: 42,
+            key: key.to_string(),
+            character: character.map(str::to_string),
+            event_type: "down".to_string(),
+            active_modifiers: modifiers.iter().map(|m| m.to_string()).collect(),
+            process_time_ms: 0.0,
+            unix_time_ms: 0,
+        }
+    }
+
+    #[test]
+    fn test_privacy_masks_typing() {
+        let typed = key("p", Some("p"), &["shift"]).masked();
+        assert_eq!(typed.key, MASKED_KEY);
+        assert_eq!(typed.character, None);
+        assert_eq!(typed.key_code, 0);
+        // Modifiers are kept so overlays can still show ⇧
+        assert_eq!(typed.active_modifiers, vec!["shift"]);
+    }
+
+    #[test]
+    fn test_privacy_keeps_shortcuts_and_named_keys() {
+        let shortcut = key("p", Some("p"), &["meta", "shift"]).masked();
+        assert_eq!(shortcut.key, "p");
+        assert_eq!(shortcut.key_code, 42);
+
+        assert_eq!(key("Enter", None, &[]).masked().key, "Enter");
+        assert_eq!(key("Shift", None, &[]).masked().key, "Shift");
+    }
+}
@@ -0,0 +1,148 @@
+//! Audio level metering
+//!
+//! Audio channels measure the RMS and peak of each audio channel over short
+//! windows (about 20 per second) as samples arrive, and publish them on a
+//! process-wide broadcast. Anyone interested, such as the Tauri event relay,
+//! calls `subscribe_audio_levels`. `AudioMonitor` publishes on the same bus
+//! to meter a device before recording starts.
+
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+/// Levels published per second
+pub const LEVELS_PER_SECOND: u32 = 20;
+
+/// Levels of one source over the last metering window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioLevels {
+    /// Recording channel ID ("microphone", "system-audio") or "monitor"
+    pub source_id: String,
+    /// Linear RMS of each audio channel, 0.0 to 1.0
+    pub rms: Vec<f32>,
+    /// Linear peak of each audio channel, 0.0 to 1.0
+    pub peak: Vec<f32>,
+}
+
+fn level_bus() -> &'static broadcast::Sender<AudioLevels> {
+    static BUS: OnceLock<broadcast::Sender<AudioLevels>> = OnceLock::new();
+    BUS.get_or_init(|| broadcast::channel(64).0)
+}
+
+/// Receive the levels of every metered source
+pub fn subscribe_audio_levels() -> broadcast::Receiver<AudioLevels> {
+    level_bus().subscribe()
+}
+
+/// Accumulates interleaved samples into per-channel RMS and peak
+pub struct LevelMeter {
+    source_id: String,
+    channels: usize,
+    window_frames: usize,
+    frames: usize,
+    sum_squares: Vec<f64>,
+    peaks: Vec<f32>,
+}
+
+impl LevelMeter {
+    pub fn new(source_id: &str, channels: u16, sample_rate: u32) -> Self {
+        let channels = channels.max(1) as usize;
+        Self {
+            source_id: source_id.to_string(),
+            channels,
+            window_frames: (sample_rate / LEVELS_PER_SECOND).max(1) as usize,
+            frames: 0,
+            sum_squares: vec![0.0; channels],
+            peaks: vec![0.0; channels],
+        }
+    }
+
+    /// Meter interleaved samples, publishing each completed window
+    pub fn push(&mut self, samples: &[f32]) {
+        self.feed(samples.iter().copied(), |levels| {
+            // Nobody listening is fine
+            let _ = level_bus().send(levels);
+        });
+    }
+
+    /// Meter interleaved 32-bit float little-endian PCM
+    pub fn push_f32le(&mut self, data: &[u8]) {
+        let samples = data
+            .chunks_exact(4)
+            .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+        self.feed(samples, |levels| {
+            let _ = level_bus().send(levels);
+        });
+    }
+
+    fn feed(&mut self, samples: impl Iterator<Item = f32>, mut emit: impl FnMut(AudioLevels)) {
+        for (i, sample) in samples.enumerate() {
+            let channel = i % self.channels;
+            self.sum_squares[channel] += (sample as f64) * (sample as f64);
+            self.peaks[channel] = self.peaks[channel].max(sample.abs());
+
+            if channel == self.channels - 1 {
+                self.frames += 1;
+                if self.frames == self.window_frames {
+                    emit(self.take());
+                }
+            }
+        }
+    }
+
+    /// Levels of the current window, starting a new one
+    fn take(&mut self) -> AudioLevels {
+        let frames = self.frames.max(1) as f64;
+        let levels = AudioLevels {
+            source_id: self.source_id.clone(),
+            rms: self
+                .sum_squares
+                .iter()
+                .map(|sum| ((sum / frames).sqrt() as f32).min(1.0))
+                .collect(),
+            peak: self.peaks.iter().map(|p| p.min(1.0)).collect(),
+        };
+
+        self.frames = 0;
+        self.sum_squares.iter_mut().for_each(|s| *s = 0.0);
+        self.peaks.iter_mut().for_each(|p| *p = 0.0);
+        levels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels_per_channel() {
+        // 20 frames per window; left is a full-scale square wave, right is silent
+        let mut meter = LevelMeter::new("microphone", 2, 400);
+        let samples: Vec<f32> = (0..20)
+            .flat_map(|i| [if i % 2 == 0 { 1.0 } else { -1.0 }, 0.0])
+            .collect();
+
+        let mut emitted = Vec::new();
+        meter.feed(samples.into_iter(), |levels| emitted.push(levels));
+
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(emitted[0].rms, vec![1.0, 0.0]);
+        assert_eq!(emitted[0].peak, vec![1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_windows_span_buffers() {
+        let mut meter = LevelMeter::new("monitor", 1, 400);
+        let mut emitted = Vec::new();
+
+        // 15 + 15 frames: one window completes in the second buffer
+        meter.feed([0.5; 15].into_iter(), |levels| emitted.push(levels));
+        assert!(emitted.is_empty());
+        meter.feed([0.25; 15].into_iter(), |levels| emitted.push(levels));
+
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(emitted[0].peak, vec![0.5]);
+        assert_eq!(meter.frames, 10);
+    }
+}
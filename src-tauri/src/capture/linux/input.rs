@@ -0,0 +1,644 @@
+//! Linux input tracking using XInput2 and XFixes
+//!
+//! Raw pointer, wheel and key events are read from every master device on
+//! the root window, so input is recorded whichever client has focus, and
+//! XFixes reports cursor shape changes. Raw events need XInput 2.1 or later:
+//! a 2.0 client stops receiving them while any device is grabbed, which
+//! includes the implicit grab of every button press, so drags would lose
+//! their motion and release.
+//!
+//! Only an X server is required; synthetic XTEST input under Xvfb works too.
+
+use crate::capture::input::types::{CursorInfo, KeyEvent, MouseClick, MouseMove, MouseScroll};
+use crate::capture::linux::screen::query_monitors;
+use crate::recorder::channel::{RecordingError, RecordingResult};
+use parking_lot::Mutex as ParkingMutex;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use x11rb::connection::Connection;
+use x11rb::protocol::xfixes::{self, ConnectionExt as _};
+use x11rb::protocol::xinput::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{ConnectionExt as _, KeyButMask, Keysym, Window};
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+
+/// Maximum delay between presses to count as a multi-click
+const MULTI_CLICK_INTERVAL_MS: f64 = 500.0;
+
+/// Maximum pointer travel between presses to count as a multi-click
+const MULTI_CLICK_DISTANCE_PX: f64 = 4.0;
+
+/// Start input tracking thread (Linux / X11)
+///
+/// Listens to XInput2 raw motion/button events on the root window, so events
+/// are received regardless of which client has focus, and to XFixes cursor
+/// notifications for cursor shape changes. Raw events don't carry a position
+/// or modifier state, so the pointer is queried whenever one arrives. Motion
+/// is sampled at most once per `poll_interval`.
+///
+/// Wheel "buttons" 4-7 are recorded as line-based scroll events.
+///
+/// When `track_keys` is set, raw key presses/releases are recorded as well.
+/// Key codes are mapped to keysyms with the keyboard mapping fetched at start;
+/// auto-repeated presses are skipped.
+///
+/// Works with synthetic XTEST input (e.g. xdotool under Xvfb).
+pub fn start_input_tracking(
+    is_recording: Arc<AtomicBool>,
+    mouse_moves: Arc<ParkingMutex<Vec<MouseMove>>>,
+    mouse_clicks: Arc<ParkingMutex<Vec<MouseClick>>>,
+    mouse_scrolls: Arc<ParkingMutex<Vec<MouseScroll>>>,
+    keystrokes: Arc<ParkingMutex<Vec<KeyEvent>>>,
+    track_keys: bool,
+    cursors: Arc<ParkingMutex<HashMap<String, CursorInfo>>>,
+    cursors_dir: PathBuf,
+    start_time: Instant,
+    poll_interval: Duration,
+    unix_ms_fn: fn() -> u64,
+    display_id: u32,
+) -> RecordingResult<std::thread::JoinHandle<()>> {
+    // Ensure cursor directory exists
+    std::fs::create_dir_all(&cursors_dir)?;
+
+    let (conn, screen_num) = x11rb::connect(None).map_err(|e| {
+        RecordingError::PlatformError(format!(
+            "Failed to connect to X server (is DISPLAY set?): {}",
+            e
+        ))
+    })?;
+    let root = conn.setup().roots[screen_num].root;
+
+    // Pointer positions are reported in root window coordinates; make them
+    // relative to the recorded monitor. X11 coordinates are already physical
+    // pixels, so no scaling is needed.
+    let monitor = query_monitors(&conn, screen_num)
+        .into_iter()
+        .nth(display_id as usize)
+        .ok_or_else(|| {
+            RecordingError::DeviceNotFound(format!("Display {} not found", display_id))
+        })?;
+    let display_origin_x = monitor.x as f64;
+    let display_origin_y = monitor.y as f64;
+
+    tracing::info!(
+        "Input tracking coordinate transform: display_id={}, origin=({}, {})",
+        display_id,
+        display_origin_x,
+        display_origin_y
+    );
+
+    select_input_events(&conn, root, track_keys)?;
+    let keymap = if track_keys {
+        Keymap::query(&conn)?
+    } else {
+        Keymap::default()
+    };
+
+    let handle = std::thread::spawn(move || {
+        tracing::info!(
+            "Linux input tracking started (poll_interval={:?})",
+            poll_interval
+        );
+
+        let process_time_ms = || start_time.elapsed().as_secs_f64() * 1000.0;
+
+        // Track which cursor hashes we've already saved to avoid duplicates
+        let mut saved_cursor_hashes: HashSet<u64> = HashSet::new();
+        let mut cursor_id = update_cursor(&conn, &cursors, &cursors_dir, &mut saved_cursor_hashes)
+            .unwrap_or_else(|| "unknown".to_string());
+
+        // Multi-click detection state: (button, time, x, y, count)
+        let mut last_press: Option<(u32, f64, f64, f64, u32)> = None;
+
+        // Always record the initial position so playback has a starting point
+        let mut pending_move = true;
+        let mut last_sample = Instant::now();
+
+        while is_recording.load(Ordering::Relaxed) {
+            let loop_start = Instant::now();
+
+            loop {
+                let event = match conn.poll_for_event() {
+                    Ok(Some(event)) => event,
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::error!("Lost connection to X server: {}", e);
+                        return;
+                    }
+                };
+
+                match event {
+                    Event::XinputRawMotion(_) => pending_move = true,
+                    Event::XinputRawButtonPress(ev) | Event::XinputRawButtonRelease(ev) => {
+                        let is_down = ev.event_type == xinput::RAW_BUTTON_PRESS_EVENT;
+                        if let Some((delta_x, delta_y)) = wheel_delta(ev.detail) {
+                            // Each notch is a press/release pair; count the press
+                            if !is_down {
+                                continue;
+                            }
+                            let Some(pointer) = query_pointer(&conn, root) else {
+                                continue;
+                            };
+
+                            mouse_scrolls.lock().push(MouseScroll {
+                                x: pointer.0 - display_origin_x,
+                                y: pointer.1 - display_origin_y,
+                                delta_x,
+                                delta_y,
+                                delta_mode: "line".to_string(),
+                                phase: None,
+                                active_modifiers: modifiers_from_mask(pointer.2),
+                                process_time_ms: process_time_ms(),
+                                unix_time_ms: unix_ms_fn(),
+                            });
+                            continue;
+                        }
+                        let Some(button) = button_name(ev.detail) else {
+                            continue;
+                        };
+                        let Some(pointer) = query_pointer(&conn, root) else {
+                            continue;
+                        };
+
+                        let x = pointer.0 - display_origin_x;
+                        let y = pointer.1 - display_origin_y;
+                        let now_ms = process_time_ms();
+
+                        let click_count = if is_down {
+                            let count = match last_press {
+                                Some((b, t, px, py, count))
+                                    if b == ev.detail
+                                        && now_ms - t <= MULTI_CLICK_INTERVAL_MS
+                                        && (x - px).hypot(y - py) <= MULTI_CLICK_DISTANCE_PX =>
+                                {
+                                    count + 1
+                                }
+                                _ => 1,
+                            };
+                            last_press = Some((ev.detail, now_ms, x, y, count));
+                            count
+                        } else {
+                            last_press
+                                .filter(|(b, ..)| *b == ev.detail)
+                                .map(|(.., count)| count)
+                                .unwrap_or(1)
+                        };
+
+                        mouse_clicks.lock().push(MouseClick {
+                            x,
+                            y,
+                            button: button.to_string(),
+                            event_type: if is_down {
+                                "down".to_string()
+                            } else {
+                                "up".to_string()
+                            },
+                            click_count,
+                            active_modifiers: modifiers_from_mask(pointer.2),
+                            process_time_ms: now_ms,
+                            unix_time_ms: unix_ms_fn(),
+                        });
+                    }
+                    Event::XinputRawKeyPress(ev) | Event::XinputRawKeyRelease(ev) => {
+                        if ev.flags.contains(xinput::KeyEventFlags::KEY_REPEAT) {
+                            continue;
+                        }
+                        let is_down = ev.event_type == xinput::RAW_KEY_PRESS_EVENT;
+                        let mask = query_pointer(&conn, root)
+                            .map(|pointer| pointer.2)
+                            .unwrap_or_default();
+                        let shift = u16::from(mask) & u16::from(KeyButMask::SHIFT) != 0;
+                        let (key, character) = key_from_keysym(keymap.keysym(ev.detail, shift));
+
+                        keystrokes.lock().push(KeyEvent {
+                            key_code: ev.detail,
+                            key,
+                            character,
+                            event_type: if is_down {
+                                "down".to_string()
+                            } else {
+                                "up".to_string()
+                            },
+                            active_modifiers: modifiers_from_mask(mask),
+                            process_time_ms: process_time_ms(),
+                            unix_time_ms: unix_ms_fn(),
+                        });
+                    }
+                    Event::XfixesCursorNotify(_) => {
+                        if let Some(id) =
+                            update_cursor(&conn, &cursors, &cursors_dir, &mut saved_cursor_hashes)
+                        {
+                            if id != cursor_id {
+                                cursor_id = id;
+                                pending_move = true;
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if pending_move && last_sample.elapsed() >= poll_interval {
+                if let Some((root_x, root_y, mask)) = query_pointer(&conn, root) {
+                    mouse_moves.lock().push(MouseMove {
+                        x: root_x - display_origin_x,
+                        y: root_y - display_origin_y,
+                        cursor_id: cursor_id.clone(),
+                        active_modifiers: modifiers_from_mask(mask),
+                        process_time_ms: process_time_ms(),
+                        unix_time_ms: unix_ms_fn(),
+                    });
+                }
+                pending_move = false;
+                last_sample = Instant::now();
+            }
+
+            let elapsed = loop_start.elapsed();
+            if elapsed < poll_interval {
+                std::thread::sleep(poll_interval - elapsed);
+            }
+        }
+
+        tracing::info!("Linux input tracking thread stopped");
+    });
+
+    Ok(handle)
+}
+
+/// Check the X server supports the raw input events tracking relies on
+pub fn check_input_support() -> RecordingResult<()> {
+    let (conn, _) = x11rb::connect(None).map_err(|e| {
+        RecordingError::PlatformError(format!(
+            "Failed to connect to X server (is DISPLAY set?): {}",
+            e
+        ))
+    })?;
+    query_xinput_version(&conn)
+}
+
+/// Announce XInput 2.2 support, failing if the server has less than 2.1
+///
+/// The version a client announces decides what it's sent: from 2.1 on, raw
+/// events keep arriving while a device is grabbed.
+fn query_xinput_version(conn: &RustConnection) -> RecordingResult<()> {
+    let xi_version = conn
+        .xinput_xi_query_version(2, 2)
+        .map_err(|e| RecordingError::PlatformError(format!("XInput2 not available: {}", e)))?
+        .reply()
+        .map_err(|e| RecordingError::PlatformError(format!("XInput2 not available: {}", e)))?;
+    if (xi_version.major_version, xi_version.minor_version) < (2, 1) {
+        return Err(RecordingError::PlatformError(format!(
+            "Input tracking requires XInput 2.1 or later, the X server supports {}.{}",
+            xi_version.major_version, xi_version.minor_version
+        )));
+    }
+    Ok(())
+}
+
+/// Subscribe to XInput2 raw pointer (and optionally key) events and XFixes
+/// cursor changes on the root window
+fn select_input_events(
+    conn: &RustConnection,
+    root: Window,
+    track_keys: bool,
+) -> RecordingResult<()> {
+    query_xinput_version(conn)?;
+
+    let mut mask = xinput::XIEventMask::RAW_MOTION
+        | xinput::XIEventMask::RAW_BUTTON_PRESS
+        | xinput::XIEventMask::RAW_BUTTON_RELEASE;
+    if track_keys {
+        mask = mask | xinput::XIEventMask::RAW_KEY_PRESS | xinput::XIEventMask::RAW_KEY_RELEASE;
+    }
+
+    conn.xinput_xi_select_events(
+        root,
+        &[xinput::EventMask {
+            deviceid: xinput::Device::ALL_MASTER.into(),
+            mask: vec![mask],
+        }],
+    )
+    .map_err(|e| RecordingError::PlatformError(format!("Failed to select XInput2 events: {}", e)))?
+    .check()
+    .map_err(|e| {
+        RecordingError::PlatformError(format!("Failed to select XInput2 events: {}", e))
+    })?;
+
+    // Cursor tracking is optional - keep recording pointer events without it
+    let xfixes_ok = conn
+        .xfixes_query_version(4, 0)
+        .ok()
+        .and_then(|cookie| cookie.reply().ok())
+        .is_some();
+    if xfixes_ok {
+        if let Err(e) = conn
+            .xfixes_select_cursor_input(root, xfixes::CursorNotifyMask::DISPLAY_CURSOR)
+            .map(|_| ())
+        {
+            tracing::warn!("Failed to select XFixes cursor events: {}", e);
+        }
+    } else {
+        tracing::warn!("XFixes not available - cursor images will not be recorded");
+    }
+
+    conn.flush()
+        .map_err(|e| RecordingError::PlatformError(format!("X11 flush failed: {}", e)))?;
+    Ok(())
+}
+
+/// Query the pointer position (root coordinates) and modifier/button mask
+fn query_pointer(conn: &RustConnection, root: Window) -> Option<(f64, f64, KeyButMask)> {
+    let reply = conn.query_pointer(root).ok()?.reply().ok()?;
+    Some((reply.root_x as f64, reply.root_y as f64, reply.mask))
+}
+
+/// Map an X11 button number to the names used by the other platforms
+fn button_name(detail: u32) -> Option<&'static str> {
+    match detail {
+        1 => Some("left"),
+        2 => Some("middle"),
+        3 => Some("right"),
+        _ => None,
+    }
+}
+
+/// Scroll delta (x, y) in lines for the X11 wheel buttons
+fn wheel_delta(detail: u32) -> Option<(f64, f64)> {
+    match detail {
+        4 => Some((0.0, -1.0)),
+        5 => Some((0.0, 1.0)),
+        6 => Some((-1.0, 0.0)),
+        7 => Some((1.0, 0.0)),
+        _ => None,
+    }
+}
+
+/// Keycode to keysym table, as returned by GetKeyboardMapping
+#[derive(Default)]
+struct Keymap {
+    min_keycode: u32,
+    keysyms_per_keycode: usize,
+    keysyms: Vec<Keysym>,
+}
+
+impl Keymap {
+    fn query(conn: &RustConnection) -> RecordingResult<Self> {
+        let setup = conn.setup();
+        let min_keycode = setup.min_keycode;
+        let count = setup.max_keycode - min_keycode + 1;
+
+        let reply = conn
+            .get_keyboard_mapping(min_keycode, count)
+            .map_err(|e| RecordingError::PlatformError(format!("Failed to query keymap: {}", e)))?
+            .reply()
+            .map_err(|e| RecordingError::PlatformError(format!("Failed to query keymap: {}", e)))?;
+
+        Ok(Self {
+            min_keycode: min_keycode as u32,
+            keysyms_per_keycode: reply.keysyms_per_keycode as usize,
+            keysyms: reply.keysyms,
+        })
+    }
+
+    /// Keysym for a keycode, using the shifted level when shift is held
+    fn keysym(&self, keycode: u32, shift: bool) -> Keysym {
+        let Some(index) = keycode.checked_sub(self.min_keycode) else {
+            return 0;
+        };
+        let start = index as usize * self.keysyms_per_keycode;
+        let Some(levels) = self.keysyms.get(start..start + self.keysyms_per_keycode) else {
+            return 0;
+        };
+
+        let base = levels.first().copied().unwrap_or(0);
+        let shifted = levels.get(1).copied().unwrap_or(0);
+        if !shift {
+            return base;
+        }
+        if shifted != 0 {
+            return shifted;
+        }
+
+        // Letters often list only the lowercase keysym
+        char::from_u32(base)
+            .filter(|c| c.is_ascii_lowercase())
+            .map(|c| c.to_ascii_uppercase() as Keysym)
+            .unwrap_or(base)
+    }
+}
+
+/// Map a keysym to a DOM-style key name and the character it types, if any
+fn key_from_keysym(keysym: Keysym) -> (String, Option<String>) {
+    let named = match keysym {
+        0xff08 => "Backspace",
+        0xff09 | 0xfe20 => "Tab",
+        0xff0d | 0xff8d => "Enter",
+        0xff13 => "Pause",
+        0xff14 => "ScrollLock",
+        0xff1b => "Escape",
+        0xff50 | 0xff95 => "Home",
+        0xff51 | 0xff96 => "ArrowLeft",
+        0xff52 | 0xff97 => "ArrowUp",
+        0xff53 | 0xff98 => "ArrowRight",
+        0xff54 | 0xff99 => "ArrowDown",
+        0xff55 | 0xff9a => "PageUp",
+        0xff56 | 0xff9b => "PageDown",
+        0xff57 | 0xff9c => "End",
+        0xff61 => "PrintScreen",
+        0xff63 | 0xff9e => "Insert",
+        0xff67 => "ContextMenu",
+        0xff7f => "NumLock",
+        0xffe1 | 0xffe2 => "Shift",
+        0xffe3 | 0xffe4 => "Control",
+        0xffe5 => "CapsLock",
+        0xffe7 | 0xffe8 | 0xffeb | 0xffec => "Meta",
+        0xffe9 | 0xffea => "Alt",
+        0xfe03 => "AltGraph",
+        0xffff | 0xff9f => "Delete",
+        0xffbe..=0xffd5 => return (format!("F{}", keysym - 0xffbe + 1), None),
+        _ => "",
+    };
+    if !named.is_empty() {
+        return (named.to_string(), None);
+    }
+
+    let character = match keysym {
+        // Keypad digits and operators
+        0xffb0..=0xffb9 => char::from_u32(keysym - 0xffb0 + '0' as u32),
+        0xffaa => Some('*'),
+        0xffab => Some('+'),
+        0xffad => Some('-'),
+        0xffae => Some('.'),
+        0xffaf => Some('/'),
+        // Latin-1 keysyms match their code points
+        0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym),
+        // Unicode keysyms
+        0x0100_0100..=0x0110_ffff => char::from_u32(keysym - 0x0100_0000),
+        _ => None,
+    };
+
+    match character {
+        Some(c) => (c.to_string(), Some(c.to_string())),
+        None => ("Unidentified".to_string(), None),
+    }
+}
+
+fn modifiers_from_mask(mask: KeyButMask) -> Vec<String> {
+    let bits = u16::from(mask);
+    let mut v = Vec::new();
+
+    if bits & u16::from(KeyButMask::SHIFT) != 0 {
+        v.push("shift".to_string());
+    }
+    if bits & u16::from(KeyButMask::CONTROL) != 0 {
+        v.push("control".to_string());
+    }
+    if bits & u16::from(KeyButMask::MOD1) != 0 {
+        v.push("alt".to_string());
+    }
+    if bits & u16::from(KeyButMask::MOD4) != 0 {
+        v.push("meta".to_string());
+    }
+
+    v
+}
+
+/// Fetch the current cursor image, saving it as PNG the first time it is seen.
+/// Returns the cursor ID.
+fn update_cursor(
+    conn: &RustConnection,
+    cursors: &Arc<ParkingMutex<HashMap<String, CursorInfo>>>,
+    cursors_dir: &Path,
+    saved_cursor_hashes: &mut HashSet<u64>,
+) -> Option<String> {
+    let image = conn.xfixes_get_cursor_image().ok()?.reply().ok()?;
+
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    image.cursor_image.hash(&mut hasher);
+    image.xhot.hash(&mut hasher);
+    image.yhot.hash(&mut hasher);
+    let hash = hasher.finish();
+    let cursor_id = format!("cursor_{:016x}", hash);
+
+    if saved_cursor_hashes.insert(hash) {
+        let image_path = cursors_dir.join(format!("{}.png", cursor_id));
+        let rgba = argb_premultiplied_to_rgba(&image.cursor_image);
+
+        match write_png(&image_path, image.width as u32, image.height as u32, &rgba) {
+            Ok(()) => {
+                cursors.lock().insert(
+                    cursor_id.clone(),
+                    CursorInfo {
+                        id: cursor_id.clone(),
+                        image_path: image_path.to_string_lossy().to_string(),
+                        hotspot_x: image.xhot as f64,
+                        hotspot_y: image.yhot as f64,
+                        width: image.width as u32,
+                        height: image.height as u32,
+                    },
+                );
+            }
+            Err(e) => {
+                tracing::warn!("Failed to save cursor image {:?}: {}", image_path, e);
+                saved_cursor_hashes.remove(&hash);
+            }
+        }
+    }
+
+    Some(cursor_id)
+}
+
+/// Convert XFixes cursor pixels (premultiplied ARGB, one u32 per pixel) to straight RGBA bytes
+fn argb_premultiplied_to_rgba(pixels: &[u32]) -> Vec<u8> {
+    let mut rgba = Vec::with_capacity(pixels.len() * 4);
+
+    for &pixel in pixels {
+        let a = (pixel >> 24) & 0xff;
+        let unpremultiply =
+            |c: u32| -> u8 { (c * 255 + a / 2).checked_div(a).unwrap_or(0).min(255) as u8 };
+
+        rgba.push(unpremultiply((pixel >> 16) & 0xff));
+        rgba.push(unpremultiply((pixel >> 8) & 0xff));
+        rgba.push(unpremultiply(pixel & 0xff));
+        rgba.push(a as u8);
+    }
+
+    rgba
+}
+
+fn write_png(path: &Path, width: u32, height: u32, rgba: &[u8]) -> Result<(), png::EncodingError> {
+    let file = std::fs::File::create(path)?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_argb_unpremultiply() {
+        // Opaque red, 50% white (premultiplied), fully transparent
+        let pixels = [0xffff0000, 0x80808080, 0x00000000];
+        let rgba = argb_premultiplied_to_rgba(&pixels);
+
+        assert_eq!(&rgba[0..4], &[255, 0, 0, 255]);
+        assert_eq!(&rgba[4..8], &[255, 255, 255, 128]);
+        assert_eq!(&rgba[8..12], &[0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_key_from_keysym() {
+        assert_eq!(
+            key_from_keysym(0x70),
+            ("p".to_string(), Some("p".to_string()))
+        );
+        assert_eq!(
+            key_from_keysym(0x20),
+            (" ".to_string(), Some(" ".to_string()))
+        );
+        assert_eq!(key_from_keysym(0xff0d), ("Enter".to_string(), None));
+        assert_eq!(key_from_keysym(0xffeb), ("Meta".to_string(), None));
+        assert_eq!(key_from_keysym(0xffc2), ("F5".to_string(), None));
+        assert_eq!(key_from_keysym(0x0100_20ac).1, Some("€".to_string()));
+        assert_eq!(key_from_keysym(0).0, "Unidentified");
+    }
+
+    #[test]
+    fn test_keymap_shift_level() {
+        // Keycode 8: a/A, keycode 9: 1/!, keycode 10: only lowercase listed
+        let keymap = Keymap {
+            min_keycode: 8,
+            keysyms_per_keycode: 2,
+            keysyms: vec![0x61, 0x41, 0x31, 0x21, 0x62, 0],
+        };
+
+        assert_eq!(keymap.keysym(8, false), 0x61);
+        assert_eq!(keymap.keysym(9, true), 0x21);
+        assert_eq!(keymap.keysym(10, true), 0x42);
+        assert_eq!(keymap.keysym(7, false), 0);
+        assert_eq!(keymap.keysym(11, false), 0);
+    }
+
+    #[test]
+    fn test_wheel_buttons_are_not_clicks() {
+        assert_eq!(button_name(1), Some("left"));
+        assert_eq!(button_name(3), Some("right"));
+        for wheel in 4..=7 {
+            assert_eq!(button_name(wheel), None);
+            assert!(wheel_delta(wheel).is_some());
+        }
+
+        // Wheel down scrolls the page down
+        assert_eq!(wheel_delta(5), Some((0.0, 1.0)));
+        assert_eq!(wheel_delta(1), None);
+    }
+}
@@ -0,0 +1,26 @@
+//! Linux capture implementations
+//!
+//! Uses X11 (XRandR + MIT-SHM) for screen capture, XInput2 + XFixes for
+//! input tracking, PulseAudio/PipeWire monitor sources for system audio and
+//! Video4Linux2 for webcams.
+
+pub mod input;
+pub mod screen;
+pub mod system_audio;
+pub mod webcam;
+
+pub use input::*;
+pub use screen::*;
+pub use system_audio::*;
+pub use webcam::*;
+
+/// X11 doesn't require explicit permission for screen capture
+pub mod permissions {
+    pub fn has_screen_recording_permission() -> bool {
+        true
+    }
+
+    pub fn request_screen_recording_permission() -> bool {
+        true
+    }
+}
@@ -0,0 +1,897 @@
+//! Linux screen capture using X11 MIT-SHM
+//!
+//! Monitors are enumerated through XRandR and frames are grabbed from the
+//! root window with XShmGetImage (falling back to plain XGetImage when the
+//! MIT-SHM extension is unavailable, e.g. on remote displays).
+//! Frames are encoded by the channel's `EncoderBackend` according to its
+//! `VideoSettings`.
+//!
+//! Only an X server is required, so this also works headless under Xvfb.
+
+use crate::capture::encoder::{ffmpeg_backend, EncoderBackend, VideoOutput, VideoSpec};
+use crate::capture::frames::{FramePacer, FrameTimeline};
+use crate::capture::region::{resolve_crop, CaptureRegion, CropRect};
+use crate::capture::traits::{CaptureTarget, DisplayInfo, Resolution, WindowBounds, WindowInfo};
+use crate::capture::video::VideoSettings;
+use crate::recorder::channel::{
+    ChannelErrorReporter, ChannelType, RecordingChannel, RecordingError, RecordingResult,
+};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
+use crate::recorder::sources::{file_basename, VideoSource};
+use async_trait::async_trait;
+use parking_lot::Mutex as ParkingMutex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use x11rb::connection::Connection;
+use x11rb::protocol::randr::{self, ConnectionExt as _};
+use x11rb::protocol::shm::{self, ConnectionExt as _};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ConnectionExt as _, ImageFormat, ImageOrder, MapState, Window,
+};
+use x11rb::rust_connection::RustConnection;
+
+/// A monitor as reported by XRandR, in root window coordinates
+#[derive(Debug, Clone)]
+pub(crate) struct X11Monitor {
+    pub name: String,
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+    pub is_primary: bool,
+    pub refresh_rate: Option<u32>,
+}
+
+/// Query active monitors through XRandR
+///
+/// Falls back to a single monitor covering the whole root window when
+/// RandR 1.5 is not available (older Xvfb builds, nested servers).
+pub(crate) fn query_monitors(conn: &RustConnection, screen_num: usize) -> Vec<X11Monitor> {
+    let screen = &conn.setup().roots[screen_num];
+    let root = screen.root;
+
+    let mut monitors = query_randr_monitors(conn, root).unwrap_or_default();
+
+    if monitors.is_empty() {
+        monitors.push(X11Monitor {
+            name: format!("Screen {}", screen_num),
+            x: 0,
+            y: 0,
+            width: screen.width_in_pixels,
+            height: screen.height_in_pixels,
+            is_primary: true,
+            refresh_rate: None,
+        });
+    }
+
+    // Make sure exactly one monitor is flagged as primary
+    if !monitors.iter().any(|m| m.is_primary) {
+        monitors[0].is_primary = true;
+    }
+
+    monitors
+}
+
+fn query_randr_monitors(conn: &RustConnection, root: Window) -> Option<Vec<X11Monitor>> {
+    // GetMonitors needs RandR 1.5
+    let version = conn.randr_query_version(1, 5).ok()?.reply().ok()?;
+    if version.major_version < 1 || (version.major_version == 1 && version.minor_version < 5) {
+        return None;
+    }
+
+    let resources = conn
+        .randr_get_screen_resources_current(root)
+        .ok()?
+        .reply()
+        .ok()?;
+    let reply = conn.randr_get_monitors(root, true).ok()?.reply().ok()?;
+
+    let monitors = reply
+        .monitors
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| {
+            let name = conn
+                .get_atom_name(monitor.name)
+                .ok()
+                .and_then(|cookie| cookie.reply().ok())
+                .map(|reply| String::from_utf8_lossy(&reply.name).to_string())
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| format!("Display {}", index + 1));
+
+            X11Monitor {
+                name,
+                x: monitor.x,
+                y: monitor.y,
+                width: monitor.width,
+                height: monitor.height,
+                is_primary: monitor.primary,
+                refresh_rate: monitor_refresh_rate(conn, &resources, &monitor.outputs),
+            }
+        })
+        .collect();
+
+    Some(monitors)
+}
+
+/// Compute the refresh rate of the CRTC driving the monitor's first output
+fn monitor_refresh_rate(
+    conn: &RustConnection,
+    resources: &randr::GetScreenResourcesCurrentReply,
+    outputs: &[randr::Output],
+) -> Option<u32> {
+    let output = *outputs.first()?;
+    let output_info = conn
+        .randr_get_output_info(output, resources.config_timestamp)
+        .ok()?
+        .reply()
+        .ok()?;
+    if output_info.crtc == x11rb::NONE {
+        return None;
+    }
+
+    let crtc_info = conn
+        .randr_get_crtc_info(output_info.crtc, resources.config_timestamp)
+        .ok()?
+        .reply()
+        .ok()?;
+    let mode = resources.modes.iter().find(|m| m.id == crtc_info.mode)?;
+
+    let total = mode.htotal as f64 * mode.vtotal as f64;
+    if total > 0.0 && mode.dot_clock > 0 {
+        Some((mode.dot_clock as f64 / total).round() as u32)
+    } else {
+        None
+    }
+}
+
+/// Get list of available displays
+pub fn get_displays() -> Vec<DisplayInfo> {
+    let (conn, screen_num) = match x11rb::connect(None) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("Failed to connect to X server: {}", e);
+            return Vec::new();
+        }
+    };
+
+    query_monitors(&conn, screen_num)
+        .into_iter()
+        .enumerate()
+        .map(|(index, monitor)| DisplayInfo {
+            id: index as u32,
+            name: monitor.name,
+            width: monitor.width as u32,
+            height: monitor.height as u32,
+            // X11 has no per-monitor scaling, coordinates are always physical pixels
+            scale_factor: 1.0,
+            is_primary: monitor.is_primary,
+            refresh_rate: monitor.refresh_rate,
+        })
+        .collect()
+}
+
+/// Intern an atom by name
+fn intern_atom(conn: &RustConnection, name: &str) -> Option<Atom> {
+    Some(
+        conn.intern_atom(false, name.as_bytes())
+            .ok()?
+            .reply()
+            .ok()?
+            .atom,
+    )
+}
+
+/// Read a window property as raw bytes
+fn get_property_bytes(conn: &RustConnection, window: Window, property: Atom) -> Option<Vec<u8>> {
+    let reply = conn
+        .get_property(false, window, property, AtomEnum::ANY, 0, u32::MAX / 4)
+        .ok()?
+        .reply()
+        .ok()?;
+    if reply.value.is_empty() {
+        None
+    } else {
+        Some(reply.value)
+    }
+}
+
+/// Top-level windows managed by the window manager
+///
+/// Uses the EWMH `_NET_CLIENT_LIST` when available, otherwise the mapped
+/// children of the root window (bare X servers such as Xvfb).
+fn list_client_windows(conn: &RustConnection, root: Window) -> Vec<Window> {
+    let from_ewmh = intern_atom(conn, "_NET_CLIENT_LIST").and_then(|atom| {
+        let reply = conn
+            .get_property(false, root, atom, AtomEnum::WINDOW, 0, u32::MAX / 4)
+            .ok()?
+            .reply()
+            .ok()?;
+        let windows: Vec<Window> = reply.value32()?.collect();
+        (!windows.is_empty()).then_some(windows)
+    });
+
+    from_ewmh.unwrap_or_else(|| {
+        conn.query_tree(root)
+            .ok()
+            .and_then(|cookie| cookie.reply().ok())
+            .map(|reply| reply.children)
+            .unwrap_or_default()
+    })
+}
+
+/// Query a window's title, class and position in root coordinates
+fn query_window(conn: &RustConnection, root: Window, window: Window) -> Option<WindowInfo> {
+    let geometry = conn.get_geometry(window).ok()?.reply().ok()?;
+    let position = conn
+        .translate_coordinates(window, root, 0, 0)
+        .ok()?
+        .reply()
+        .ok()?;
+    let attributes = conn.get_window_attributes(window).ok()?.reply().ok()?;
+
+    let title = intern_atom(conn, "_NET_WM_NAME")
+        .and_then(|atom| get_property_bytes(conn, window, atom))
+        .or_else(|| get_property_bytes(conn, window, AtomEnum::WM_NAME.into()))
+        .map(|bytes| String::from_utf8_lossy(&bytes).to_string())
+        .unwrap_or_default();
+
+    // WM_CLASS holds "instance\0class\0"; the class is the application name
+    let app_name = get_property_bytes(conn, window, AtomEnum::WM_CLASS.into())
+        .map(|bytes| {
+            let parts: Vec<String> = bytes
+                .split(|&b| b == 0)
+                .filter(|part| !part.is_empty())
+                .map(|part| String::from_utf8_lossy(part).to_string())
+                .collect();
+            parts.last().cloned().unwrap_or_default()
+        })
+        .unwrap_or_default();
+
+    Some(WindowInfo {
+        id: window,
+        title,
+        app_name,
+        bounds: WindowBounds {
+            x: position.dst_x as i32,
+            y: position.dst_y as i32,
+            width: geometry.width as u32,
+            height: geometry.height as u32,
+        },
+        is_on_screen: attributes.map_state == MapState::VIEWABLE,
+    })
+}
+
+/// Get list of capturable windows
+pub fn get_windows() -> Vec<WindowInfo> {
+    let (conn, screen_num) = match x11rb::connect(None) {
+        Ok(c) => c,
+        Err(e) => {
+            tracing::warn!("Failed to connect to X server: {}", e);
+            return Vec::new();
+        }
+    };
+    let root = conn.setup().roots[screen_num].root;
+
+    list_client_windows(&conn, root)
+        .into_iter()
+        .filter_map(|window| query_window(&conn, root, window))
+        .filter(|info| info.is_on_screen && info.bounds.width > 1 && info.bounds.height > 1)
+        .collect()
+}
+
+/// Get information about a single window
+pub fn get_window_info(window_id: u32) -> Option<WindowInfo> {
+    let (conn, screen_num) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots[screen_num].root;
+    query_window(&conn, root, window_id)
+}
+
+/// Index of the display that contains the center of a window
+pub fn display_for_window(window_id: u32) -> Option<u32> {
+    let (conn, screen_num) = x11rb::connect(None).ok()?;
+    let root = conn.setup().roots[screen_num].root;
+    let window = query_window(&conn, root, window_id)?;
+
+    let center_x = window.bounds.x + window.bounds.width as i32 / 2;
+    let center_y = window.bounds.y + window.bounds.height as i32 / 2;
+    let monitors = query_monitors(&conn, screen_num);
+
+    monitors
+        .iter()
+        .position(|m| {
+            center_x >= m.x as i32
+                && center_x < m.x as i32 + m.width as i32
+                && center_y >= m.y as i32
+                && center_y < m.y as i32 + m.height as i32
+        })
+        .or_else(|| monitors.iter().position(|m| m.is_primary))
+        .map(|index| index as u32)
+}
+
+/// Shared memory segment attached to the X server
+struct ShmSegment {
+    seg: shm::Seg,
+    addr: *mut libc::c_void,
+    size: usize,
+}
+
+// SAFETY: the mapping is owned by a single grabber and only accessed from
+// the task that owns it.
+unsafe impl Send for ShmSegment {}
+
+/// Grabs frames of a single monitor (or a rectangle of it) from the X root window
+struct X11FrameGrabber {
+    conn: RustConnection,
+    root: Window,
+    monitor: X11Monitor,
+    x: i16,
+    y: i16,
+    width: u16,
+    height: u16,
+    shm: Option<ShmSegment>,
+}
+
+impl X11FrameGrabber {
+    fn new(display_id: u32, crop: Option<CropRect>) -> RecordingResult<Self> {
+        let (conn, screen_num) = x11rb::connect(None).map_err(|e| {
+            RecordingError::PlatformError(format!(
+                "Failed to connect to X server (is DISPLAY set?): {}",
+                e
+            ))
+        })?;
+
+        let monitor = query_monitors(&conn, screen_num)
+            .into_iter()
+            .nth(display_id as usize)
+            .ok_or_else(|| {
+                RecordingError::DeviceNotFound(format!("Display {} not found", display_id))
+            })?;
+
+        let setup = conn.setup();
+        let screen = &setup.roots[screen_num];
+        let root = screen.root;
+
+        // Frames are piped to FFmpeg as BGRA, which is what a 32bpp
+        // little-endian ZPixmap looks like in memory
+        let bits_per_pixel = setup
+            .pixmap_formats
+            .iter()
+            .find(|f| f.depth == screen.root_depth)
+            .map(|f| f.bits_per_pixel);
+        if bits_per_pixel != Some(32) || setup.image_byte_order != ImageOrder::LSB_FIRST {
+            return Err(RecordingError::CaptureError(format!(
+                "Unsupported X visual (depth {}, {:?} bpp) - a 24/32-bit little-endian display is required",
+                screen.root_depth, bits_per_pixel
+            )));
+        }
+
+        let (x, y, width, height) = match crop {
+            Some(rect) => (
+                monitor.x + rect.x as i16,
+                monitor.y + rect.y as i16,
+                rect.width as u16,
+                rect.height as u16,
+            ),
+            None => (monitor.x, monitor.y, monitor.width, monitor.height),
+        };
+
+        let mut grabber = Self {
+            conn,
+            root,
+            monitor,
+            x,
+            y,
+            width,
+            height,
+            shm: None,
+        };
+
+        grabber.shm = grabber.attach_shm();
+        if grabber.shm.is_none() {
+            tracing::warn!("MIT-SHM not available, falling back to XGetImage (slower)");
+        }
+
+        Ok(grabber)
+    }
+
+    /// Create a SysV shared memory segment and attach it to the X server
+    fn attach_shm(&self) -> Option<ShmSegment> {
+        self.conn.shm_query_version().ok()?.reply().ok()?;
+
+        let size = self.width as usize * self.height as usize * 4;
+
+        unsafe {
+            let shmid = libc::shmget(libc::IPC_PRIVATE, size, libc::IPC_CREAT | 0o600);
+            if shmid < 0 {
+                return None;
+            }
+
+            let addr = libc::shmat(shmid, std::ptr::null(), 0);
+            if addr as isize == -1 {
+                libc::shmctl(shmid, libc::IPC_RMID, std::ptr::null_mut());
+                return None;
+            }
+
+            let attached = self.conn.generate_id().ok().and_then(|seg| {
+                self.conn
+                    .shm_attach(seg, shmid as u32, false)
+                    .ok()?
+                    .check()
+                    .ok()
+                    .map(|_| seg)
+            });
+
+            // Mark for removal now - the segment is freed once both sides detach
+            libc::shmctl(shmid, libc::IPC_RMID, std::ptr::null_mut());
+
+            match attached {
+                Some(seg) => Some(ShmSegment { seg, addr, size }),
+                None => {
+                    libc::shmdt(addr);
+                    None
+                }
+            }
+        }
+    }
+
+    fn width(&self) -> u32 {
+        self.width as u32
+    }
+
+    fn height(&self) -> u32 {
+        self.height as u32
+    }
+
+    /// Move the grabbed rectangle (size stays the same)
+    fn move_to(&mut self, rect: &CropRect) {
+        self.x = self.monitor.x + rect.x as i16;
+        self.y = self.monitor.y + rect.y as i16;
+    }
+
+    /// Position of a window relative to the monitor
+    fn window_origin(&self, window: Window) -> Option<(i64, i64)> {
+        let position = self
+            .conn
+            .translate_coordinates(window, self.root, 0, 0)
+            .ok()?
+            .reply()
+            .ok()?;
+        Some((
+            position.dst_x as i64 - self.monitor.x as i64,
+            position.dst_y as i64 - self.monitor.y as i64,
+        ))
+    }
+
+    /// Capture a single BGRA frame of the monitor
+    fn grab(&self) -> Option<Vec<u8>> {
+        match &self.shm {
+            Some(segment) => {
+                self.conn
+                    .shm_get_image(
+                        self.root,
+                        self.x,
+                        self.y,
+                        self.width,
+                        self.height,
+                        !0,
+                        ImageFormat::Z_PIXMAP.into(),
+                        segment.seg,
+                        0,
+                    )
+                    .ok()?
+                    .reply()
+                    .ok()?;
+
+                let data =
+                    unsafe { std::slice::from_raw_parts(segment.addr as *const u8, segment.size) };
+                Some(data.to_vec())
+            }
+            None => {
+                let reply = self
+                    .conn
+                    .get_image(
+                        ImageFormat::Z_PIXMAP,
+                        self.root,
+                        self.x,
+                        self.y,
+                        self.width,
+                        self.height,
+                        !0,
+                    )
+                    .ok()?
+                    .reply()
+                    .ok()?;
+                Some(reply.data)
+            }
+        }
+    }
+}
+
+impl Drop for X11FrameGrabber {
+    fn drop(&mut self) {
+        if let Some(segment) = self.shm.take() {
+            let _ = self.conn.shm_detach(segment.seg);
+            let _ = self.conn.flush();
+            unsafe {
+                libc::shmdt(segment.addr);
+            }
+        }
+    }
+}
+
+/// Display capture channel for Linux (X11)
+pub struct DisplayCaptureChannel {
+    id: String,
+    display_id: u32,
+    is_recording: Arc<AtomicBool>,
+    output_dir: Option<PathBuf>,
+    session_index: usize,
+    clock: RecordingClock,
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
+    output_files: Arc<ParkingMutex<Vec<String>>>,
+    encoders: Arc<dyn EncoderBackend>,
+    encoder: Option<Arc<VideoOutput>>,
+    capture_handle: Option<tokio::task::JoinHandle<FrameTimeline>>,
+    width: u32,
+    height: u32,
+    video: VideoSettings,
+    target: Option<CaptureTarget>,
+    capture_region: Option<Arc<CaptureRegion>>,
+    file_suffix: Option<String>,
+}
+
+/// Work out which part of the monitor a window or region target covers
+fn resolve_monitor_crop(
+    target: Option<&CaptureTarget>,
+    display_id: u32,
+) -> RecordingResult<Option<CropRect>> {
+    let (conn, screen_num) = x11rb::connect(None).map_err(|e| {
+        RecordingError::PlatformError(format!("Failed to connect to X server: {}", e))
+    })?;
+    let monitor = query_monitors(&conn, screen_num)
+        .into_iter()
+        .nth(display_id as usize)
+        .ok_or_else(|| {
+            RecordingError::DeviceNotFound(format!("Display {} not found", display_id))
+        })?;
+    let root = conn.setup().roots[screen_num].root;
+
+    resolve_crop(
+        target,
+        display_id,
+        monitor.width as u32,
+        monitor.height as u32,
+        |window_id| {
+            let window = query_window(&conn, root, window_id)?;
+            Some(WindowBounds {
+                x: window.bounds.x - monitor.x as i32,
+                y: window.bounds.y - monitor.y as i32,
+                ..window.bounds
+            })
+        },
+    )
+}
+
+impl DisplayCaptureChannel {
+    /// Create a new display capture channel
+    pub fn new(display_id: u32) -> Self {
+        Self {
+            id: format!("display-{}", display_id),
+            display_id,
+            is_recording: Arc::new(AtomicBool::new(false)),
+            output_dir: None,
+            session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
+            output_files: Arc::new(ParkingMutex::new(Vec::new())),
+            encoders: ffmpeg_backend(),
+            encoder: None,
+            capture_handle: None,
+            width: 1920,
+            height: 1080,
+            video: VideoSettings::default(),
+            target: None,
+            capture_region: None,
+            file_suffix: None,
+        }
+    }
+
+    /// Capture a window or region of the display instead of all of it
+    ///
+    /// The current crop origin is published to `capture_region` so input
+    /// tracking can use the same coordinate space.
+    pub fn with_target(
+        mut self,
+        target: CaptureTarget,
+        capture_region: Arc<CaptureRegion>,
+    ) -> Self {
+        self.target = Some(target);
+        self.capture_region = Some(capture_region);
+        self
+    }
+
+    /// Capture at the settings' frame rate and encode with them
+    pub fn with_video_settings(mut self, video: VideoSettings) -> Self {
+        self.video = video;
+        self
+    }
+
+    /// Encode through another backend (FFmpeg by default)
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
+    /// Record an additional display to `recording-N-{suffix}` files
+    pub fn with_file_suffix(mut self, suffix: String) -> Self {
+        self.file_suffix = Some(suffix);
+        self
+    }
+
+    fn basename(&self) -> String {
+        file_basename(self.session_index, self.file_suffix.as_deref())
+    }
+}
+
+#[async_trait]
+impl RecordingChannel for DisplayCaptureChannel {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Display
+    }
+
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
+        self.encoders.check_video(&self.video)?;
+
+        // Get display info
+        let displays = get_displays();
+        let display = displays.get(self.display_id as usize).ok_or_else(|| {
+            RecordingError::DeviceNotFound(format!("Display {} not found", self.display_id))
+        })?;
+        self.width = display.width;
+        self.height = display.height;
+
+        if let Some(rect) = resolve_monitor_crop(self.target.as_ref(), self.display_id)? {
+            self.width = rect.width;
+            self.height = rect.height;
+        }
+
+        self.output_dir = Some(output_dir.to_path_buf());
+        self.session_index = session_index;
+        self.clock = clock.clone();
+
+        tracing::info!(
+            "Linux display capture initialized for display {} ({}x{}, target: {:?})",
+            self.display_id,
+            self.width,
+            self.height,
+            self.target
+        );
+        Ok(())
+    }
+
+    async fn start(&mut self) -> RecordingResult<()> {
+        if self.is_recording.load(Ordering::SeqCst) {
+            return Err(RecordingError::AlreadyRecording);
+        }
+
+        let output_dir = self.output_dir.clone().ok_or_else(|| {
+            RecordingError::ConfigurationError("Output directory not set".to_string())
+        })?;
+
+        // Windows are re-resolved every session since they may have moved
+        let crop = resolve_monitor_crop(self.target.as_ref(), self.display_id)?;
+        let mut grabber = X11FrameGrabber::new(self.display_id, crop)?;
+
+        // Frame times are measured from the first grab
+        let mut pacer = FramePacer::new(self.video.fps, &self.clock, &self.id, self.session_index)
+            .with_health(self.health.clone());
+
+        // Capture first frame to make sure grabbing works before starting the encoder
+        let first_frame = grabber.grab().ok_or_else(|| {
+            RecordingError::CaptureError("Failed to capture initial frame".to_string())
+        })?;
+
+        self.width = grabber.width();
+        self.height = grabber.height();
+
+        tracing::info!(
+            "Actual capture dimensions: {}x{} (MIT-SHM: {})",
+            self.width,
+            self.height,
+            grabber.shm.is_some()
+        );
+
+        let spec = VideoSpec {
+            width: self.width,
+            height: self.height,
+            settings: self.video.clone(),
+            output_file: self.video.output_file(&output_dir, &self.basename()),
+        };
+        let encoder = Arc::new(
+            self.encoders
+                .video_encoder(&spec)
+                .map(|encoder| {
+                    VideoOutput::new(encoder).with_error_reporter(self.error_reporter.clone())
+                })
+                .map_err(|e| {
+                    RecordingError::CaptureError(format!("Failed to start encoder: {}", e))
+                })?,
+        );
+
+        // Write first frame
+        let expected_size = (self.width * self.height * 4) as usize;
+        if first_frame.len() >= expected_size {
+            pacer.push(&first_frame[..expected_size], |frame| {
+                encoder.write_frame(frame)
+            });
+        }
+
+        self.encoder = Some(encoder.clone());
+        self.is_recording.store(true, Ordering::SeqCst);
+
+        if let (Some(rect), Some(region)) = (crop, &self.capture_region) {
+            region.set_origin_now(rect.x as f64, rect.y as f64);
+        }
+
+        // Start capture loop
+        let is_recording = self.is_recording.clone();
+        let fps = self.video.fps;
+        let width = self.width;
+        let height = self.height;
+        let follow_window = match self.target {
+            Some(CaptureTarget::Window { window_id }) => crop.map(|rect| (window_id, rect)),
+            _ => None,
+        };
+        let capture_region = self.capture_region.clone();
+
+        let handle = tokio::spawn(async move {
+            let frame_interval = std::time::Duration::from_millis(1000 / fps as u64);
+            let expected_size = (width * height * 4) as usize;
+            let mut current_rect = crop;
+
+            while is_recording.load(Ordering::SeqCst) {
+                let start = std::time::Instant::now();
+
+                // Keep the crop on the window as it moves
+                if let Some((window_id, rect)) = follow_window {
+                    if let Some((x, y)) = grabber.window_origin(window_id) {
+                        let moved = rect.moved_to(
+                            x,
+                            y,
+                            grabber.monitor.width as u32,
+                            grabber.monitor.height as u32,
+                        );
+                        if current_rect != Some(moved) {
+                            grabber.move_to(&moved);
+                            current_rect = Some(moved);
+                            if let Some(ref region) = capture_region {
+                                region.set_origin_now(moved.x as f64, moved.y as f64);
+                            }
+                        }
+                    }
+                }
+
+                if let Some(data) = grabber.grab() {
+                    if data.len() >= expected_size {
+                        pacer.push(&data[..expected_size], |frame| encoder.write_frame(frame));
+                    }
+                }
+
+                let count = encoder.frame_count();
+                if count.is_multiple_of(60) && count > 0 {
+                    tracing::debug!(
+                        "Captured {} frames ({:.1}s) at {}x{}",
+                        count,
+                        count as f64 / fps as f64,
+                        width,
+                        height
+                    );
+                }
+
+                let elapsed = start.elapsed();
+                if elapsed < frame_interval {
+                    tokio::time::sleep(frame_interval - elapsed).await;
+                }
+            }
+
+            pacer.finish(|frame| encoder.write_frame(frame))
+        });
+
+        self.capture_handle = Some(handle);
+
+        tracing::info!(
+            "Linux display capture started for display {} ({}x{} @ {}fps)",
+            self.display_id,
+            self.width,
+            self.height,
+            self.video.fps
+        );
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> RecordingResult<()> {
+        if !self.is_recording.load(Ordering::SeqCst) {
+            return Err(RecordingError::NotRecording);
+        }
+
+        self.is_recording.store(false, Ordering::SeqCst);
+
+        let timeline = match self.capture_handle.take() {
+            Some(handle) => handle.await.ok(),
+            None => None,
+        };
+
+        if let Some(ref encoder) = self.encoder {
+            let files = encoder.finish().map_err(|e| {
+                RecordingError::CaptureError(format!("Failed to finish encoding: {}", e))
+            })?;
+            self.output_files.lock().extend(files);
+        }
+        self.encoder = None;
+
+        if let (Some(timeline), Some(ref output_dir)) = (timeline, &self.output_dir) {
+            tracing::info!(
+                "Frame timing: {} captured, {} dropped, {} encoded",
+                timeline.frames.len(),
+                timeline.dropped_count(),
+                timeline.output_frame_count
+            );
+            let sidecar = timeline.write_sidecar(output_dir, &self.basename())?;
+            self.output_files.lock().push(sidecar);
+        }
+
+        tracing::info!("Linux display capture stopped");
+        Ok(())
+    }
+
+    async fn pause(&mut self) -> RecordingResult<()> {
+        self.stop().await
+    }
+
+    async fn resume(&mut self, session_index: usize) -> RecordingResult<()> {
+        self.session_index = session_index;
+        self.start().await
+    }
+
+    fn is_recording(&self) -> bool {
+        self.is_recording.load(Ordering::SeqCst)
+    }
+
+    fn output_files(&self) -> Vec<String> {
+        self.output_files.lock().clone()
+    }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
+
+    fn video_source(&self) -> Option<VideoSource> {
+        Some(VideoSource {
+            channel_id: self.id.clone(),
+            display_id: Some(self.display_id),
+            file_suffix: self.file_suffix.clone(),
+            capture_size: Some(Resolution {
+                width: self.width,
+                height: self.height,
+            }),
+        })
+    }
+}
@@ -0,0 +1,432 @@
+//! Linux System Audio Capture using PulseAudio/PipeWire monitor sources
+//!
+//! Every PulseAudio sink has a `<sink>.monitor` source that carries whatever
+//! is being played to it. PipeWire exposes the same sources through
+//! pipewire-pulse, so both servers are handled by the same code path.
+//!
+//! Samples are read from `parec` as 48kHz stereo f32le and fed to the shared
+//! `AudioEncoder`. The channel can be exercised headless against a null sink:
+//!
+//! ```text
+//! pactl load-module module-null-sink sink_name=ci_sink
+//! ```
+
+use crate::capture::audio::{AudioEncoder, AudioFormat};
+use crate::capture::encoder::{ffmpeg_backend, EncoderBackend};
+use crate::recorder::channel::{
+    ChannelErrorReporter, ChannelType, RecordingChannel, RecordingError, RecordingResult,
+};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
+use async_trait::async_trait;
+use parking_lot::Mutex as ParkingMutex;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+const SAMPLE_RATE: u32 = 48000;
+const CHANNELS: u16 = 2;
+
+/// Bytes of one interleaved f32 frame
+const FRAME_BYTES: usize = CHANNELS as usize * 4;
+
+/// Check if system audio capture is available
+/// Returns true when a PulseAudio-compatible server is reachable and `parec` is installed
+pub fn is_system_audio_available() -> bool {
+    let server_running = Command::new("pactl")
+        .arg("info")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false);
+
+    let parec_installed = Command::new("parec")
+        .arg("--version")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok();
+
+    server_running && parec_installed
+}
+
+/// Get the name of the default sink
+fn get_default_sink() -> Option<String> {
+    // pactl >= 15 (and pipewire-pulse) support get-default-sink directly
+    if let Ok(output) = Command::new("pactl").arg("get-default-sink").output() {
+        if output.status.success() {
+            let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            if !name.is_empty() {
+                return Some(name);
+            }
+        }
+    }
+
+    // Older pactl: parse "Default Sink: <name>" from `pactl info`
+    let output = Command::new("pactl").arg("info").output().ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.strip_prefix("Default Sink:"))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Check whether a source with the given name exists
+fn source_exists(source: &str) -> bool {
+    Command::new("pactl")
+        .args(["list", "short", "sources"])
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.split('\t').nth(1) == Some(source))
+        })
+        .unwrap_or(false)
+}
+
+/// System audio capture channel for Linux
+///
+/// Records the monitor source of the default sink, or of a named sink.
+pub struct SystemAudioCaptureChannel {
+    id: String,
+    sink_name: Option<String>,
+    monitor_source: Option<String>,
+    is_recording: Arc<AtomicBool>,
+    output_dir: Option<PathBuf>,
+    session_index: usize,
+    clock: RecordingClock,
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
+    format: AudioFormat,
+    encoders: Arc<dyn EncoderBackend>,
+    output_files: Arc<ParkingMutex<Vec<String>>>,
+    encoder: Arc<ParkingMutex<Option<Arc<AudioEncoder>>>>,
+    process: Arc<ParkingMutex<Option<Child>>>,
+    stream_handle: Arc<ParkingMutex<Option<std::thread::JoinHandle<()>>>>,
+    sample_count: Arc<AtomicU64>,
+}
+
+impl SystemAudioCaptureChannel {
+    /// Create a new system audio capture channel
+    /// If sink_name is None, the default sink is recorded
+    pub fn new(sink_name: Option<String>) -> Self {
+        Self {
+            id: "system-audio".to_string(),
+            sink_name,
+            monitor_source: None,
+            is_recording: Arc::new(AtomicBool::new(false)),
+            output_dir: None,
+            session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
+            format: AudioFormat::default(),
+            encoders: ffmpeg_backend(),
+            output_files: Arc::new(ParkingMutex::new(Vec::new())),
+            encoder: Arc::new(ParkingMutex::new(None)),
+            process: Arc::new(ParkingMutex::new(None)),
+            stream_handle: Arc::new(ParkingMutex::new(None)),
+            sample_count: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Codec to record system audio with (AAC by default)
+    pub fn with_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Encode through another backend (FFmpeg by default)
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
+    /// Check if system audio capture is available
+    pub fn is_available(&self) -> bool {
+        is_system_audio_available()
+    }
+}
+
+impl Default for SystemAudioCaptureChannel {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+#[async_trait]
+impl RecordingChannel for SystemAudioCaptureChannel {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::SystemAudio
+    }
+
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
+        if !is_system_audio_available() {
+            return Err(RecordingError::ConfigurationError(
+                "System audio capture requires a running PulseAudio or PipeWire (pipewire-pulse) server and parec (pulseaudio-utils)."
+                    .to_string(),
+            ));
+        }
+        self.encoders.check_audio(&self.format)?;
+
+        let sink = match &self.sink_name {
+            Some(name) => name.clone(),
+            None => get_default_sink().ok_or_else(|| {
+                RecordingError::DeviceNotFound("No default audio sink".to_string())
+            })?,
+        };
+
+        let monitor_source = format!("{}.monitor", sink);
+        if !source_exists(&monitor_source) {
+            return Err(RecordingError::DeviceNotFound(format!(
+                "Monitor source '{}' not found",
+                monitor_source
+            )));
+        }
+
+        self.monitor_source = Some(monitor_source);
+        self.output_dir = Some(output_dir.to_path_buf());
+        self.session_index = session_index;
+        self.clock = clock.clone();
+
+        tracing::info!(
+            "System audio channel initialized: {} ({}Hz, {}ch)",
+            sink,
+            SAMPLE_RATE,
+            CHANNELS
+        );
+        Ok(())
+    }
+
+    async fn start(&mut self) -> RecordingResult<()> {
+        if self.is_recording.load(Ordering::SeqCst) {
+            return Err(RecordingError::AlreadyRecording);
+        }
+
+        let output_dir = self.output_dir.clone().ok_or_else(|| {
+            RecordingError::ConfigurationError("Output directory not set".to_string())
+        })?;
+
+        let monitor_source = self.monitor_source.clone().ok_or_else(|| {
+            RecordingError::ConfigurationError("Monitor source not set".to_string())
+        })?;
+
+        // Record raw interleaved f32le from the monitor source
+        let mut process = Command::new("parec")
+            .args([
+                "--raw",
+                &format!("--device={}", monitor_source),
+                "--format=float32le",
+                &format!("--rate={}", SAMPLE_RATE),
+                &format!("--channels={}", CHANNELS),
+                "--client-name=Open ScreenStudio",
+                "--stream-name=System Audio",
+            ])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| RecordingError::CaptureError(format!("Failed to start parec: {}", e)))?;
+
+        let mut stdout = process.stdout.take().ok_or_else(|| {
+            RecordingError::CaptureError("Failed to open parec output".to_string())
+        })?;
+
+        // Create encoder
+        let encoder = match AudioEncoder::new(
+            self.encoders.as_ref(),
+            SAMPLE_RATE,
+            CHANNELS,
+            &output_dir,
+            self.session_index,
+            "system",
+            self.format,
+        ) {
+            Ok(encoder) => Arc::new(
+                encoder
+                    .with_clock(self.clock.clone(), &self.id)
+                    .with_error_reporter(self.error_reporter.clone())
+                    .with_health(self.health.clone())
+                    .with_levels(&self.id),
+            ),
+            Err(e) => {
+                let _ = process.kill();
+                let _ = process.wait();
+                return Err(RecordingError::CaptureError(format!(
+                    "Failed to start audio encoder: {}",
+                    e
+                )));
+            }
+        };
+        *self.encoder.lock() = Some(encoder.clone());
+        *self.process.lock() = Some(process);
+
+        self.is_recording.store(true, Ordering::SeqCst);
+        self.sample_count.store(0, Ordering::SeqCst);
+
+        let is_recording = self.is_recording.clone();
+        let sample_count = self.sample_count.clone();
+        let reporter = self.error_reporter.clone();
+
+        // Pump samples from parec into the encoder until parec is killed on stop
+        let handle = std::thread::spawn(move || {
+            // 20ms of 48kHz stereo f32
+            let mut buf = vec![0u8; (SAMPLE_RATE as usize / 50) * FRAME_BYTES];
+            // Bytes of a frame split across reads, kept at the start of `buf`
+            let mut partial = 0;
+
+            tracing::info!("System audio monitor stream started");
+
+            loop {
+                match stdout.read(&mut buf[partial..]) {
+                    Ok(0) => break,
+                    Ok(n) => {
+                        // Only whole frames go to the encoder, or the channels swap
+                        let filled = partial + n;
+                        let whole = filled - filled % FRAME_BYTES;
+                        if is_recording.load(Ordering::Relaxed) && whole > 0 {
+                            encoder.write_samples(&buf[..whole]);
+                            sample_count.fetch_add((whole / 4) as u64, Ordering::Relaxed);
+                        }
+                        buf.copy_within(whole..filled, 0);
+                        partial = filled - whole;
+                    }
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        reporter.report(format!("System audio stream error: {}", e));
+                        break;
+                    }
+                }
+            }
+
+            // parec only exits early if the sink went away
+            if is_recording.load(Ordering::SeqCst) {
+                reporter.report("System audio monitor closed while recording");
+            }
+
+            tracing::info!("System audio stream stopped");
+        });
+
+        *self.stream_handle.lock() = Some(handle);
+
+        tracing::info!("System audio capture started from {}", monitor_source);
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> RecordingResult<()> {
+        if !self.is_recording.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        self.is_recording.store(false, Ordering::SeqCst);
+
+        // Stop parec; this closes its stdout and ends the reader thread
+        if let Some(mut process) = self.process.lock().take() {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+
+        if let Some(handle) = self.stream_handle.lock().take() {
+            let _ = handle.join();
+        }
+
+        // Finish encoding
+        if let Some(ref encoder) = *self.encoder.lock() {
+            if let Ok(Some(output_file)) = encoder.finish() {
+                tracing::info!(
+                    "System audio encoding finished: {} samples, output: {}",
+                    self.sample_count.load(Ordering::SeqCst),
+                    output_file
+                );
+                self.output_files.lock().push(output_file);
+            }
+        }
+        *self.encoder.lock() = None;
+
+        tracing::info!("System audio capture stopped");
+        Ok(())
+    }
+
+    async fn pause(&mut self) -> RecordingResult<()> {
+        self.stop().await
+    }
+
+    async fn resume(&mut self, session_index: usize) -> RecordingResult<()> {
+        self.session_index = session_index;
+        self.start().await
+    }
+
+    fn is_recording(&self) -> bool {
+        self.is_recording.load(Ordering::SeqCst)
+    }
+
+    fn output_files(&self) -> Vec<String> {
+        self.output_files.lock().clone()
+    }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// Records a null sink; needs a PulseAudio or PipeWire server and FFmpeg
+    #[tokio::test]
+    #[ignore]
+    async fn test_records_a_null_sink() {
+        let output = Command::new("pactl")
+            .args([
+                "load-module",
+                "module-null-sink",
+                "sink_name=open_screenstudio_test",
+            ])
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "failed to load module-null-sink");
+        let module = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut channel =
+            SystemAudioCaptureChannel::new(Some("open_screenstudio_test".to_string()));
+        let result = async {
+            channel
+                .initialize(dir.path(), 0, &RecordingClock::default())
+                .await?;
+            channel.start().await?;
+            tokio::time::sleep(Duration::from_millis(500)).await;
+            channel.stop().await
+        }
+        .await;
+        let _ = Command::new("pactl")
+            .args(["unload-module", &module])
+            .status();
+        result.unwrap();
+
+        let files = channel.output_files();
+        assert_eq!(files.len(), 1);
+        let path = Path::new(&files[0]);
+        let name = path.file_name().unwrap().to_string_lossy();
+        assert!(name.starts_with("recording-0-system."), "{}", name);
+        assert!(path.metadata().unwrap().len() > 0);
+    }
+}
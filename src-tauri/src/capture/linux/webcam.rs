@@ -0,0 +1,793 @@
+//! Linux webcam capture using Video4Linux2
+//!
+//! Cameras and their frame sizes are enumerated with V4L2 ioctls. FFmpeg's
+//! v4l2 demuxer reads the camera and pipes raw BGRA frames back, which are
+//! paced and encoded like display frames: through the channel's
+//! `EncoderBackend`, with the recording's `VideoSettings`.
+//!
+//! V4L2 buffers carry monotonic timestamps, which FFmpeg passes through
+//! unless told otherwise. With `-timestamps abs` it converts them to
+//! wall-clock time, so the start time it reports for the input is the Unix
+//! time of the first frame. The video starts at that frame, and its time is
+//! written to a `recording-N-webcam.json` sidecar, which lets the editor line
+//! the camera up with the other channels.
+//!
+//! Can be tested without hardware through v4l2loopback:
+//!
+//! ```text
+//! modprobe v4l2loopback video_nr=10
+//! ffmpeg -re -f lavfi -i testsrc=size=1280x720:rate=30 -pix_fmt yuyv422 -f v4l2 /dev/video10
+//! ```
+
+use crate::capture::encoder::{ffmpeg_backend, EncoderBackend, VideoOutput, VideoSpec};
+use crate::capture::frames::{FramePacer, FrameTimeline};
+use crate::capture::media_tools::{self, MediaTool};
+use crate::capture::traits::{CameraInfo, Resolution};
+use crate::capture::video::VideoSettings;
+use crate::recorder::channel::{
+    ChannelErrorReporter, ChannelType, RecordingChannel, RecordingError, RecordingResult,
+};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
+use async_trait::async_trait;
+use parking_lot::Mutex as ParkingMutex;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::fs::OpenOptionsExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Preferred capture size when the camera supports it
+const PREFERRED_RESOLUTION: (u32, u32) = (1280, 720);
+
+/// Common sizes offered for cameras that report stepwise/continuous ranges
+const COMMON_RESOLUTIONS: [(u32, u32); 6] = [
+    (640, 480),
+    (800, 600),
+    (1280, 720),
+    (1920, 1080),
+    (2560, 1440),
+    (3840, 2160),
+];
+
+// =============================================================================
+// V4L2 ioctl definitions (linux/videodev2.h)
+// =============================================================================
+
+const V4L2_CAP_VIDEO_CAPTURE: u32 = 0x0000_0001;
+const V4L2_CAP_DEVICE_CAPS: u32 = 0x8000_0000;
+const V4L2_BUF_TYPE_VIDEO_CAPTURE: u32 = 1;
+const V4L2_FRMSIZE_TYPE_DISCRETE: u32 = 1;
+
+#[repr(C)]
+struct V4l2Capability {
+    driver: [u8; 16],
+    card: [u8; 32],
+    bus_info: [u8; 32],
+    version: u32,
+    capabilities: u32,
+    device_caps: u32,
+    reserved: [u32; 3],
+}
+
+#[repr(C)]
+struct V4l2FmtDesc {
+    index: u32,
+    type_: u32,
+    flags: u32,
+    description: [u8; 32],
+    pixelformat: u32,
+    mbus_code: u32,
+    reserved: [u32; 3],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct V4l2FrmSizeStepwise {
+    min_width: u32,
+    max_width: u32,
+    step_width: u32,
+    min_height: u32,
+    max_height: u32,
+    step_height: u32,
+}
+
+#[repr(C)]
+struct V4l2FrmSizeEnum {
+    index: u32,
+    pixel_format: u32,
+    type_: u32,
+    // Union of v4l2_frmsize_discrete (width, height) and v4l2_frmsize_stepwise;
+    // the discrete variant shares its layout with the first two fields.
+    stepwise: V4l2FrmSizeStepwise,
+    reserved: [u32; 2],
+}
+
+const fn ioc(dir: u32, nr: u32, size: usize) -> u32 {
+    (dir << 30) | ((size as u32) << 16) | ((b'V' as u32) << 8) | nr
+}
+
+const IOC_READ: u32 = 2;
+const IOC_READ_WRITE: u32 = 3;
+const VIDIOC_QUERYCAP: u32 = ioc(IOC_READ, 0, std::mem::size_of::<V4l2Capability>());
+const VIDIOC_ENUM_FMT: u32 = ioc(IOC_READ_WRITE, 2, std::mem::size_of::<V4l2FmtDesc>());
+const VIDIOC_ENUM_FRAMESIZES: u32 = ioc(IOC_READ_WRITE, 74, std::mem::size_of::<V4l2FrmSizeEnum>());
+
+/// Issue an ioctl, retrying on EINTR
+fn xioctl<T>(fd: i32, request: u32, arg: &mut T) -> bool {
+    loop {
+        let ret = unsafe { libc::ioctl(fd, request as _, arg as *mut T) };
+        if ret != -1 {
+            return true;
+        }
+        if std::io::Error::last_os_error().raw_os_error() != Some(libc::EINTR) {
+            return false;
+        }
+    }
+}
+
+fn c_string(bytes: &[u8]) -> String {
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    String::from_utf8_lossy(&bytes[..len]).trim().to_string()
+}
+
+/// Query a device node; returns its card name if it is a video capture device
+fn query_capture_device(fd: i32) -> Option<String> {
+    let mut cap: V4l2Capability = unsafe { std::mem::zeroed() };
+    if !xioctl(fd, VIDIOC_QUERYCAP, &mut cap) {
+        return None;
+    }
+
+    // Metadata nodes share the driver's capabilities, device_caps describes this node
+    let caps = if cap.capabilities & V4L2_CAP_DEVICE_CAPS != 0 {
+        cap.device_caps
+    } else {
+        cap.capabilities
+    };
+
+    if caps & V4L2_CAP_VIDEO_CAPTURE == 0 {
+        return None;
+    }
+
+    Some(c_string(&cap.card))
+}
+
+/// Enumerate the frame sizes supported across all pixel formats, largest first
+fn query_resolutions(fd: i32) -> Vec<Resolution> {
+    let mut sizes: Vec<(u32, u32)> = Vec::new();
+
+    for fmt_index in 0.. {
+        let mut fmt: V4l2FmtDesc = unsafe { std::mem::zeroed() };
+        fmt.index = fmt_index;
+        fmt.type_ = V4L2_BUF_TYPE_VIDEO_CAPTURE;
+        if !xioctl(fd, VIDIOC_ENUM_FMT, &mut fmt) {
+            break;
+        }
+
+        for size_index in 0.. {
+            let mut frmsize: V4l2FrmSizeEnum = unsafe { std::mem::zeroed() };
+            frmsize.index = size_index;
+            frmsize.pixel_format = fmt.pixelformat;
+            if !xioctl(fd, VIDIOC_ENUM_FRAMESIZES, &mut frmsize) {
+                break;
+            }
+
+            let range = frmsize.stepwise;
+            if frmsize.type_ == V4L2_FRMSIZE_TYPE_DISCRETE {
+                // Discrete: width/height alias the first two stepwise fields
+                sizes.push((range.min_width, range.max_width));
+            } else {
+                // Stepwise/continuous: offer the common sizes within range
+                sizes.extend(COMMON_RESOLUTIONS.iter().copied().filter(|&(w, h)| {
+                    w >= range.min_width
+                        && w <= range.max_width
+                        && h >= range.min_height
+                        && h <= range.max_height
+                }));
+                break;
+            }
+        }
+    }
+
+    sizes.sort_by_key(|&(w, h)| std::cmp::Reverse(w as u64 * h as u64));
+    sizes.dedup();
+
+    sizes
+        .into_iter()
+        .map(|(width, height)| Resolution { width, height })
+        .collect()
+}
+
+/// Get list of available cameras
+pub fn get_cameras() -> Vec<CameraInfo> {
+    let mut nodes: Vec<PathBuf> = match std::fs::read_dir("/sys/class/video4linux") {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("video"))
+            .map(|name| Path::new("/dev").join(name))
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+    nodes.sort_by_key(|path| {
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.trim_start_matches("video").parse::<u32>().ok())
+            .unwrap_or(u32::MAX)
+    });
+
+    nodes
+        .into_iter()
+        .filter_map(|path| {
+            let file = std::fs::OpenOptions::new()
+                .read(true)
+                .write(true)
+                // Don't block if another process is streaming from the device
+                .custom_flags(libc::O_NONBLOCK)
+                .open(&path)
+                .ok()?;
+            let fd = file.as_raw_fd();
+
+            let name = query_capture_device(fd)?;
+            Some(CameraInfo {
+                id: path.to_string_lossy().to_string(),
+                name,
+                supported_resolutions: query_resolutions(fd),
+            })
+        })
+        .collect()
+}
+
+/// Pick the capture size: 720p if available, else the largest size up to 1080p
+fn choose_resolution(supported: &[Resolution]) -> Option<Resolution> {
+    supported
+        .iter()
+        .find(|r| (r.width, r.height) == PREFERRED_RESOLUTION)
+        .or_else(|| {
+            supported
+                .iter()
+                .find(|r| r.width <= 1920 && r.height <= 1080)
+        })
+        .or_else(|| supported.last())
+        .cloned()
+}
+
+/// Timing information written next to each webcam video
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WebcamSessionInfo {
+    /// Device the session was recorded from
+    pub device_id: String,
+
+    /// Capture width in pixels
+    pub width: u32,
+
+    /// Capture height in pixels
+    pub height: u32,
+
+    /// Unix time when the channel was started
+    pub start_unix_ms: f64,
+
+    /// Unix time of the first captured frame (if FFmpeg reported it)
+    pub first_frame_unix_ms: Option<f64>,
+
+    /// Delay between channel start and the first frame in the video
+    pub start_offset_ms: f64,
+}
+
+fn now_unix_ms() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+        * 1000.0
+}
+
+/// Parse the input start time from an FFmpeg banner line such as
+/// `  Duration: N/A, start: 1700000000.123456, bitrate: 147456 kb/s`
+fn parse_input_start_secs(line: &str) -> Option<f64> {
+    let rest = line.split("start: ").nth(1)?;
+    rest.split(',').next()?.trim().parse().ok()
+}
+
+/// Read FFmpeg's log up to its output section, returning the input start
+/// time in seconds if it was logged
+///
+/// FFmpeg describes the input once the camera is open, before the first frame
+/// is passed on. If the log ends first, the camera couldn't be opened and the
+/// error is the last error line.
+fn read_input_start(lines: &mut impl Iterator<Item = String>) -> Result<Option<f64>, String> {
+    let mut in_input_section = false;
+    let mut start_secs = None;
+    let mut last_error = None;
+    for line in lines {
+        if line.starts_with("Input #0") {
+            in_input_section = true;
+        } else if line.starts_with("Output #0") {
+            return Ok(start_secs);
+        } else if in_input_section && start_secs.is_none() {
+            start_secs = parse_input_start_secs(&line);
+        }
+        if line.contains("Error") || line.contains("error") {
+            last_error = Some(line);
+        }
+    }
+    Err(last_error.unwrap_or_else(|| "FFmpeg exited before the camera opened".to_string()))
+}
+
+/// Webcam capture channel for Linux (V4L2)
+pub struct WebcamCaptureChannel {
+    id: String,
+    device_id: Option<String>,
+    device_path: Option<String>,
+    resolution: Option<Resolution>,
+    is_recording: Arc<AtomicBool>,
+    output_dir: Option<PathBuf>,
+    session_index: usize,
+    clock: RecordingClock,
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
+    output_files: Arc<ParkingMutex<Vec<String>>>,
+    video: VideoSettings,
+    encoders: Arc<dyn EncoderBackend>,
+    encoder: Option<Arc<VideoOutput>>,
+    /// Size of the frames FFmpeg pipes back
+    frame_size: (u32, u32),
+    process: ParkingMutex<Option<Child>>,
+    capture_handle: Option<tokio::task::JoinHandle<FrameTimeline>>,
+    stderr_handle: Option<std::thread::JoinHandle<()>>,
+    start_unix_ms: f64,
+    first_frame_unix_ms: Option<f64>,
+}
+
+impl WebcamCaptureChannel {
+    /// Create a new webcam capture channel
+    /// If device_id is None, uses the first available camera
+    pub fn new(device_id: Option<String>) -> Self {
+        Self {
+            id: "webcam".to_string(),
+            device_id,
+            device_path: None,
+            resolution: None,
+            is_recording: Arc::new(AtomicBool::new(false)),
+            output_dir: None,
+            session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
+            output_files: Arc::new(ParkingMutex::new(Vec::new())),
+            video: VideoSettings::default(),
+            encoders: ffmpeg_backend(),
+            encoder: None,
+            frame_size: PREFERRED_RESOLUTION,
+            process: ParkingMutex::new(None),
+            capture_handle: None,
+            stderr_handle: None,
+            start_unix_ms: 0.0,
+            first_frame_unix_ms: None,
+        }
+    }
+
+    /// Set the frame rate and encoding settings
+    pub fn with_video_settings(mut self, video: VideoSettings) -> Self {
+        self.video = video;
+        self
+    }
+
+    /// Encode through another backend (FFmpeg by default)
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
+    fn session_basename(&self) -> String {
+        format!("recording-{}-webcam", self.session_index)
+    }
+}
+
+#[async_trait]
+impl RecordingChannel for WebcamCaptureChannel {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Webcam
+    }
+
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
+        self.encoders.check_video(&self.video)?;
+        // FFmpeg reads the camera whichever backend encodes
+        media_tools::require(MediaTool::Ffmpeg)?;
+
+        let cameras = get_cameras();
+        let camera = match &self.device_id {
+            Some(id) => cameras.iter().find(|c| &c.id == id).ok_or_else(|| {
+                RecordingError::DeviceNotFound(format!("Camera '{}' not found", id))
+            })?,
+            None => cameras
+                .first()
+                .ok_or_else(|| RecordingError::DeviceNotFound("No camera found".to_string()))?,
+        };
+
+        self.device_path = Some(camera.id.clone());
+        self.resolution = choose_resolution(&camera.supported_resolutions);
+        // Frames are scaled to a known size so they can be read off the pipe
+        self.frame_size = self
+            .resolution
+            .as_ref()
+            .map(|r| (r.width, r.height))
+            .unwrap_or(PREFERRED_RESOLUTION);
+        self.output_dir = Some(output_dir.to_path_buf());
+        self.session_index = session_index;
+        self.clock = clock.clone();
+
+        tracing::info!(
+            "Webcam channel initialized: {} ({}) at {}",
+            camera.name,
+            camera.id,
+            self.resolution
+                .as_ref()
+                .map(|r| format!("{}x{}", r.width, r.height))
+                .unwrap_or_else(|| "device default size".to_string())
+        );
+        Ok(())
+    }
+
+    async fn start(&mut self) -> RecordingResult<()> {
+        if self.is_recording.load(Ordering::SeqCst) {
+            return Err(RecordingError::AlreadyRecording);
+        }
+
+        let output_dir = self.output_dir.clone().ok_or_else(|| {
+            RecordingError::ConfigurationError("Output directory not set".to_string())
+        })?;
+        let device_path = self.device_path.clone().ok_or_else(|| {
+            RecordingError::ConfigurationError("Camera device not set".to_string())
+        })?;
+
+        std::fs::create_dir_all(&output_dir)?;
+        let (width, height) = self.frame_size;
+
+        let mut args: Vec<String> = vec![
+            "-nostats".into(),
+            "-f".into(),
+            "v4l2".into(),
+            "-thread_queue_size".into(),
+            "512".into(),
+            // Wall-clock frame times, which the clock can convert
+            "-timestamps".into(),
+            "abs".into(),
+        ];
+        if let Some(ref resolution) = self.resolution {
+            args.push("-video_size".into());
+            args.push(format!("{}x{}", resolution.width, resolution.height));
+        }
+        let frame_size = format!("{}x{}", width, height);
+        args.extend(
+            [
+                "-i",
+                &device_path,
+                "-f",
+                "rawvideo",
+                "-pix_fmt",
+                "bgra",
+                "-s",
+                &frame_size,
+                "-",
+            ]
+            .iter()
+            .map(|s| s.to_string()),
+        );
+
+        self.start_unix_ms = now_unix_ms();
+        let mut process = media_tools::require(MediaTool::Ffmpeg)?
+            .args(&args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| RecordingError::CaptureError(format!("Failed to start FFmpeg: {}", e)))?;
+        let (Some(mut stdout), Some(stderr)) = (process.stdout.take(), process.stderr.take())
+        else {
+            let _ = process.kill();
+            let _ = process.wait();
+            return Err(RecordingError::CaptureError(
+                "Failed to read from FFmpeg".to_string(),
+            ));
+        };
+
+        // Wait for the camera to open, learning when its first frame was taken
+        let mut lines = BufReader::new(stderr).lines().map_while(Result::ok);
+        let start_secs = match read_input_start(&mut lines) {
+            Ok(start_secs) => start_secs,
+            Err(message) => {
+                let _ = process.kill();
+                let _ = process.wait();
+                return Err(RecordingError::CaptureError(format!(
+                    "Failed to open camera {}: {}",
+                    device_path, message
+                )));
+            }
+        };
+        self.first_frame_unix_ms = start_secs.map(|secs| secs * 1000.0);
+
+        let spec = VideoSpec {
+            width,
+            height,
+            settings: self.video.clone(),
+            output_file: self
+                .video
+                .output_file(&output_dir, &self.session_basename()),
+        };
+        let encoder = match self.encoders.video_encoder(&spec) {
+            Ok(encoder) => {
+                Arc::new(VideoOutput::new(encoder).with_error_reporter(self.error_reporter.clone()))
+            }
+            Err(e) => {
+                let _ = process.kill();
+                let _ = process.wait();
+                return Err(RecordingError::CaptureError(format!(
+                    "Failed to start encoder: {}",
+                    e
+                )));
+            }
+        };
+
+        // The video starts with the camera's first frame
+        let start_ms = match self.first_frame_unix_ms {
+            Some(unix_ms) => self.clock.ms_from_unix(unix_ms),
+            None => self.clock.now_ms(),
+        };
+        let mut pacer = FramePacer::starting_at(
+            self.video.fps,
+            &self.clock,
+            &self.id,
+            self.session_index,
+            start_ms,
+        )
+        .with_health(self.health.clone());
+
+        let frame_encoder = encoder.clone();
+        let capture_handle = tokio::task::spawn_blocking(move || {
+            let mut frame = vec![0u8; width as usize * height as usize * 4];
+            // FFmpeg's stdout closes when it is stopped or the camera goes away
+            while stdout.read_exact(&mut frame).is_ok() {
+                pacer.push(&frame, |frame| frame_encoder.write_frame(frame));
+            }
+            pacer.finish(|frame| frame_encoder.write_frame(frame))
+        });
+
+        // Keep draining FFmpeg's log so the pipe never fills up and stalls it
+        let is_recording = self.is_recording.clone();
+        let reporter = self.error_reporter.clone();
+        let stderr_handle = std::thread::spawn(move || {
+            let mut last_error = None;
+            for line in lines {
+                if line.contains("Error") || line.contains("error") {
+                    tracing::warn!("Webcam FFmpeg: {}", line);
+                    last_error = Some(line);
+                }
+            }
+
+            // The log only ends before stop if FFmpeg died (e.g. the camera was unplugged)
+            if is_recording.load(Ordering::SeqCst) {
+                reporter.report(match last_error {
+                    Some(line) => format!("Webcam FFmpeg exited: {}", line),
+                    None => "Webcam FFmpeg exited while recording".to_string(),
+                });
+            }
+        });
+
+        *self.process.lock() = Some(process);
+        self.encoder = Some(encoder);
+        self.capture_handle = Some(capture_handle);
+        self.stderr_handle = Some(stderr_handle);
+        self.is_recording.store(true, Ordering::SeqCst);
+
+        tracing::info!(
+            "Webcam capture started: {} ({} @ {}fps)",
+            device_path,
+            frame_size,
+            self.video.fps
+        );
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> RecordingResult<()> {
+        if !self.is_recording.load(Ordering::SeqCst) {
+            return Err(RecordingError::NotRecording);
+        }
+
+        self.is_recording.store(false, Ordering::SeqCst);
+
+        // FFmpeg only reads the camera, so there is nothing for it to finalize
+        if let Some(mut process) = self.process.lock().take() {
+            let _ = process.kill();
+            let _ = process.wait();
+        }
+
+        let timeline = match self.capture_handle.take() {
+            Some(handle) => handle.await.ok(),
+            None => None,
+        };
+        if let Some(handle) = self.stderr_handle.take() {
+            let _ = handle.join();
+        }
+
+        if let Some(ref encoder) = self.encoder {
+            let files = encoder.finish().map_err(|e| {
+                RecordingError::CaptureError(format!("Failed to finish encoding: {}", e))
+            })?;
+            self.output_files.lock().extend(files);
+        }
+        self.encoder = None;
+
+        let output_dir = self.output_dir.clone().ok_or_else(|| {
+            RecordingError::ConfigurationError("Output directory not set".to_string())
+        })?;
+        let base = self.session_basename();
+
+        if let Some(timeline) = timeline {
+            tracing::info!(
+                "Webcam frame timing: {} captured, {} dropped, {} encoded",
+                timeline.frames.len(),
+                timeline.dropped_count(),
+                timeline.output_frame_count
+            );
+            let sidecar = timeline.write_sidecar(&output_dir, &base)?;
+            self.output_files.lock().push(sidecar);
+        }
+
+        let (width, height) = self.frame_size;
+        let info = WebcamSessionInfo {
+            device_id: self.device_path.clone().unwrap_or_default(),
+            width,
+            height,
+            start_unix_ms: self.start_unix_ms,
+            first_frame_unix_ms: self.first_frame_unix_ms,
+            start_offset_ms: self
+                .first_frame_unix_ms
+                .map(|t| (t - self.start_unix_ms).max(0.0))
+                .unwrap_or(0.0),
+        };
+
+        let info_path = output_dir.join(format!("{}.json", base));
+        let data = serde_json::to_vec_pretty(&info).map_err(std::io::Error::other)?;
+        std::fs::write(&info_path, data)?;
+        self.output_files
+            .lock()
+            .push(info_path.to_string_lossy().to_string());
+
+        tracing::info!("Webcam capture stopped");
+        Ok(())
+    }
+
+    async fn pause(&mut self) -> RecordingResult<()> {
+        self.stop().await
+    }
+
+    async fn resume(&mut self, session_index: usize) -> RecordingResult<()> {
+        self.session_index = session_index;
+        self.start().await
+    }
+
+    fn is_recording(&self) -> bool {
+        self.is_recording.load(Ordering::SeqCst)
+    }
+
+    fn output_files(&self) -> Vec<String> {
+        self.output_files.lock().clone()
+    }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ioctl_numbers_match_videodev2() {
+        assert_eq!(VIDIOC_QUERYCAP, 0x8068_5600);
+        assert_eq!(VIDIOC_ENUM_FMT, 0xC040_5602);
+        assert_eq!(VIDIOC_ENUM_FRAMESIZES, 0xC02C_564A);
+    }
+
+    #[test]
+    fn test_parse_input_start() {
+        let line = "  Duration: N/A, start: 1700000000.123456, bitrate: 147456 kb/s";
+        assert_eq!(parse_input_start_secs(line), Some(1700000000.123456));
+        assert_eq!(
+            parse_input_start_secs("  Stream #0:0: Video: rawvideo"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_read_input_start() {
+        let log = [
+            "Input #0, video4linux2,v4l2, from '/dev/video0':",
+            "  Duration: N/A, start: 1700000000.250000, bitrate: 147456 kb/s",
+            "  Stream #0:0: Video: rawvideo (YUY2 / 0x32595559), yuyv422, 1280x720",
+            "Output #0, rawvideo, to 'pipe:':",
+            "  Duration: N/A, start: 0.000000, bitrate: 884736 kb/s",
+        ];
+        let mut lines = log.iter().map(|s| s.to_string());
+        assert_eq!(read_input_start(&mut lines), Ok(Some(1700000000.25)));
+        // The rest of the log is left for the drain thread
+        assert_eq!(lines.count(), 1);
+
+        let failed = [
+            "[video4linux2,v4l2 @ 0x55d0] Cannot open video device /dev/video9: No such file or directory",
+            "/dev/video9: No such file or directory",
+            "Error opening input file /dev/video9.",
+        ];
+        let mut lines = failed.iter().map(|s| s.to_string());
+        assert_eq!(
+            read_input_start(&mut lines),
+            Err("Error opening input file /dev/video9.".to_string())
+        );
+    }
+
+    #[test]
+    fn test_input_start_is_on_the_recording_clock() {
+        let clock = RecordingClock::new();
+        let start_secs = (clock.start_unix_ms() + 250) as f64 / 1000.0;
+        let line = format!(
+            "  Duration: N/A, start: {:.6}, bitrate: 147456 kb/s",
+            start_secs
+        );
+
+        let unix_ms = parse_input_start_secs(&line).unwrap() * 1000.0;
+        assert!((clock.ms_from_unix(unix_ms) - 250.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_choose_resolution_prefers_720p() {
+        let supported = vec![
+            Resolution {
+                width: 1920,
+                height: 1080,
+            },
+            Resolution {
+                width: 1280,
+                height: 720,
+            },
+            Resolution {
+                width: 640,
+                height: 480,
+            },
+        ];
+        let chosen = choose_resolution(&supported).unwrap();
+        assert_eq!((chosen.width, chosen.height), (1280, 720));
+
+        let supported = vec![
+            Resolution {
+                width: 3840,
+                height: 2160,
+            },
+            Resolution {
+                width: 1920,
+                height: 1080,
+            },
+        ];
+        let chosen = choose_resolution(&supported).unwrap();
+        assert_eq!((chosen.width, chosen.height), (1920, 1080));
+    }
+}
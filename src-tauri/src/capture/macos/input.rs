@@ -1,6 +1,10 @@
-use crate::capture::input::types::{CursorInfo, MouseClick, MouseMove};
+use crate::capture::input::types::{CursorInfo, KeyEvent, MouseClick, MouseMove, MouseScroll};
 use crate::recorder::channel::RecordingResult;
+use core_foundation::runloop::{kCFRunLoopCommonModes, kCFRunLoopDefaultMode, CFRunLoop};
 use core_graphics::display::CGDisplay;
+use core_graphics::event::{
+    CGEventTap, CGEventTapLocation, CGEventTapOptions, CGEventTapPlacement, CGEventType, EventField,
+};
 use objc2::rc::Retained;
 use objc2_app_kit::{NSBitmapImageFileType, NSBitmapImageRep, NSCursor, NSEvent, NSImage};
 use objc2_foundation::{NSDictionary, NSString};
@@ -18,11 +22,22 @@ use std::time::{Duration, Instant};
 /// This implementation uses polling for mouse moves at a fixed interval.
 /// Click detection is currently best-effort via NSEvent modifier flags and mouse state.
 ///
+/// Scroll events can't be polled, so they come from a listen-only CGEventTap
+/// running on its own thread (requires the Accessibility or Input Monitoring
+/// permission; scrolling is simply not recorded without it).
+///
+/// Keystrokes are polled the same way as the mouse, through `CGEventSourceKeyState`, when
+/// `track_keys` is set (requires the Input Monitoring permission). Key names
+/// assume an ANSI (US) layout.
+///
 /// Note: A full CGEventTap-based implementation may require additional FFI.
 pub fn start_input_tracking(
     is_recording: Arc<AtomicBool>,
     mouse_moves: Arc<ParkingMutex<Vec<MouseMove>>>,
     mouse_clicks: Arc<ParkingMutex<Vec<MouseClick>>>,
+    mouse_scrolls: Arc<ParkingMutex<Vec<MouseScroll>>>,
+    keystrokes: Arc<ParkingMutex<Vec<KeyEvent>>>,
+    track_keys: bool,
     cursors: Arc<ParkingMutex<HashMap<String, CursorInfo>>>,
     cursors_dir: PathBuf,
     start_time: Instant,
@@ -50,6 +65,15 @@ pub fn start_input_tracking(
         display_id, display_origin_x, display_origin_y, display_height, scale_factor
     );
 
+    let scroll_handle = start_scroll_tap(
+        is_recording.clone(),
+        mouse_scrolls,
+        start_time,
+        unix_ms_fn,
+        (display_origin_x, display_origin_y),
+        scale_factor,
+    );
+
     let handle = std::thread::spawn(move || {
         tracing::info!(
             "macOS input tracking started (poll_interval={:?})",
@@ -58,6 +82,7 @@ pub fn start_input_tracking(
 
         let mut last_left_down = false;
         let mut last_right_down = false;
+        let mut keys_down = [false; VIRTUAL_KEY_COUNT];
         // Track which cursor hashes we've already saved to avoid duplicates
         let mut saved_cursor_hashes: HashSet<u64> = HashSet::new();
 
@@ -160,18 +185,315 @@ pub fn start_input_tracking(
                 last_right_down = right_down;
             }
 
+            if track_keys {
+                for (key_code, was_down) in keys_down.iter_mut().enumerate() {
+                    let is_down =
+                        unsafe { CGEventSourceKeyState(COMBINED_SESSION_STATE, key_code as u16) };
+                    if is_down == *was_down {
+                        continue;
+                    }
+                    *was_down = is_down;
+
+                    let shift = modifiers.iter().any(|m| m == "shift");
+                    let Some((key, character)) = key_from_virtual_keycode(key_code as u16, shift)
+                    else {
+                        continue;
+                    };
+
+                    keystrokes.lock().push(KeyEvent {
+                        key_code: key_code as u32,
+                        key,
+                        character,
+                        event_type: if is_down { "down".to_string() } else { "up".to_string() },
+                        active_modifiers: modifiers.clone(),
+                        process_time_ms: start_time.elapsed().as_secs_f64() * 1000.0,
+                        unix_time_ms: unix_ms_fn(),
+                    });
+                }
+            }
+
             let elapsed = loop_start.elapsed();
             if elapsed < poll_interval {
                 std::thread::sleep(poll_interval - elapsed);
             }
         }
 
+        let _ = scroll_handle.join();
         tracing::info!("macOS input tracking thread stopped");
     });
 
     Ok(handle)
 }
 
+/// kCGScrollWheelEventScrollPhase
+const SCROLL_PHASE_FIELD: u32 = 99;
+
+/// kCGScrollWheelEventMomentumPhase
+const MOMENTUM_PHASE_FIELD: u32 = 123;
+
+/// Record scroll wheel events through a listen-only event tap until recording stops
+///
+/// Event locations are global Quartz coordinates (top-left origin, points),
+/// so they only need the display origin and the Retina scale applied.
+fn start_scroll_tap(
+    is_recording: Arc<AtomicBool>,
+    mouse_scrolls: Arc<ParkingMutex<Vec<MouseScroll>>>,
+    start_time: Instant,
+    unix_ms_fn: fn() -> u64,
+    display_origin: (f64, f64),
+    scale_factor: f64,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let tap = CGEventTap::new(
+            CGEventTapLocation::Session,
+            CGEventTapPlacement::HeadInsertEventTap,
+            CGEventTapOptions::ListenOnly,
+            vec![CGEventType::ScrollWheel],
+            move |_proxy, _event_type, event| {
+                let location = event.location();
+                let continuous =
+                    event.get_integer_value_field(EventField::SCROLL_WHEEL_EVENT_IS_CONTINUOUS) != 0;
+
+                // Deltas are positive when scrolling up/left; flip them to
+                // the DOM convention
+                let (delta_x, delta_y, delta_mode) = if continuous {
+                    (
+                        -event.get_double_value_field(
+                            EventField::SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_2,
+                        ) * scale_factor,
+                        -event.get_double_value_field(
+                            EventField::SCROLL_WHEEL_EVENT_POINT_DELTA_AXIS_1,
+                        ) * scale_factor,
+                        "pixel",
+                    )
+                } else {
+                    (
+                        -event.get_double_value_field(EventField::SCROLL_WHEEL_EVENT_DELTA_AXIS_2),
+                        -event.get_double_value_field(EventField::SCROLL_WHEEL_EVENT_DELTA_AXIS_1),
+                        "line",
+                    )
+                };
+
+                mouse_scrolls.lock().push(MouseScroll {
+                    x: (location.x - display_origin.0) * scale_factor,
+                    y: (location.y - display_origin.1) * scale_factor,
+                    delta_x,
+                    delta_y,
+                    delta_mode: delta_mode.to_string(),
+                    phase: scroll_phase(
+                        event.get_integer_value_field(SCROLL_PHASE_FIELD),
+                        event.get_integer_value_field(MOMENTUM_PHASE_FIELD),
+                    ),
+                    active_modifiers: modifiers_from_flags(unsafe {
+                        NSEvent::modifierFlags_class()
+                    }),
+                    process_time_ms: start_time.elapsed().as_secs_f64() * 1000.0,
+                    unix_time_ms: unix_ms_fn(),
+                });
+                None
+            },
+        );
+
+        let tap = match tap {
+            Ok(tap) => tap,
+            Err(()) => {
+                tracing::warn!("Failed to create scroll event tap - scrolling will not be recorded");
+                return;
+            }
+        };
+        let Ok(source) = tap.mach_port.create_runloop_source(0) else {
+            tracing::warn!("Failed to create run loop source for scroll event tap");
+            return;
+        };
+
+        unsafe {
+            CFRunLoop::get_current().add_source(&source, kCFRunLoopCommonModes);
+        }
+        tap.enable();
+
+        while is_recording.load(Ordering::Relaxed) {
+            CFRunLoop::run_in_mode(
+                unsafe { kCFRunLoopDefaultMode },
+                Duration::from_millis(100),
+                true,
+            );
+        }
+    })
+}
+
+/// Name the gesture phase of a continuous scroll event
+fn scroll_phase(scroll_phase: i64, momentum_phase: i64) -> Option<String> {
+    if momentum_phase != 0 {
+        return Some("momentum".to_string());
+    }
+
+    let phase = match scroll_phase {
+        1 => "began",
+        2 => "changed",
+        4 => "ended",
+        8 => "cancelled",
+        128 => "mayBegin",
+        _ => return None,
+    };
+    Some(phase.to_string())
+}
+
+/// Number of virtual key codes polled for keystrokes
+const VIRTUAL_KEY_COUNT: usize = 128;
+
+/// kCGEventSourceStateCombinedSessionState
+const COMBINED_SESSION_STATE: i32 = 0;
+
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGEventSourceKeyState(state_id: i32, key: u16) -> bool;
+}
+
+/// Map a virtual key code (ANSI layout) to a DOM-style key name and the
+/// character it types, if any
+fn key_from_virtual_keycode(key_code: u16, shift: bool) -> Option<(String, Option<String>)> {
+    let named = match key_code {
+        36 | 76 => "Enter",
+        48 => "Tab",
+        51 => "Backspace",
+        53 => "Escape",
+        54 | 55 => "Meta",
+        56 | 60 => "Shift",
+        57 => "CapsLock",
+        58 | 61 => "Alt",
+        59 | 62 => "Control",
+        63 => "Fn",
+        71 => "Clear",
+        114 => "Insert",
+        115 => "Home",
+        116 => "PageUp",
+        117 => "Delete",
+        119 => "End",
+        121 => "PageDown",
+        123 => "ArrowLeft",
+        124 => "ArrowRight",
+        125 => "ArrowDown",
+        126 => "ArrowUp",
+        122 => "F1",
+        120 => "F2",
+        99 => "F3",
+        118 => "F4",
+        96 => "F5",
+        97 => "F6",
+        98 => "F7",
+        100 => "F8",
+        101 => "F9",
+        109 => "F10",
+        103 => "F11",
+        111 => "F12",
+        105 => "F13",
+        107 => "F14",
+        113 => "F15",
+        _ => "",
+    };
+    if !named.is_empty() {
+        return Some((named.to_string(), None));
+    }
+
+    let base = match key_code {
+        0 => 'a',
+        1 => 's',
+        2 => 'd',
+        3 => 'f',
+        4 => 'h',
+        5 => 'g',
+        6 => 'z',
+        7 => 'x',
+        8 => 'c',
+        9 => 'v',
+        11 => 'b',
+        12 => 'q',
+        13 => 'w',
+        14 => 'e',
+        15 => 'r',
+        16 => 'y',
+        17 => 't',
+        18 => '1',
+        19 => '2',
+        20 => '3',
+        21 => '4',
+        22 => '6',
+        23 => '5',
+        24 => '=',
+        25 => '9',
+        26 => '7',
+        27 => '-',
+        28 => '8',
+        29 => '0',
+        30 => ']',
+        31 => 'o',
+        32 => 'u',
+        33 => '[',
+        34 => 'i',
+        35 => 'p',
+        37 => 'l',
+        38 => 'j',
+        39 => '\'',
+        40 => 'k',
+        41 => ';',
+        42 => '\\',
+        43 => ',',
+        44 => '/',
+        45 => 'n',
+        46 => 'm',
+        47 => '.',
+        49 => ' ',
+        50 => '`',
+        // Keypad
+        65 => '.',
+        67 => '*',
+        69 => '+',
+        75 => '/',
+        78 => '-',
+        81 => '=',
+        82..=89 => char::from(b'0' + (key_code - 82) as u8),
+        91 => '8',
+        92 => '9',
+        _ => return None,
+    };
+
+    let c = if shift && !(65..=92).contains(&key_code) {
+        shifted_ansi(base)
+    } else {
+        base
+    };
+    Some((c.to_string(), Some(c.to_string())))
+}
+
+/// Character typed with shift held on an ANSI layout
+fn shifted_ansi(c: char) -> char {
+    match c {
+        'a'..='z' => c.to_ascii_uppercase(),
+        '1' => '!',
+        '2' => '@',
+        '3' => '#',
+        '4' => '$',
+        '5' => '%',
+        '6' => '^',
+        '7' => '&',
+        '8' => '*',
+        '9' => '(',
+        '0' => ')',
+        '-' => '_',
+        '=' => '+',
+        '[' => '{',
+        ']' => '}',
+        '\\' => '|',
+        ';' => ':',
+        '\'' => '"',
+        ',' => '<',
+        '.' => '>',
+        '/' => '?',
+        '`' => '~',
+        _ => c,
+    }
+}
+
 /// Generate a stable cursor ID and hash based on image content.
 /// Returns (cursor_id, image_hash) where the hash is used for deduplication.
 fn cursor_id_and_hash(cursor: &Retained<NSCursor>) -> (String, u64) {
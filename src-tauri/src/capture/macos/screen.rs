@@ -1,17 +1,35 @@
 //! macOS screen capture using CGWindowListCreateImage
 //!
 //! This module provides screen capture functionality using Core Graphics.
-//! Frames are captured and encoded to H.264 segments using FFmpeg.
-
-use crate::capture::traits::DisplayInfo;
-use crate::recorder::channel::{ChannelType, RecordingChannel, RecordingError, RecordingResult};
+//! Frames are captured and encoded by the channel's `EncoderBackend`
+//! according to its `VideoSettings`.
+
+use crate::capture::encoder::{ffmpeg_backend, EncoderBackend, VideoOutput, VideoSpec};
+use crate::capture::frames::{FramePacer, FrameTimeline};
+use crate::capture::region::{crop_frame, resolve_crop, CaptureRegion};
+use crate::capture::traits::{CaptureTarget, DisplayInfo, Resolution, WindowBounds, WindowInfo};
+use crate::capture::video::VideoSettings;
+use crate::recorder::channel::{
+    ChannelErrorReporter, ChannelType, RecordingChannel, RecordingError, RecordingResult,
+};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
+use crate::recorder::sources::{file_basename, VideoSource};
 use async_trait::async_trait;
+use core_foundation::array::CFArray;
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::boolean::CFBoolean;
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::number::CFNumber;
+use core_foundation::string::CFString;
 use core_graphics::display::{kCGWindowListOptionOnScreenOnly, CGDisplay};
+use core_graphics::window::{
+    copy_window_info, kCGNullWindowID, kCGWindowListExcludeDesktopElements,
+    kCGWindowListOptionIncludingWindow,
+};
 use parking_lot::Mutex as ParkingMutex;
-use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 /// Get list of available displays
@@ -49,6 +67,126 @@ pub fn get_displays() -> Vec<DisplayInfo> {
         .collect()
 }
 
+/// Look up a value in a CGWindowList info dictionary
+fn dict_value(dict: &CFDictionary, key: &str) -> Option<CFType> {
+    let key = CFString::new(key);
+    dict.find(key.as_CFTypeRef())
+        .map(|value| unsafe { CFType::wrap_under_get_rule(*value) })
+}
+
+fn dict_number(dict: &CFDictionary, key: &str) -> Option<f64> {
+    dict_value(dict, key)?.downcast::<CFNumber>()?.to_f64()
+}
+
+fn dict_string(dict: &CFDictionary, key: &str) -> Option<String> {
+    dict_value(dict, key)?
+        .downcast::<CFString>()
+        .map(|s| s.to_string())
+}
+
+/// Window info and layer from a CGWindowList entry
+fn parse_window_info(dict: &CFDictionary) -> Option<(WindowInfo, i64)> {
+    let id = dict_number(dict, "kCGWindowNumber")? as u32;
+    let layer = dict_number(dict, "kCGWindowLayer").unwrap_or(0.0) as i64;
+
+    // kCGWindowBounds is a CGRect dictionary in global points (top-left origin)
+    let bounds = dict_value(dict, "kCGWindowBounds")?.downcast::<CFDictionary>()?;
+    let bounds = WindowBounds {
+        x: dict_number(&bounds, "X")? as i32,
+        y: dict_number(&bounds, "Y")? as i32,
+        width: dict_number(&bounds, "Width")? as u32,
+        height: dict_number(&bounds, "Height")? as u32,
+    };
+
+    let is_on_screen = dict_value(dict, "kCGWindowIsOnscreen")
+        .and_then(|value| value.downcast::<CFBoolean>())
+        .map(bool::from)
+        .unwrap_or(false);
+
+    Some((
+        WindowInfo {
+            id,
+            // Titles are only visible with screen recording permission
+            title: dict_string(dict, "kCGWindowName").unwrap_or_default(),
+            app_name: dict_string(dict, "kCGWindowOwnerName").unwrap_or_default(),
+            bounds,
+            is_on_screen,
+        },
+        layer,
+    ))
+}
+
+fn list_windows(option: u32, relative_to_window: u32) -> Vec<(WindowInfo, i64)> {
+    let array: CFArray = match copy_window_info(option, relative_to_window) {
+        Some(array) => array,
+        None => return Vec::new(),
+    };
+
+    array
+        .iter()
+        .filter_map(|item| {
+            let dict: CFDictionary =
+                unsafe { CFDictionary::wrap_under_get_rule(*item as CFDictionaryRef) };
+            parse_window_info(&dict)
+        })
+        .collect()
+}
+
+/// Get list of capturable windows (normal application windows on screen)
+pub fn get_windows() -> Vec<WindowInfo> {
+    list_windows(
+        kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements,
+        kCGNullWindowID,
+    )
+    .into_iter()
+    .filter(|(info, layer)| *layer == 0 && info.bounds.width > 1 && info.bounds.height > 1)
+    .map(|(info, _)| info)
+    .collect()
+}
+
+/// Get information about a single window
+pub fn get_window_info(window_id: u32) -> Option<WindowInfo> {
+    list_windows(kCGWindowListOptionIncludingWindow, window_id)
+        .into_iter()
+        .map(|(info, _)| info)
+        .find(|info| info.id == window_id)
+}
+
+/// Display that contains the center of a window
+pub fn display_for_window(window_id: u32) -> Option<u32> {
+    let window = get_window_info(window_id)?;
+    let center_x = window.bounds.x as f64 + window.bounds.width as f64 / 2.0;
+    let center_y = window.bounds.y as f64 + window.bounds.height as f64 / 2.0;
+
+    let display_ids = CGDisplay::active_displays().unwrap_or_default();
+    display_ids
+        .iter()
+        .copied()
+        .find(|&id| {
+            let bounds = CGDisplay::new(id).bounds();
+            center_x >= bounds.origin.x
+                && center_x < bounds.origin.x + bounds.size.width
+                && center_y >= bounds.origin.y
+                && center_y < bounds.origin.y + bounds.size.height
+        })
+        .or_else(|| Some(CGDisplay::main().id))
+}
+
+/// Window bounds relative to a display, in pixels
+fn window_bounds_on_display(window_id: u32, display_id: u32) -> Option<WindowBounds> {
+    let window = get_window_info(window_id)?;
+    let display = CGDisplay::new(display_id);
+    let bounds = display.bounds();
+    let scale = display.pixels_high() as f64 / bounds.size.height;
+
+    Some(WindowBounds {
+        x: ((window.bounds.x as f64 - bounds.origin.x) * scale).round() as i32,
+        y: ((window.bounds.y as f64 - bounds.origin.y) * scale).round() as i32,
+        width: (window.bounds.width as f64 * scale).round() as u32,
+        height: (window.bounds.height as f64 * scale).round() as u32,
+    })
+}
+
 /// Capture a single frame from a display using CGDisplayCreateImage
 fn capture_display_frame(display_id: u32) -> Option<(Vec<u8>, u32, u32)> {
     let display = CGDisplay::new(display_id);
@@ -88,132 +226,10 @@ fn capture_display_frame(display_id: u32) -> Option<(Vec<u8>, u32, u32)> {
     Some((pixel_data, width, height))
 }
 
-/// FFmpeg encoder for HLS segment output
-struct FFmpegSegmentEncoder {
-    process: ParkingMutex<Option<Child>>,
-    frame_count: AtomicU64,
-    running: AtomicBool,
-    output_dir: PathBuf,
-    segment_index: usize,
-}
-
-impl FFmpegSegmentEncoder {
-    fn new(
-        width: u32,
-        height: u32,
-        fps: u32,
-        output_dir: &Path,
-        segment_index: usize,
-    ) -> Result<Self, std::io::Error> {
-        // Create output directory if it doesn't exist
-        std::fs::create_dir_all(output_dir)?;
-
-        let output_file = output_dir
-            .join(format!("recording-{segment_index}.mp4"))
-            .to_string_lossy()
-            .to_string();
-
-        // Start FFmpeg process for MP4 output
-        // Input: raw BGRA frames from stdin
-        // Output: H.264 encoded MP4
-        let process = Command::new("ffmpeg")
-            .args([
-                "-y",                            // Overwrite output
-                "-f", "rawvideo",                // Input format
-                "-pixel_format", "bgra",         // BGRA pixel format from CGImage
-                "-video_size", &format!("{width}x{height}"),
-                "-framerate", &fps.to_string(),
-                "-i", "-",                       // Read from stdin
-                "-c:v", "libx264",               // H.264 codec
-                "-preset", "veryfast",           // Good balance of speed and compression
-                "-pix_fmt", "yuv420p",           // Output pixel format (required for compatibility)
-                "-crf", "18",                    // High quality (lower = better, 18 is visually lossless)
-                "-g", &(fps * 2).to_string(),    // GOP size = 2 seconds
-                "-movflags", "+faststart",       // Move moov atom to start for streaming
-                &output_file,
-            ])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped()) // Capture stderr for debugging
-            .spawn()?;
-
-        tracing::info!(
-            "Started FFmpeg encoder: {}x{} @ {}fps, segments to {:?}",
-            width,
-            height,
-            fps,
-            output_dir
-        );
-
-        Ok(Self {
-            process: ParkingMutex::new(Some(process)),
-            frame_count: AtomicU64::new(0),
-            running: AtomicBool::new(true),
-            output_dir: output_dir.to_path_buf(),
-            segment_index,
-        })
-    }
-
-    fn write_frame(&self, data: &[u8]) -> bool {
-        if !self.running.load(Ordering::Relaxed) {
-            return false;
-        }
-
-        let mut guard = self.process.lock();
-        if let Some(ref mut process) = *guard {
-            if let Some(ref mut stdin) = process.stdin {
-                if stdin.write_all(data).is_ok() {
-                    self.frame_count.fetch_add(1, Ordering::Relaxed);
-                    return true;
-                }
-            }
-        }
-        false
-    }
-
-    fn frame_count(&self) -> u64 {
-        self.frame_count.load(Ordering::Relaxed)
-    }
-
-    fn finish(&self) -> Result<Vec<String>, std::io::Error> {
-        self.running.store(false, Ordering::Relaxed);
-        let mut guard = self.process.lock();
-        if let Some(mut process) = guard.take() {
-            // Close stdin to signal EOF
-            drop(process.stdin.take());
-            // Wait for FFmpeg to finish
-            let output = process.wait_with_output()?;
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                tracing::warn!("FFmpeg exited with status {}: {}", output.status, stderr);
-            }
-        }
-
-        // Find the output file
-        let output_file = self.output_dir
-            .join(format!("recording-{}.mp4", self.segment_index))
-            .to_string_lossy()
-            .to_string();
-        
-        let mut files = Vec::new();
-        if std::path::Path::new(&output_file).exists() {
-            files.push(output_file.clone());
-        }
-
-        tracing::info!(
-            "FFmpeg finished: {} frames, output: {}",
-            self.frame_count(),
-            output_file,
-        );
-
-        Ok(files)
-    }
-}
-
 /// Display capture channel using CGWindowListCreateImage
 ///
 /// This implementation captures frames using Core Graphics and
-/// encodes them to H.264 segments using FFmpeg.
+/// encodes them with its `EncoderBackend` (FFmpeg by default).
 pub struct DisplayCaptureChannel {
     /// Channel identifier
     id: String,
@@ -230,14 +246,23 @@ pub struct DisplayCaptureChannel {
     /// Current session index
     session_index: usize,
 
+    /// Shared recording clock
+    clock: RecordingClock,
+    /// Reports encoder failures while capturing
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
+
     /// Output files created
     output_files: Arc<ParkingMutex<Vec<String>>>,
 
-    /// FFmpeg encoder
-    encoder: Option<Arc<FFmpegSegmentEncoder>>,
+    /// Opens the session's encoder
+    encoders: Arc<dyn EncoderBackend>,
+
+    /// Encoder of the current session
+    encoder: Option<Arc<VideoOutput>>,
 
-    /// Capture task handle
-    capture_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Capture task handle (returns the session's frame timeline)
+    capture_handle: Option<tokio::task::JoinHandle<FrameTimeline>>,
 
     /// Capture width
     width: u32,
@@ -245,8 +270,17 @@ pub struct DisplayCaptureChannel {
     /// Capture height
     height: u32,
 
-    /// Capture FPS
-    fps: u32,
+    /// Capture frame rate and encoding
+    video: VideoSettings,
+
+    /// Window or region to crop to (None captures the whole display)
+    target: Option<CaptureTarget>,
+
+    /// Crop origin timeline shared with input tracking
+    capture_region: Option<Arc<CaptureRegion>>,
+
+    /// Suffix of the file names when recording an additional display
+    file_suffix: Option<String>,
 }
 
 impl DisplayCaptureChannel {
@@ -258,14 +292,53 @@ impl DisplayCaptureChannel {
             is_recording: Arc::new(AtomicBool::new(false)),
             output_dir: None,
             session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
             output_files: Arc::new(ParkingMutex::new(Vec::new())),
+            encoders: ffmpeg_backend(),
             encoder: None,
             capture_handle: None,
             width: 1920,
             height: 1080,
-            fps: 30,
+            video: VideoSettings::default(),
+            target: None,
+            capture_region: None,
+            file_suffix: None,
         }
     }
+
+    /// Capture a window or region of the display instead of all of it
+    ///
+    /// The current crop origin is published to `capture_region` so input
+    /// tracking can use the same coordinate space.
+    pub fn with_target(mut self, target: CaptureTarget, capture_region: Arc<CaptureRegion>) -> Self {
+        self.target = Some(target);
+        self.capture_region = Some(capture_region);
+        self
+    }
+
+    /// Capture at the settings' frame rate and encode with them
+    pub fn with_video_settings(mut self, video: VideoSettings) -> Self {
+        self.video = video;
+        self
+    }
+
+    /// Encode through another backend (FFmpeg by default)
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
+    /// Record an additional display to `recording-N-{suffix}` files
+    pub fn with_file_suffix(mut self, suffix: String) -> Self {
+        self.file_suffix = Some(suffix);
+        self
+    }
+
+    fn basename(&self) -> String {
+        file_basename(self.session_index, self.file_suffix.as_deref())
+    }
 }
 
 #[async_trait]
@@ -278,7 +351,12 @@ impl RecordingChannel for DisplayCaptureChannel {
         ChannelType::Display
     }
 
-    async fn initialize(&mut self, output_dir: &Path, session_index: usize) -> RecordingResult<()> {
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
         // Check permission first
         if !super::permissions::has_screen_recording_permission() {
             super::permissions::request_screen_recording_permission();
@@ -288,12 +366,7 @@ impl RecordingChannel for DisplayCaptureChannel {
             ));
         }
 
-        // Check if FFmpeg is available
-        if Command::new("ffmpeg").arg("-version").output().is_err() {
-            return Err(RecordingError::ConfigurationError(
-                "FFmpeg not found. Please install FFmpeg: brew install ffmpeg".to_string(),
-            ));
-        }
+        self.encoders.check_video(&self.video)?;
 
         // Get display info for resolution - use native (pixel) resolution for Retina displays
         let display = CGDisplay::new(self.display_id);
@@ -302,6 +375,7 @@ impl RecordingChannel for DisplayCaptureChannel {
 
         self.output_dir = Some(output_dir.to_path_buf());
         self.session_index = session_index;
+        self.clock = clock.clone();
 
         tracing::info!(
             "Display capture channel initialized for display {} ({}x{})",
@@ -321,6 +395,10 @@ impl RecordingChannel for DisplayCaptureChannel {
             RecordingError::ConfigurationError("Output directory not set".to_string())
         })?;
 
+        // Frame times are measured from the first capture
+        let mut pacer = FramePacer::new(self.video.fps, &self.clock, &self.id, self.session_index)
+            .with_health(self.health.clone());
+
         // Capture first frame to determine actual dimensions
         let (first_frame, actual_width, actual_height) = capture_display_frame(self.display_id)
             .ok_or_else(|| RecordingError::CaptureError("Failed to capture initial frame".to_string()))?;
@@ -335,22 +413,44 @@ impl RecordingChannel for DisplayCaptureChannel {
             actual_height
         );
 
-        // Create FFmpeg encoder with actual dimensions
+        // Windows are re-resolved every session since they may have moved
+        let crop = resolve_crop(
+            self.target.as_ref(),
+            self.display_id,
+            actual_width,
+            actual_height,
+            |window_id| window_bounds_on_display(window_id, self.display_id),
+        )?;
+        let first_frame = match crop {
+            Some(rect) => {
+                self.width = rect.width;
+                self.height = rect.height;
+                if let Some(ref region) = self.capture_region {
+                    region.set_origin_now(rect.x as f64, rect.y as f64);
+                }
+                crop_frame(&first_frame, actual_width, &rect)
+            }
+            None => first_frame,
+        };
+
+        // Create the encoder with actual dimensions
+        let spec = VideoSpec {
+            width: self.width,
+            height: self.height,
+            settings: self.video.clone(),
+            output_file: self.video.output_file(&output_dir, &self.basename()),
+        };
         let encoder = Arc::new(
-            FFmpegSegmentEncoder::new(
-                self.width,
-                self.height,
-                self.fps,
-                &output_dir,
-                self.session_index,
-            )
-            .map_err(|e| RecordingError::CaptureError(format!("Failed to start FFmpeg: {}", e)))?,
+            self.encoders
+                .video_encoder(&spec)
+                .map(|encoder| VideoOutput::new(encoder).with_error_reporter(self.error_reporter.clone()))
+                .map_err(|e| RecordingError::CaptureError(format!("Failed to start encoder: {}", e)))?,
         );
         
         // Write the first frame
         let expected_size = (self.width * self.height * 4) as usize;
         if first_frame.len() >= expected_size {
-            encoder.write_frame(&first_frame[..expected_size]);
+            pacer.push(&first_frame[..expected_size], |frame| encoder.write_frame(frame));
         }
         
         self.encoder = Some(encoder.clone());
@@ -359,21 +459,49 @@ impl RecordingChannel for DisplayCaptureChannel {
         // Start capture loop in background task
         let is_recording = self.is_recording.clone();
         let display_id = self.display_id;
-        let fps = self.fps;
+        let fps = self.video.fps;
         let width = self.width;
         let height = self.height;
+        let follow_window = match self.target {
+            Some(CaptureTarget::Window { window_id }) => Some(window_id),
+            _ => None,
+        };
+        let capture_region = self.capture_region.clone();
 
         let handle = tokio::spawn(async move {
             let frame_interval = std::time::Duration::from_millis(1000 / fps as u64);
             let expected_size = (width * height * 4) as usize; // BGRA = 4 bytes per pixel
+            let mut current_rect = crop;
 
             while is_recording.load(Ordering::SeqCst) {
                 let start = std::time::Instant::now();
 
+                // Keep the crop on the window as it moves
+                if let (Some(window_id), Some(rect)) = (follow_window, current_rect) {
+                    if let Some(bounds) = window_bounds_on_display(window_id, display_id) {
+                        let moved = rect.moved_to(
+                            bounds.x as i64,
+                            bounds.y as i64,
+                            actual_width,
+                            actual_height,
+                        );
+                        if moved != rect {
+                            current_rect = Some(moved);
+                            if let Some(ref region) = capture_region {
+                                region.set_origin_now(moved.x as f64, moved.y as f64);
+                            }
+                        }
+                    }
+                }
+
                 // Capture frame
-                if let Some((data, _w, _h)) = capture_display_frame(display_id) {
+                if let Some((data, frame_width, _h)) = capture_display_frame(display_id) {
+                    let data = match current_rect {
+                        Some(ref rect) => crop_frame(&data, frame_width, rect),
+                        None => data,
+                    };
                     if data.len() >= expected_size {
-                        encoder.write_frame(&data[..expected_size]);
+                        pacer.push(&data[..expected_size], |frame| encoder.write_frame(frame));
                     }
                 }
 
@@ -395,6 +523,9 @@ impl RecordingChannel for DisplayCaptureChannel {
                     tokio::time::sleep(frame_interval - elapsed).await;
                 }
             }
+
+            // Repeat the last frame up to the stop time
+            pacer.finish(|frame| encoder.write_frame(frame))
         });
 
         self.capture_handle = Some(handle);
@@ -404,7 +535,7 @@ impl RecordingChannel for DisplayCaptureChannel {
             self.display_id,
             self.width,
             self.height,
-            self.fps
+            self.video.fps
         );
         Ok(())
     }
@@ -417,9 +548,10 @@ impl RecordingChannel for DisplayCaptureChannel {
         self.is_recording.store(false, Ordering::SeqCst);
 
         // Wait for capture task to finish
-        if let Some(handle) = self.capture_handle.take() {
-            let _ = handle.await;
-        }
+        let timeline = match self.capture_handle.take() {
+            Some(handle) => handle.await.ok(),
+            None => None,
+        };
 
         // Finish encoding and collect output files
         if let Some(ref encoder) = self.encoder {
@@ -430,6 +562,18 @@ impl RecordingChannel for DisplayCaptureChannel {
         }
         self.encoder = None;
 
+        // Per-frame capture times for aligning input events with the video
+        if let (Some(timeline), Some(ref output_dir)) = (timeline, &self.output_dir) {
+            tracing::info!(
+                "Frame timing: {} captured, {} dropped, {} encoded",
+                timeline.frames.len(),
+                timeline.dropped_count(),
+                timeline.output_frame_count
+            );
+            let sidecar = timeline.write_sidecar(output_dir, &self.basename())?;
+            self.output_files.lock().push(sidecar);
+        }
+
         tracing::info!("Display capture stopped");
         Ok(())
     }
@@ -452,4 +596,24 @@ impl RecordingChannel for DisplayCaptureChannel {
     fn output_files(&self) -> Vec<String> {
         self.output_files.lock().clone()
     }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
+
+    fn video_source(&self) -> Option<VideoSource> {
+        Some(VideoSource {
+            channel_id: self.id.clone(),
+            display_id: Some(self.display_id),
+            file_suffix: self.file_suffix.clone(),
+            capture_size: Some(Resolution {
+                width: self.width,
+                height: self.height,
+            }),
+        })
+    }
 }
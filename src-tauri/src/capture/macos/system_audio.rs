@@ -11,8 +11,13 @@
 //!
 //! This module handles both formats and converts to interleaved stereo for FFmpeg.
 
-use crate::capture::audio::AudioEncoder;
-use crate::recorder::channel::{ChannelType, RecordingChannel, RecordingError, RecordingResult};
+use crate::capture::audio::{AudioEncoder, AudioFormat};
+use crate::capture::encoder::{ffmpeg_backend, EncoderBackend};
+use crate::recorder::channel::{
+    ChannelErrorReporter, ChannelType, RecordingChannel, RecordingError, RecordingResult,
+};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
 use async_trait::async_trait;
 use parking_lot::Mutex as ParkingMutex;
 use screencapturekit::cm::{AudioBuffer, AudioBufferList, CMFormatDescription};
@@ -230,6 +235,11 @@ pub struct SystemAudioCaptureChannel {
     is_recording: Arc<AtomicBool>,
     output_dir: Option<PathBuf>,
     session_index: usize,
+    clock: RecordingClock,
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
+    format: AudioFormat,
+    encoders: Arc<dyn EncoderBackend>,
     output_files: Arc<ParkingMutex<Vec<String>>>,
     encoder: Arc<ParkingMutex<Option<Arc<AudioEncoder>>>>,
     stream: ParkingMutex<Option<SCStream>>,
@@ -245,6 +255,11 @@ impl SystemAudioCaptureChannel {
             is_recording: Arc::new(AtomicBool::new(false)),
             output_dir: None,
             session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
+            format: AudioFormat::default(),
+            encoders: ffmpeg_backend(),
             output_files: Arc::new(ParkingMutex::new(Vec::new())),
             encoder: Arc::new(ParkingMutex::new(None)),
             stream: ParkingMutex::new(None),
@@ -252,6 +267,18 @@ impl SystemAudioCaptureChannel {
         }
     }
 
+    /// Codec to record system audio with (AAC by default)
+    pub fn with_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Encode through another backend (FFmpeg by default)
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
     /// Check if system audio capture is available
     pub fn is_available(&self) -> bool {
         is_system_audio_available()
@@ -274,9 +301,16 @@ impl RecordingChannel for SystemAudioCaptureChannel {
         ChannelType::SystemAudio
     }
 
-    async fn initialize(&mut self, output_dir: &Path, session_index: usize) -> RecordingResult<()> {
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
+        self.encoders.check_audio(&self.format)?;
         self.output_dir = Some(output_dir.to_path_buf());
         self.session_index = session_index;
+        self.clock = clock.clone();
 
         tracing::info!(
             "System audio channel initialized with ScreenCaptureKit for display {}",
@@ -347,9 +381,25 @@ impl RecordingChannel for SystemAudioCaptureChannel {
 
         // Create encoder (48kHz stereo)
         let encoder = Arc::new(
-            AudioEncoder::new(48000, 2, &output_dir, self.session_index, "system").map_err(
-                |e| RecordingError::CaptureError(format!("Failed to start audio encoder: {}", e)),
-            )?,
+            AudioEncoder::new(
+                self.encoders.as_ref(),
+                48000,
+                2,
+                &output_dir,
+                self.session_index,
+                "system",
+                self.format,
+            )
+            .map(|encoder| {
+                encoder
+                    .with_clock(self.clock.clone(), &self.id)
+                    .with_error_reporter(self.error_reporter.clone())
+                    .with_health(self.health.clone())
+                    .with_levels(&self.id)
+            })
+            .map_err(|e| {
+                RecordingError::CaptureError(format!("Failed to start audio encoder: {}", e))
+            })?,
         );
         *self.encoder.lock() = Some(encoder.clone());
 
@@ -428,4 +478,12 @@ impl RecordingChannel for SystemAudioCaptureChannel {
     fn output_files(&self) -> Vec<String> {
         self.output_files.lock().clone()
     }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
 }
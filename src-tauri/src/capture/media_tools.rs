@@ -0,0 +1,461 @@
+//! FFmpeg and FFprobe discovery
+//!
+//! Every pipeline that shells out to FFmpeg or FFprobe goes through
+//! `command`, which runs the tool found first in:
+//!
+//! 1. the configured path (`configure`, or the `OPEN_SCREENSTUDIO_FFMPEG` /
+//!    `OPEN_SCREENSTUDIO_FFPROBE` environment variables)
+//! 2. a sidecar binary shipped next to the app executable
+//! 3. `PATH`
+//!
+//! `toolchain` probes the FFmpeg found that way for its version and the
+//! encoders, muxers and filters it was built with, so channels can turn a
+//! missing codec into an error naming what to change before recording starts.
+//! The probe is cached until `refresh_toolchain` or `configure` is called.
+
+use crate::capture::audio::AudioFormat;
+use crate::capture::video::{OutputScale, VideoSettings};
+use crate::recorder::channel::{RecordingError, RecordingResult};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, OnceLock};
+
+#[cfg(target_os = "macos")]
+const INSTALL_HINT: &str = "brew install ffmpeg";
+#[cfg(target_os = "linux")]
+const INSTALL_HINT: &str = "e.g. sudo apt install ffmpeg";
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+const INSTALL_HINT: &str = "and add it to PATH";
+
+/// An external media tool
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MediaTool {
+    Ffmpeg,
+    Ffprobe,
+}
+
+impl MediaTool {
+    pub fn name(&self) -> &'static str {
+        match self {
+            MediaTool::Ffmpeg => "ffmpeg",
+            MediaTool::Ffprobe => "ffprobe",
+        }
+    }
+
+    fn display_name(&self) -> &'static str {
+        match self {
+            MediaTool::Ffmpeg => "FFmpeg",
+            MediaTool::Ffprobe => "FFprobe",
+        }
+    }
+
+    /// Environment variable holding the tool's configured path
+    pub fn env_var(&self) -> &'static str {
+        match self {
+            MediaTool::Ffmpeg => "OPEN_SCREENSTUDIO_FFMPEG",
+            MediaTool::Ffprobe => "OPEN_SCREENSTUDIO_FFPROBE",
+        }
+    }
+
+    fn file_name(&self) -> String {
+        format!("{}{}", self.name(), std::env::consts::EXE_SUFFIX)
+    }
+
+    /// Error for when the tool can't be found, saying how to fix it
+    pub fn not_found_message(&self) -> String {
+        format!(
+            "{} not found. Please install FFmpeg ({}) or set {} to its path.",
+            self.display_name(),
+            INSTALL_HINT,
+            self.env_var()
+        )
+    }
+}
+
+/// Where a tool was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ToolSource {
+    /// The path given with `configure` or the environment
+    Configured,
+    /// Bundled next to the app executable
+    Sidecar,
+    /// Found on `PATH`
+    Path,
+}
+
+/// A located tool binary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocatedTool {
+    pub path: PathBuf,
+    pub source: ToolSource,
+}
+
+/// Tool paths set by the user, overriding the sidecar and `PATH`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MediaToolPaths {
+    pub ffmpeg: Option<PathBuf>,
+    pub ffprobe: Option<PathBuf>,
+}
+
+impl MediaToolPaths {
+    fn from_env() -> Self {
+        let var = |tool: MediaTool| {
+            std::env::var_os(tool.env_var())
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        };
+        Self {
+            ffmpeg: var(MediaTool::Ffmpeg),
+            ffprobe: var(MediaTool::Ffprobe),
+        }
+    }
+
+    fn get(&self, tool: MediaTool) -> Option<&Path> {
+        match tool {
+            MediaTool::Ffmpeg => self.ffmpeg.as_deref(),
+            MediaTool::Ffprobe => self.ffprobe.as_deref(),
+        }
+    }
+}
+
+fn configured_paths() -> &'static RwLock<MediaToolPaths> {
+    static PATHS: OnceLock<RwLock<MediaToolPaths>> = OnceLock::new();
+    PATHS.get_or_init(|| RwLock::new(MediaToolPaths::from_env()))
+}
+
+fn cached_toolchain() -> &'static RwLock<Option<Arc<MediaToolchain>>> {
+    static TOOLCHAIN: OnceLock<RwLock<Option<Arc<MediaToolchain>>>> = OnceLock::new();
+    TOOLCHAIN.get_or_init(|| RwLock::new(None))
+}
+
+/// Use these tool paths from now on, replacing the environment's
+pub fn configure(paths: MediaToolPaths) {
+    *configured_paths().write() = paths;
+    *cached_toolchain().write() = None;
+}
+
+/// Find a tool, trying the configured path, the sidecar and `PATH` in turn
+pub fn locate(tool: MediaTool) -> Option<LocatedTool> {
+    if let Some(path) = configured_paths().read().get(tool) {
+        if path.is_file() {
+            return Some(LocatedTool {
+                path: path.to_path_buf(),
+                source: ToolSource::Configured,
+            });
+        }
+        tracing::warn!(
+            "Configured {} path {:?} does not exist, falling back",
+            tool.name(),
+            path
+        );
+    }
+
+    let sidecar = std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(tool.file_name())))
+        .filter(|path| path.is_file());
+    if let Some(path) = sidecar {
+        return Some(LocatedTool {
+            path,
+            source: ToolSource::Sidecar,
+        });
+    }
+
+    let path_var = std::env::var_os("PATH")?;
+    find_in_path(&tool.file_name(), &path_var).map(|path| LocatedTool {
+        path,
+        source: ToolSource::Path,
+    })
+}
+
+/// First directory of a `PATH`-style list holding the file
+fn find_in_path(file_name: &str, path_var: &OsStr) -> Option<PathBuf> {
+    std::env::split_paths(path_var)
+        .map(|dir| dir.join(file_name))
+        .find(|path| path.is_file())
+}
+
+/// Command running the located tool
+///
+/// Falls back to the bare name when the tool wasn't found, so spawning
+/// fails with the usual "not found" error.
+pub fn command(tool: MediaTool) -> Command {
+    match locate(tool) {
+        Some(located) => Command::new(located.path),
+        None => Command::new(tool.name()),
+    }
+}
+
+/// Command running the located tool, failing when it can't be found
+pub fn require(tool: MediaTool) -> RecordingResult<Command> {
+    locate(tool)
+        .map(|located| Command::new(located.path))
+        .ok_or_else(|| RecordingError::ConfigurationError(tool.not_found_message()))
+}
+
+/// A probed tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolInfo {
+    pub path: String,
+    pub source: ToolSource,
+    /// Version from `-version`, e.g. "6.1.1-3ubuntu5" or "n7.0"
+    pub version: Option<String>,
+}
+
+/// The media tools found and what FFmpeg was built with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaToolchain {
+    pub ffmpeg: Option<ToolInfo>,
+    pub ffprobe: Option<ToolInfo>,
+    pub encoders: BTreeSet<String>,
+    pub muxers: BTreeSet<String>,
+    pub filters: BTreeSet<String>,
+}
+
+/// The probed toolchain, probing on first use and while FFmpeg is missing
+pub fn toolchain() -> Arc<MediaToolchain> {
+    if let Some(toolchain) = cached_toolchain().read().as_ref() {
+        if toolchain.ffmpeg.is_some() {
+            return toolchain.clone();
+        }
+    }
+    refresh_toolchain()
+}
+
+/// Probe the tools again, e.g. after FFmpeg was installed
+pub fn refresh_toolchain() -> Arc<MediaToolchain> {
+    let toolchain = Arc::new(MediaToolchain::probe());
+    *cached_toolchain().write() = Some(toolchain.clone());
+    toolchain
+}
+
+/// Stdout of a tool run with the given arguments
+fn run(path: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new(path).args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+impl MediaToolchain {
+    fn probe() -> Self {
+        let info = |tool: MediaTool| {
+            let located = locate(tool)?;
+            let version = run(&located.path, &["-version"]);
+            Some(ToolInfo {
+                path: located.path.to_string_lossy().to_string(),
+                source: located.source,
+                version: version.as_deref().and_then(parse_version),
+            })
+        };
+        let ffmpeg = info(MediaTool::Ffmpeg);
+        let ffprobe = info(MediaTool::Ffprobe);
+
+        let list = |flag: &str| {
+            ffmpeg
+                .as_ref()
+                .and_then(|tool| run(Path::new(&tool.path), &["-hide_banner", flag]))
+                .map(|output| parse_list(&output))
+                .unwrap_or_default()
+        };
+        let toolchain = Self {
+            encoders: list("-encoders"),
+            muxers: list("-muxers"),
+            filters: list("-filters"),
+            ffmpeg,
+            ffprobe,
+        };
+
+        match toolchain.ffmpeg {
+            Some(ref ffmpeg) => tracing::info!(
+                "Found FFmpeg {} at {} ({:?}): {} encoders, {} muxers, {} filters",
+                ffmpeg.version.as_deref().unwrap_or("(unknown version)"),
+                ffmpeg.path,
+                ffmpeg.source,
+                toolchain.encoders.len(),
+                toolchain.muxers.len(),
+                toolchain.filters.len()
+            ),
+            None => tracing::warn!("FFmpeg not found"),
+        }
+        toolchain
+    }
+
+    fn require_ffmpeg(&self) -> RecordingResult<&ToolInfo> {
+        self.ffmpeg.as_ref().ok_or_else(|| {
+            RecordingError::ConfigurationError(MediaTool::Ffmpeg.not_found_message())
+        })
+    }
+
+    /// Check FFmpeg can encode with `encoder` into a `muxer` file
+    ///
+    /// `purpose` names what needs them in the error, e.g. "webcam recordings".
+    pub fn check_encoding(&self, encoder: &str, muxer: &str, purpose: &str) -> RecordingResult<()> {
+        let ffmpeg = self.require_ffmpeg()?;
+        if !self.encoders.contains(encoder) {
+            return Err(RecordingError::ConfigurationError(format!(
+                "The FFmpeg at {} has no {} encoder, which {} need. \
+                 Pick another codec or install an FFmpeg build that includes it.",
+                ffmpeg.path, encoder, purpose
+            )));
+        }
+        if !self.muxers.contains(muxer) {
+            return Err(RecordingError::ConfigurationError(format!(
+                "The FFmpeg at {} can't write {} files, which {} need. \
+                 Pick another format or install an FFmpeg build that includes it.",
+                ffmpeg.path, muxer, purpose
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check FFmpeg can encode display recordings with these settings
+    pub fn check_video(&self, settings: &VideoSettings) -> RecordingResult<()> {
+        let purpose = format!("{:?} recordings", settings.codec);
+        self.check_encoding(settings.codec.encoder(), settings.codec.muxer(), &purpose)?;
+        if settings.scale != OutputScale::Native && !self.filters.contains("scale") {
+            let ffmpeg = self.require_ffmpeg()?;
+            return Err(RecordingError::ConfigurationError(format!(
+                "The FFmpeg at {} has no scale filter, which scaled output needs. \
+                 Record at native size or install an FFmpeg build that includes it.",
+                ffmpeg.path
+            )));
+        }
+        Ok(())
+    }
+
+    /// Check FFmpeg can encode audio tracks in this format
+    pub fn check_audio(&self, format: &AudioFormat) -> RecordingResult<()> {
+        let purpose = format!("{} audio tracks", format.extension());
+        self.check_encoding(format.encoder(), format.muxer(), &purpose)
+    }
+}
+
+/// Version from the first line of `-version`, e.g. "ffmpeg version 6.1 Copyright ..."
+fn parse_version(output: &str) -> Option<String> {
+    let mut words = output.lines().next()?.split_whitespace();
+    words.find(|word| *word == "version")?;
+    words.next().map(str::to_string)
+}
+
+/// Names listed by `-encoders`, `-muxers` or `-filters`
+fn parse_list(output: &str) -> BTreeSet<String> {
+    // Entries are a flags column then the name: " V....D libx264  ...",
+    // "  E mp4  ...", " TSC scale  V->V ...". Legend lines have "=" for a
+    // name, and muxers may list several names separated by commas.
+    output
+        .lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let flags = columns.next()?;
+            let names = columns.next()?;
+            let is_flags = flags
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c == '.' || c == '|');
+            (is_flags && names != "=").then_some(names)
+        })
+        .flat_map(|names| names.split(','))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_version() {
+        let output =
+            "ffmpeg version 6.1.1-3ubuntu5 Copyright (c) 2000-2023 the FFmpeg developers\n\
+                      built with gcc 13 (Ubuntu 13.2.0-23ubuntu3)\n";
+        assert_eq!(parse_version(output).as_deref(), Some("6.1.1-3ubuntu5"));
+        assert_eq!(
+            parse_version("ffprobe version n7.0 Copyright").as_deref(),
+            Some("n7.0")
+        );
+        assert_eq!(parse_version("not ffmpeg"), None);
+    }
+
+    #[test]
+    fn test_parse_lists() {
+        let encoders = "Encoders:\n V..... = Video\n A..... = Audio\n ------\n \
+                        V....D libx264              libx264 H.264 / AVC\n \
+                        V..... libx264rgb           libx264 H.264 RGB\n \
+                        A....D flac                 FLAC (Free Lossless Audio Codec)\n";
+        let encoders = parse_list(encoders);
+        assert!(encoders.contains("libx264"));
+        assert!(encoders.contains("libx264rgb"));
+        assert!(encoders.contains("flac"));
+        assert!(!encoders.contains("="));
+        assert!(!encoders.contains("libx265"));
+
+        let muxers = "File formats:\n D. = Demuxing supported\n .E = Muxing supported\n ---\n  \
+                      E matroska        Matroska\n  E mp4             MP4 (MPEG-4 Part 14)\n \
+                      DE ogg,oga        Ogg\n";
+        let muxers = parse_list(muxers);
+        assert_eq!(
+            muxers.iter().map(String::as_str).collect::<Vec<_>>(),
+            vec!["matroska", "mp4", "oga", "ogg"]
+        );
+
+        let filters = "Filters:\n  T.. = Timeline support\n  A = Audio input/output\n  \
+                       | = Source or sink filter\n TSC scale             V->V       Scale the input video\n \
+                       ... abench            A->A       Benchmark part of a filtergraph.\n";
+        let filters = parse_list(filters);
+        assert_eq!(filters.len(), 2);
+        assert!(filters.contains("scale"));
+    }
+
+    #[test]
+    fn test_check_reports_missing_codec() {
+        let toolchain = MediaToolchain {
+            ffmpeg: Some(ToolInfo {
+                path: "/usr/bin/ffmpeg".to_string(),
+                source: ToolSource::Path,
+                version: Some("6.1".to_string()),
+            }),
+            ffprobe: None,
+            encoders: ["libx264", "flac"].map(String::from).into(),
+            muxers: ["mp4", "flac"].map(String::from).into(),
+            filters: BTreeSet::new(),
+        };
+        assert!(toolchain.check_video(&VideoSettings::default()).is_ok());
+        assert!(toolchain.check_audio(&AudioFormat::Flac).is_ok());
+
+        let error = toolchain
+            .check_audio(&AudioFormat::default())
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("no aac encoder"), "{}", error);
+
+        let missing = MediaToolchain::default()
+            .check_video(&VideoSettings::default())
+            .unwrap_err()
+            .to_string();
+        assert!(missing.contains("OPEN_SCREENSTUDIO_FFMPEG"), "{}", missing);
+    }
+
+    #[test]
+    fn test_find_in_path() {
+        let first = tempfile::tempdir().unwrap();
+        let second = tempfile::tempdir().unwrap();
+        std::fs::write(second.path().join("ffmpeg"), "").unwrap();
+        let path_var = std::env::join_paths([first.path(), second.path()]).unwrap();
+
+        assert_eq!(
+            find_in_path("ffmpeg", &path_var),
+            Some(second.path().join("ffmpeg"))
+        );
+        assert_eq!(find_in_path("ffprobe", &path_var), None);
+    }
+}
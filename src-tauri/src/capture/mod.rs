@@ -4,7 +4,15 @@
 
 pub mod traits;
 pub mod audio;
+pub mod audio_devices;
+pub mod encoder;
+pub mod frames;
 pub mod input;
+pub mod levels;
+pub mod media_tools;
+pub mod region;
+pub mod synthetic;
+pub mod video;
 
 #[cfg(target_os = "macos")]
 pub mod macos;
@@ -12,14 +20,17 @@ pub mod macos;
 #[cfg(target_os = "windows")]
 pub mod windows;
 
+#[cfg(target_os = "linux")]
+pub mod linux;
+
 // Re-export traits
-pub use traits::{DisplayInfo, WindowInfo, WindowBounds, AudioDeviceInfo, CameraInfo, Resolution};
+pub use traits::{CaptureTarget, DisplayInfo, WindowInfo, WindowBounds, AudioDeviceInfo, CameraInfo, Resolution};
 
 // Re-export permission functions from traits (which delegates to platform)
 pub use traits::{has_screen_recording_permission, request_screen_recording_permission};
 
 // Re-export audio functions
-pub use audio::{get_audio_input_devices, MicrophoneCaptureChannel};
+pub use audio::{get_audio_input_devices, AudioMonitor, MicrophoneCaptureChannel};
 
 // Re-export input channel
 pub use input::InputTrackingChannel;
@@ -0,0 +1,316 @@
+//! Cropping for window and region capture
+//!
+//! Display channels capture the whole display (or the part of it that
+//! holds the target) and crop each frame to a `CropRect`. When following a
+//! window the crop moves with it, so the channel records every origin change
+//! in a shared `CaptureRegion`. Input tracking uses that timeline to express
+//! mouse coordinates in the cropped video's space.
+
+use crate::capture::traits::{CaptureTarget, WindowBounds};
+use crate::recorder::channel::{RecordingError, RecordingResult};
+use parking_lot::Mutex as ParkingMutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Rectangle inside a captured frame, in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropRect {
+    /// Clip a rectangle to the frame
+    ///
+    /// Width and height are rounded down to even numbers since the encoder
+    /// outputs yuv420p. Returns None if nothing of the rectangle is visible.
+    pub fn clipped(
+        x: i64,
+        y: i64,
+        width: u32,
+        height: u32,
+        frame_width: u32,
+        frame_height: u32,
+    ) -> Option<Self> {
+        let left = x.clamp(0, frame_width as i64);
+        let top = y.clamp(0, frame_height as i64);
+        let right = (x + width as i64).clamp(0, frame_width as i64);
+        let bottom = (y + height as i64).clamp(0, frame_height as i64);
+
+        let width = ((right - left) as u32) & !1;
+        let height = ((bottom - top) as u32) & !1;
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        Some(Self {
+            x: left as u32,
+            y: top as u32,
+            width,
+            height,
+        })
+    }
+
+    /// Place a rectangle of this size at (x, y), shifted to stay inside the frame
+    ///
+    /// Used to follow a window: the output size is fixed for the session while
+    /// the window may move partially off screen.
+    pub fn moved_to(&self, x: i64, y: i64, frame_width: u32, frame_height: u32) -> Self {
+        let max_x = frame_width.saturating_sub(self.width) as i64;
+        let max_y = frame_height.saturating_sub(self.height) as i64;
+
+        Self {
+            x: x.clamp(0, max_x) as u32,
+            y: y.clamp(0, max_y) as u32,
+            width: self.width,
+            height: self.height,
+        }
+    }
+
+    /// Whether the rectangle covers the whole frame
+    pub fn is_full_frame(&self, frame_width: u32, frame_height: u32) -> bool {
+        self.x == 0 && self.y == 0 && self.width == frame_width && self.height == frame_height
+    }
+}
+
+/// Copy a rectangle out of a tightly packed 4-byte-per-pixel frame
+pub fn crop_frame(frame: &[u8], frame_width: u32, rect: &CropRect) -> Vec<u8> {
+    let stride = frame_width as usize * 4;
+    let row_len = rect.width as usize * 4;
+    let mut cropped = Vec::with_capacity(row_len * rect.height as usize);
+
+    for row in rect.y..rect.y + rect.height {
+        let start = row as usize * stride + rect.x as usize * 4;
+        match frame.get(start..start + row_len) {
+            Some(pixels) => cropped.extend_from_slice(pixels),
+            None => cropped.resize(cropped.len() + row_len, 0),
+        }
+    }
+
+    cropped
+}
+
+/// Work out which part of the captured frame a window or region target covers
+///
+/// `window_bounds` looks a window up on the display, in the frame's pixels;
+/// each platform finds windows its own way. Returns None for whole displays.
+pub fn resolve_crop(
+    target: Option<&CaptureTarget>,
+    display_id: u32,
+    frame_width: u32,
+    frame_height: u32,
+    window_bounds: impl FnOnce(u32) -> Option<WindowBounds>,
+) -> RecordingResult<Option<CropRect>> {
+    let bounds = match target {
+        Some(CaptureTarget::Region { bounds, .. }) => bounds.clone(),
+        Some(CaptureTarget::Window { window_id }) => {
+            window_bounds(*window_id).ok_or_else(|| {
+                RecordingError::DeviceNotFound(format!("Window {} not found", window_id))
+            })?
+        }
+        Some(CaptureTarget::Display { .. }) | None => return Ok(None),
+    };
+
+    CropRect::clipped(
+        bounds.x as i64,
+        bounds.y as i64,
+        bounds.width,
+        bounds.height,
+        frame_width,
+        frame_height,
+    )
+    .map(Some)
+    .ok_or_else(|| {
+        RecordingError::ConfigurationError(format!(
+            "Capture area is not visible on display {}",
+            display_id
+        ))
+    })
+}
+
+/// Crop origin at a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct RegionOrigin {
+    unix_time_ms: u64,
+    x: f64,
+    y: f64,
+}
+
+/// Timeline of crop origins shared between the display and input channels
+#[derive(Debug, Default)]
+pub struct CaptureRegion {
+    origins: ParkingMutex<Vec<RegionOrigin>>,
+}
+
+impl CaptureRegion {
+    /// Create an empty timeline (origin 0,0 until the first update)
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the crop origin, in display pixels, from the given time onward
+    pub fn set_origin(&self, unix_time_ms: u64, x: f64, y: f64) {
+        let mut origins = self.origins.lock();
+        if let Some(last) = origins.last() {
+            if last.x == x && last.y == y {
+                return;
+            }
+        }
+        origins.push(RegionOrigin { unix_time_ms, x, y });
+    }
+
+    /// Record the crop origin from now on
+    pub fn set_origin_now(&self, x: f64, y: f64) {
+        let now_unix_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        self.set_origin(now_unix_ms, x, y);
+    }
+
+    /// Crop origin in effect at the given time
+    pub fn origin_at(&self, unix_time_ms: u64) -> (f64, f64) {
+        let origins = self.origins.lock();
+        let index = origins.partition_point(|o| o.unix_time_ms <= unix_time_ms);
+
+        // Events before the first update use the initial crop
+        origins
+            .get(index.saturating_sub(1))
+            .map(|o| (o.x, o.y))
+            .unwrap_or((0.0, 0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clipped_rect_stays_inside_frame() {
+        // Clipped to 81px wide, then rounded down to even
+        let rect = CropRect::clipped(-10, 20, 101, 50, 81, 60).unwrap();
+        assert_eq!(
+            rect,
+            CropRect {
+                x: 0,
+                y: 20,
+                width: 80,
+                height: 40
+            }
+        );
+
+        assert!(CropRect::clipped(100, 0, 50, 50, 80, 60).is_none());
+    }
+
+    #[test]
+    fn test_moved_to_keeps_size() {
+        let rect = CropRect {
+            x: 0,
+            y: 0,
+            width: 40,
+            height: 20,
+        };
+
+        let moved = rect.moved_to(70, -5, 100, 50);
+        assert_eq!((moved.x, moved.y), (60, 0));
+        assert_eq!((moved.width, moved.height), (40, 20));
+    }
+
+    #[test]
+    fn test_crop_frame() {
+        // 4x2 frame where each pixel's bytes are its index
+        let frame: Vec<u8> = (0..8u8).flat_map(|i| [i; 4]).collect();
+        let rect = CropRect {
+            x: 1,
+            y: 0,
+            width: 2,
+            height: 2,
+        };
+
+        let cropped = crop_frame(&frame, 4, &rect);
+        let pixels: Vec<u8> = cropped.chunks(4).map(|p| p[0]).collect();
+        assert_eq!(pixels, vec![1, 2, 5, 6]);
+    }
+
+    #[test]
+    fn test_resolve_crop_targets() {
+        let window = WindowBounds {
+            x: 90,
+            y: 40,
+            width: 30,
+            height: 20,
+        };
+        let lookup = |id: u32| (id == 7).then(|| window.clone());
+        let crop = |target: &CaptureTarget| resolve_crop(Some(target), 0, 100, 50, lookup);
+
+        assert_eq!(
+            crop(&CaptureTarget::Display { display_id: 0 }).unwrap(),
+            None
+        );
+        assert_eq!(resolve_crop(None, 0, 100, 50, lookup).unwrap(), None);
+
+        let region = CaptureTarget::Region {
+            display_id: 0,
+            bounds: WindowBounds {
+                x: 10,
+                y: 5,
+                width: 41,
+                height: 20,
+            },
+        };
+        assert_eq!(
+            crop(&region).unwrap(),
+            Some(CropRect {
+                x: 10,
+                y: 5,
+                width: 40,
+                height: 20
+            })
+        );
+
+        // Windows are clipped to the frame
+        assert_eq!(
+            crop(&CaptureTarget::Window { window_id: 7 }).unwrap(),
+            Some(CropRect {
+                x: 90,
+                y: 40,
+                width: 10,
+                height: 10
+            })
+        );
+        assert!(matches!(
+            crop(&CaptureTarget::Window { window_id: 8 }),
+            Err(RecordingError::DeviceNotFound(_))
+        ));
+
+        let off_screen = CaptureTarget::Region {
+            display_id: 0,
+            bounds: WindowBounds {
+                x: 200,
+                y: 0,
+                width: 10,
+                height: 10,
+            },
+        };
+        assert!(matches!(
+            crop(&off_screen),
+            Err(RecordingError::ConfigurationError(_))
+        ));
+    }
+
+    #[test]
+    fn test_origin_timeline() {
+        let region = CaptureRegion::new();
+        assert_eq!(region.origin_at(5), (0.0, 0.0));
+
+        region.set_origin(100, 10.0, 20.0);
+        region.set_origin(200, 10.0, 20.0);
+        region.set_origin(300, 30.0, 40.0);
+
+        assert_eq!(region.origin_at(50), (10.0, 20.0));
+        assert_eq!(region.origin_at(250), (10.0, 20.0));
+        assert_eq!(region.origin_at(300), (30.0, 40.0));
+        assert_eq!(region.origin_at(1000), (30.0, 40.0));
+    }
+}
@@ -0,0 +1,1041 @@
+//! Synthetic "test pattern" capture sources
+//!
+//! Deterministic stand-ins for the real capture channels, so the recording
+//! pipeline can run without a screen, audio devices or input hardware
+//! (headless CI, demos):
+//!
+//! - `SyntheticDisplayChannel` renders color bars, a moving box, a binary
+//!   frame counter and a white flash at every whole second
+//! - `SyntheticAudioChannel` generates a sine tone or short clicks; clicks
+//!   land on whole seconds, in sync with the display flash
+//! - Scripted input (`start_scripted_input`) replays a mouse path through
+//!   `InputTrackingChannel`, producing the same files as real tracking
+//!
+//! Content is generated from elapsed time, so a session of N seconds always
+//! contains `N * fps` frames and `N * sample_rate` samples regardless of
+//! scheduling jitter. When FFmpeg is installed the output is encoded like
+//! a real recording; otherwise frames and samples are generated and counted
+//! but not written, unless another backend is given with `with_encoders`.
+
+use crate::capture::audio::{AudioEncoder, AudioFormat};
+use crate::capture::encoder::{
+    ffmpeg_backend, EncoderBackend, NullBackend, VideoOutput, VideoSpec,
+};
+use crate::capture::frames::FrameTimeline;
+use crate::capture::input::types::{MouseClick, MouseMove};
+use crate::capture::input::{InputTrackingChannel, KeystrokeMode};
+use crate::capture::media_tools::{self, MediaTool};
+use crate::capture::traits::Resolution;
+use crate::capture::video::VideoSettings;
+use crate::recorder::channel::{
+    ChannelErrorReporter, ChannelType, RecordingChannel, RecordingError, RecordingResult,
+};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
+use crate::recorder::sources::{display_file_suffix, file_basename, VideoSource};
+use crate::recorder::state::RecordingConfig;
+use async_trait::async_trait;
+use parking_lot::Mutex as ParkingMutex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+const SAMPLE_RATE: u32 = 48000;
+const CHANNELS: u16 = 2;
+
+/// Number of bits in the frame counter drawn along the top of each frame
+const COUNTER_BITS: u32 = 32;
+
+/// Test signal for a synthetic audio source
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SyntheticSignal {
+    /// Continuous sine tone
+    #[serde(rename_all = "camelCase")]
+    Tone { frequency_hz: f64 },
+
+    /// 10ms 1kHz bursts at a fixed interval, starting at 0
+    #[serde(rename_all = "camelCase")]
+    Clicks { interval_ms: u32 },
+}
+
+/// Synthetic sources to record instead of real devices
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TestSourceConfig {
+    /// Test pattern width in pixels
+    pub width: u32,
+
+    /// Test pattern height in pixels
+    pub height: u32,
+
+    /// Test pattern frame rate
+    pub fps: u32,
+
+    /// Signal used for the microphone channel
+    pub microphone_signal: SyntheticSignal,
+
+    /// Signal used for the system audio channel
+    pub system_audio_signal: SyntheticSignal,
+}
+
+impl Default for TestSourceConfig {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            fps: 30,
+            microphone_signal: SyntheticSignal::Tone {
+                frequency_hz: 440.0,
+            },
+            system_audio_signal: SyntheticSignal::Clicks { interval_ms: 1000 },
+        }
+    }
+}
+
+/// Build the synthetic channels for a recording config
+///
+/// Mirrors the real channel selection: display always, input when
+/// `track_input` is set, and audio channels when enabled. Each additional
+/// display gets its own test pattern (and scripted input).
+pub fn test_channels(
+    config: &RecordingConfig,
+    sources: &TestSourceConfig,
+) -> Vec<Box<dyn RecordingChannel>> {
+    let mut channels: Vec<Box<dyn RecordingChannel>> = vec![Box::new(
+        SyntheticDisplayChannel::new(sources.width, sources.height, sources.fps)
+            .with_video_settings(config.video.clone()),
+    )];
+
+    if config.track_input {
+        channels.push(Box::new(
+            InputTrackingChannel::scripted(ScriptedMousePath::tour(sources.width, sources.height))
+                .with_keystroke_mode(config.keystroke_mode),
+        ));
+    }
+
+    for display_id in config.additional_displays(config.display_id) {
+        let suffix = display_file_suffix(display_id);
+        channels.push(Box::new(
+            SyntheticDisplayChannel::new(sources.width, sources.height, sources.fps)
+                .with_video_settings(config.video.clone())
+                .with_file_suffix(suffix.clone()),
+        ));
+        if config.track_input {
+            channels.push(Box::new(
+                InputTrackingChannel::scripted(ScriptedMousePath::tour(
+                    sources.width,
+                    sources.height,
+                ))
+                .with_keystroke_mode(KeystrokeMode::Off)
+                .with_file_suffix(suffix),
+            ));
+        }
+    }
+
+    if config.capture_microphone {
+        channels.push(Box::new(
+            SyntheticAudioChannel::new(ChannelType::Microphone, sources.microphone_signal.clone())
+                .with_format(config.microphone_format),
+        ));
+    }
+
+    if config.capture_system_audio {
+        channels.push(Box::new(
+            SyntheticAudioChannel::new(
+                ChannelType::SystemAudio,
+                sources.system_audio_signal.clone(),
+            )
+            .with_format(config.system_audio_format),
+        ));
+    }
+
+    channels
+}
+
+/// FFmpeg when it's installed, otherwise a backend that discards everything
+fn default_encoders() -> Arc<dyn EncoderBackend> {
+    if media_tools::locate(MediaTool::Ffmpeg).is_some() {
+        ffmpeg_backend()
+    } else {
+        tracing::warn!("FFmpeg not found, synthetic recordings will not be saved");
+        Arc::new(NullBackend)
+    }
+}
+
+/// Units (frames or samples) generated per session
+#[derive(Debug, Default)]
+pub struct SyntheticStats {
+    sessions: ParkingMutex<BTreeMap<usize, u64>>,
+}
+
+impl SyntheticStats {
+    fn add(&self, session_index: usize, count: u64) {
+        *self.sessions.lock().entry(session_index).or_insert(0) += count;
+    }
+
+    /// Units generated in a session
+    pub fn session(&self, session_index: usize) -> u64 {
+        self.sessions
+            .lock()
+            .get(&session_index)
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Units generated across all sessions
+    pub fn total(&self) -> u64 {
+        self.sessions.lock().values().sum()
+    }
+
+    /// Number of sessions that generated anything
+    pub fn session_count(&self) -> usize {
+        self.sessions.lock().len()
+    }
+}
+
+/// Number of units due after `elapsed` at `rate` units per second
+fn units_due(elapsed: Duration, rate: u32) -> u64 {
+    (elapsed.as_secs_f64() * rate as f64).floor() as u64
+}
+
+// =============================================================================
+// Display
+// =============================================================================
+
+const COLOR_BARS: [[u8; 4]; 8] = [
+    // BGRA
+    [192, 192, 192, 255], // gray
+    [0, 192, 192, 255],   // yellow
+    [192, 192, 0, 255],   // cyan
+    [0, 192, 0, 255],     // green
+    [192, 0, 192, 255],   // magenta
+    [0, 0, 192, 255],     // red
+    [192, 0, 0, 255],     // blue
+    [16, 16, 16, 255],    // black
+];
+
+fn fill_rect(frame: &mut [u8], width: u32, x: u32, y: u32, w: u32, h: u32, color: [u8; 4]) {
+    let height = frame.len() as u32 / (width * 4);
+    if x >= width {
+        return;
+    }
+    for row in y..(y + h).min(height) {
+        let start = ((row * width + x) * 4) as usize;
+        let end = ((row * width + (x + w).min(width)) * 4) as usize;
+        for pixel in frame[start..end].chunks_exact_mut(4) {
+            pixel.copy_from_slice(&color);
+        }
+    }
+}
+
+/// Render test pattern frame `index` as BGRA
+pub fn render_test_pattern(width: u32, height: u32, fps: u32, index: u64) -> Vec<u8> {
+    let mut frame = vec![0u8; (width * height * 4) as usize];
+
+    // Color bars
+    let bar_width = width.div_ceil(COLOR_BARS.len() as u32);
+    for (i, color) in COLOR_BARS.iter().enumerate() {
+        fill_rect(
+            &mut frame,
+            width,
+            i as u32 * bar_width,
+            0,
+            bar_width,
+            height,
+            *color,
+        );
+    }
+
+    // Box bouncing left to right, 8px per frame
+    let box_size = (height / 6).max(2);
+    let travel = width.saturating_sub(box_size).max(1) as u64;
+    let position = (index * 8) % (travel * 2);
+    let box_x = if position < travel {
+        position
+    } else {
+        travel * 2 - position
+    } as u32;
+    fill_rect(
+        &mut frame,
+        width,
+        box_x,
+        (height - box_size) / 2,
+        box_size,
+        box_size,
+        [255, 255, 255, 255],
+    );
+
+    // Frame counter: one white (1) or black (0) cell per bit, MSB first
+    let cell_width = (width / COUNTER_BITS).max(1);
+    let cell_height = (height / 24).max(2);
+    for bit in 0..COUNTER_BITS {
+        let set = (index >> (COUNTER_BITS - 1 - bit)) & 1 == 1;
+        let color = if set {
+            [255, 255, 255, 255]
+        } else {
+            [0, 0, 0, 255]
+        };
+        fill_rect(
+            &mut frame,
+            width,
+            bit * cell_width,
+            0,
+            cell_width,
+            cell_height,
+            color,
+        );
+    }
+
+    // Sync flash on every whole second (lines up with audio clicks)
+    if fps > 0 && index.is_multiple_of(fps as u64) {
+        let flash_height = (height / 8).max(2);
+        fill_rect(
+            &mut frame,
+            width,
+            0,
+            height - flash_height,
+            width,
+            flash_height,
+            [255, 255, 255, 255],
+        );
+    }
+
+    frame
+}
+
+/// Read the frame counter back out of a rendered test pattern
+pub fn decode_frame_counter(frame: &[u8], width: u32) -> u64 {
+    let cell_width = (width / COUNTER_BITS).max(1);
+    (0..COUNTER_BITS).fold(0u64, |value, bit| {
+        // Sample the middle of the cell on the first row
+        let x = bit * cell_width + cell_width / 2;
+        let luma = frame[(x * 4) as usize];
+        (value << 1) | (luma > 127) as u64
+    })
+}
+
+/// Display channel that records a synthetic test pattern
+pub struct SyntheticDisplayChannel {
+    id: String,
+    width: u32,
+    height: u32,
+    fps: u32,
+    video: VideoSettings,
+    encoders: Arc<dyn EncoderBackend>,
+    is_recording: Arc<AtomicBool>,
+    output_dir: Option<PathBuf>,
+    session_index: usize,
+    clock: RecordingClock,
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
+    output_files: Arc<ParkingMutex<Vec<String>>>,
+    render_handle: Option<std::thread::JoinHandle<()>>,
+    stats: Arc<SyntheticStats>,
+    file_suffix: Option<String>,
+}
+
+impl SyntheticDisplayChannel {
+    /// Create a new test pattern channel
+    pub fn new(width: u32, height: u32, fps: u32) -> Self {
+        Self {
+            id: "display-synthetic".to_string(),
+            // yuv420p needs even dimensions
+            width: width.max(2) & !1,
+            height: height.max(2) & !1,
+            fps: fps.max(1),
+            video: VideoSettings::default(),
+            encoders: default_encoders(),
+            is_recording: Arc::new(AtomicBool::new(false)),
+            output_dir: None,
+            session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
+            output_files: Arc::new(ParkingMutex::new(Vec::new())),
+            render_handle: None,
+            stats: Arc::new(SyntheticStats::default()),
+            file_suffix: None,
+        }
+    }
+
+    /// Encode with the given settings
+    ///
+    /// The pattern keeps rendering at the frame rate it was created with.
+    pub fn with_video_settings(mut self, video: VideoSettings) -> Self {
+        self.video = video;
+        self
+    }
+
+    /// Encode through another backend
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
+    /// Stand in for an additional display, writing `recording-N-{suffix}` files
+    ///
+    /// The suffix is appended to the channel ID as well.
+    pub fn with_file_suffix(mut self, suffix: String) -> Self {
+        self.id = format!("{}-{}", self.id, suffix);
+        self.file_suffix = Some(suffix);
+        self
+    }
+
+    /// Frames rendered per session
+    pub fn stats(&self) -> Arc<SyntheticStats> {
+        self.stats.clone()
+    }
+}
+
+#[async_trait]
+impl RecordingChannel for SyntheticDisplayChannel {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn channel_type(&self) -> ChannelType {
+        ChannelType::Display
+    }
+
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
+        self.encoders.check_video(&self.video)?;
+        self.output_dir = Some(output_dir.to_path_buf());
+        self.session_index = session_index;
+        self.clock = clock.clone();
+
+        tracing::info!(
+            "Synthetic display initialized ({}x{} @ {}fps)",
+            self.width,
+            self.height,
+            self.fps
+        );
+        Ok(())
+    }
+
+    async fn start(&mut self) -> RecordingResult<()> {
+        if self.is_recording.load(Ordering::SeqCst) {
+            return Err(RecordingError::AlreadyRecording);
+        }
+
+        let output_dir = self.output_dir.clone().ok_or_else(|| {
+            RecordingError::ConfigurationError("Output directory not set".to_string())
+        })?;
+
+        let basename = file_basename(self.session_index, self.file_suffix.as_deref());
+        let settings = VideoSettings {
+            fps: self.fps,
+            ..self.video.clone()
+        };
+        let spec = VideoSpec {
+            width: self.width,
+            height: self.height,
+            output_file: settings.output_file(&output_dir, &basename),
+            settings,
+        };
+        let encoder = self
+            .encoders
+            .video_encoder(&spec)
+            .map(|encoder| {
+                VideoOutput::new(encoder).with_error_reporter(self.error_reporter.clone())
+            })
+            .map_err(|e| {
+                RecordingError::EncodingError(format!("Failed to start encoder: {}", e))
+            })?;
+
+        self.is_recording.store(true, Ordering::SeqCst);
+
+        let is_recording = self.is_recording.clone();
+        let output_files = self.output_files.clone();
+        let stats = self.stats.clone();
+        let (width, height, fps) = (self.width, self.height, self.fps);
+        let session_index = self.session_index;
+        let clock = self.clock.clone();
+        let channel_id = self.id.clone();
+        let health = self.health.clone();
+        health.set_target_fps(fps);
+
+        let handle = std::thread::spawn(move || {
+            let start = Instant::now();
+            let start_ms = clock.now_ms();
+            clock.mark_first_sample(&channel_id, session_index, start_ms);
+            let mut timeline = FrameTimeline::starting_at(fps, &clock, start_ms);
+            let mut rendered = 0u64;
+            let mut encoder_failed = false;
+
+            loop {
+                let running = is_recording.load(Ordering::SeqCst);
+
+                // Frame 0 is shown at t=0, so one more frame than elapsed periods
+                let due = units_due(start.elapsed(), fps) + 1;
+                while rendered < due {
+                    let frame = render_test_pattern(width, height, fps, rendered);
+                    if !encoder_failed {
+                        let write_started = Instant::now();
+                        let accepted = encoder.write_frame(&frame);
+                        health.record_write(frame.len(), write_started.elapsed(), accepted);
+                        encoder_failed = !accepted;
+                    }
+
+                    // Frames are generated on the grid, so the timeline is exact
+                    let frame_time_ms = rendered as f64 * 1000.0 / fps as f64;
+                    health.record_frame(timeline.stamp_at(
+                        start_ms + frame_time_ms,
+                        timeline.start_unix_time_ms + frame_time_ms as u64,
+                    ));
+                    rendered += 1;
+                }
+
+                if !running {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(5));
+            }
+
+            stats.add(session_index, rendered);
+            match encoder.finish() {
+                Ok(files) => output_files.lock().extend(files),
+                Err(e) => tracing::warn!("Failed to finish encoding: {}", e),
+            }
+            match timeline.write_sidecar(&output_dir, &basename) {
+                Ok(sidecar) => output_files.lock().push(sidecar),
+                Err(e) => tracing::warn!("Failed to write frame timestamps: {}", e),
+            }
+        });
+
+        self.render_handle = Some(handle);
+
+        tracing::info!("Synthetic display started (session {})", self.session_index);
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> RecordingResult<()> {
+        if !self.is_recording.load(Ordering::SeqCst) {
+            return Err(RecordingError::NotRecording);
+        }
+
+        self.is_recording.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.render_handle.take() {
+            let _ = handle.join();
+        }
+
+        tracing::info!(
+            "Synthetic display stopped ({} frames in session {})",
+            self.stats.session(self.session_index),
+            self.session_index
+        );
+        Ok(())
+    }
+
+    async fn pause(&mut self) -> RecordingResult<()> {
+        self.stop().await
+    }
+
+    async fn resume(&mut self, session_index: usize) -> RecordingResult<()> {
+        self.session_index = session_index;
+        self.start().await
+    }
+
+    fn is_recording(&self) -> bool {
+        self.is_recording.load(Ordering::SeqCst)
+    }
+
+    fn output_files(&self) -> Vec<String> {
+        self.output_files.lock().clone()
+    }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
+
+    fn video_source(&self) -> Option<VideoSource> {
+        Some(VideoSource {
+            channel_id: self.id.clone(),
+            display_id: None,
+            file_suffix: self.file_suffix.clone(),
+            capture_size: Some(Resolution {
+                width: self.width,
+                height: self.height,
+            }),
+        })
+    }
+}
+
+// =============================================================================
+// Audio
+// =============================================================================
+
+/// Sample value of a test signal at absolute sample index `n` (per channel)
+pub fn signal_sample(signal: &SyntheticSignal, n: u64) -> f32 {
+    let t = n as f64 / SAMPLE_RATE as f64;
+    match *signal {
+        SyntheticSignal::Tone { frequency_hz } => {
+            (0.25 * (std::f64::consts::TAU * frequency_hz * t).sin()) as f32
+        }
+        SyntheticSignal::Clicks { interval_ms } => {
+            let interval = (SAMPLE_RATE as u64 * interval_ms.max(1) as u64 / 1000).max(1);
+            let burst = SAMPLE_RATE as u64 / 100; // 10ms
+            if n % interval < burst {
+                (0.8 * (std::f64::consts::TAU * 1000.0 * t).sin()) as f32
+            } else {
+                0.0
+            }
+        }
+    }
+}
+
+/// Audio channel that records a synthetic test signal
+pub struct SyntheticAudioChannel {
+    id: String,
+    channel_type: ChannelType,
+    signal: SyntheticSignal,
+    format: AudioFormat,
+    encoders: Arc<dyn EncoderBackend>,
+    is_recording: Arc<AtomicBool>,
+    output_dir: Option<PathBuf>,
+    session_index: usize,
+    clock: RecordingClock,
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
+    output_files: Arc<ParkingMutex<Vec<String>>>,
+    generator_handle: Option<std::thread::JoinHandle<()>>,
+    stats: Arc<SyntheticStats>,
+}
+
+impl SyntheticAudioChannel {
+    /// Create a new synthetic audio channel
+    ///
+    /// `channel_type` selects the file the samples are written to, and should
+    /// be `Microphone` or `SystemAudio`. The format defaults to what the real
+    /// channel of that type uses.
+    pub fn new(channel_type: ChannelType, signal: SyntheticSignal) -> Self {
+        let format = match channel_type {
+            ChannelType::SystemAudio => AudioFormat::default(),
+            _ => AudioFormat::lossless(),
+        };
+        Self {
+            id: format!("{}-synthetic", channel_type),
+            channel_type,
+            signal,
+            format,
+            encoders: default_encoders(),
+            is_recording: Arc::new(AtomicBool::new(false)),
+            output_dir: None,
+            session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
+            output_files: Arc::new(ParkingMutex::new(Vec::new())),
+            generator_handle: None,
+            stats: Arc::new(SyntheticStats::default()),
+        }
+    }
+
+    /// Set the codec and container of the audio file
+    pub fn with_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Encode through another backend
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
+    /// Sample frames (per channel) generated per session
+    pub fn stats(&self) -> Arc<SyntheticStats> {
+        self.stats.clone()
+    }
+
+    fn file_suffix(&self) -> &'static str {
+        match self.channel_type {
+            ChannelType::SystemAudio => "system",
+            _ => "mic",
+        }
+    }
+}
+
+#[async_trait]
+impl RecordingChannel for SyntheticAudioChannel {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn channel_type(&self) -> ChannelType {
+        self.channel_type
+    }
+
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
+        self.encoders.check_audio(&self.format)?;
+        self.output_dir = Some(output_dir.to_path_buf());
+        self.session_index = session_index;
+        self.clock = clock.clone();
+
+        tracing::info!("Synthetic audio initialized ({:?})", self.signal);
+        Ok(())
+    }
+
+    async fn start(&mut self) -> RecordingResult<()> {
+        if self.is_recording.load(Ordering::SeqCst) {
+            return Err(RecordingError::AlreadyRecording);
+        }
+
+        let output_dir = self.output_dir.clone().ok_or_else(|| {
+            RecordingError::ConfigurationError("Output directory not set".to_string())
+        })?;
+
+        let encoder = AudioEncoder::new(
+            self.encoders.as_ref(),
+            SAMPLE_RATE,
+            CHANNELS,
+            &output_dir,
+            self.session_index,
+            self.file_suffix(),
+            self.format,
+        )
+        .map(|encoder| {
+            encoder
+                .with_error_reporter(self.error_reporter.clone())
+                .with_health(self.health.clone())
+                .with_levels(&self.id)
+        })
+        .map_err(|e| {
+            RecordingError::EncodingError(format!("Failed to start audio encoder: {}", e))
+        })?;
+
+        self.is_recording.store(true, Ordering::SeqCst);
+
+        let is_recording = self.is_recording.clone();
+        let output_files = self.output_files.clone();
+        let stats = self.stats.clone();
+        let signal = self.signal.clone();
+        let session_index = self.session_index;
+        let clock = self.clock.clone();
+        let channel_id = self.id.clone();
+
+        let handle = std::thread::spawn(move || {
+            // Samples are generated from elapsed time, so sample 0 is at the start
+            let start = Instant::now();
+            clock.mark_first_sample(&channel_id, session_index, clock.now_ms());
+            let mut generated = 0u64;
+            let mut buf: Vec<u8> = Vec::new();
+
+            loop {
+                let running = is_recording.load(Ordering::SeqCst);
+
+                let due = units_due(start.elapsed(), SAMPLE_RATE);
+                if due > generated {
+                    buf.clear();
+                    for n in generated..due {
+                        let sample = signal_sample(&signal, n).to_le_bytes();
+                        for _ in 0..CHANNELS {
+                            buf.extend_from_slice(&sample);
+                        }
+                    }
+                    encoder.write_samples(&buf);
+                    generated = due;
+                }
+
+                if !running {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+
+            stats.add(session_index, generated);
+            if let Ok(Some(file)) = encoder.finish() {
+                output_files.lock().push(file);
+            }
+        });
+
+        self.generator_handle = Some(handle);
+
+        tracing::info!("Synthetic audio started (session {})", self.session_index);
+        Ok(())
+    }
+
+    async fn stop(&mut self) -> RecordingResult<()> {
+        if !self.is_recording.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+
+        self.is_recording.store(false, Ordering::SeqCst);
+
+        if let Some(handle) = self.generator_handle.take() {
+            let _ = handle.join();
+        }
+
+        tracing::info!(
+            "Synthetic audio stopped ({} samples in session {})",
+            self.stats.session(self.session_index),
+            self.session_index
+        );
+        Ok(())
+    }
+
+    async fn pause(&mut self) -> RecordingResult<()> {
+        self.stop().await
+    }
+
+    async fn resume(&mut self, session_index: usize) -> RecordingResult<()> {
+        self.session_index = session_index;
+        self.start().await
+    }
+
+    fn is_recording(&self) -> bool {
+        self.is_recording.load(Ordering::SeqCst)
+    }
+
+    fn output_files(&self) -> Vec<String> {
+        self.output_files.lock().clone()
+    }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
+}
+
+// =============================================================================
+// Input
+// =============================================================================
+
+/// A point on a scripted mouse path
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptedPoint {
+    /// Time from the start of the path
+    pub at_ms: f64,
+    pub x: f64,
+    pub y: f64,
+    /// Left click when the cursor reaches this point
+    #[serde(default)]
+    pub click: bool,
+}
+
+/// Mouse path replayed by scripted input; loops after the last point
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptedMousePath {
+    pub points: Vec<ScriptedPoint>,
+}
+
+impl ScriptedMousePath {
+    /// Tour of a display: center, then each corner region, clicking at each stop
+    pub fn tour(width: u32, height: u32) -> Self {
+        let (w, h) = (width as f64, height as f64);
+        let stops = [
+            (0.5, 0.5),
+            (0.2, 0.2),
+            (0.8, 0.2),
+            (0.8, 0.8),
+            (0.2, 0.8),
+            (0.5, 0.5),
+        ];
+
+        let points = stops
+            .iter()
+            .enumerate()
+            .map(|(i, &(fx, fy))| ScriptedPoint {
+                at_ms: i as f64 * 800.0,
+                x: w * fx,
+                y: h * fy,
+                click: i > 0,
+            })
+            .collect();
+
+        Self { points }
+    }
+
+    fn duration_ms(&self) -> f64 {
+        self.points.last().map(|p| p.at_ms).unwrap_or(0.0)
+    }
+
+    /// Interpolated cursor position at time `t_ms`
+    pub fn position_at(&self, t_ms: f64) -> (f64, f64) {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return (0.0, 0.0),
+        };
+
+        let duration = self.duration_ms();
+        let t = if duration > 0.0 { t_ms % duration } else { 0.0 };
+
+        if t <= first.at_ms {
+            return (first.x, first.y);
+        }
+
+        for pair in self.points.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            if t <= b.at_ms {
+                let span = b.at_ms - a.at_ms;
+                let k = if span > 0.0 {
+                    (t - a.at_ms) / span
+                } else {
+                    1.0
+                };
+                return (a.x + (b.x - a.x) * k, a.y + (b.y - a.y) * k);
+            }
+        }
+
+        (last.x, last.y)
+    }
+
+    /// Points with `click` set that were reached in (from_ms, to_ms]
+    fn clicks_between(&self, from_ms: f64, to_ms: f64) -> Vec<&ScriptedPoint> {
+        let duration = self.duration_ms();
+        if duration <= 0.0 {
+            return Vec::new();
+        }
+
+        let mut clicks = Vec::new();
+        let mut cycle_start = (from_ms / duration).floor() * duration;
+        while cycle_start <= to_ms {
+            for point in self.points.iter().filter(|p| p.click) {
+                let at = cycle_start + point.at_ms;
+                if at > from_ms && at <= to_ms {
+                    clicks.push(point);
+                }
+            }
+            cycle_start += duration;
+        }
+        clicks
+    }
+}
+
+/// Replay a scripted mouse path into the input tracking buffers
+///
+/// Counterpart of the platform `start_input_tracking` functions.
+pub fn start_scripted_input(
+    is_recording: Arc<AtomicBool>,
+    mouse_moves: Arc<ParkingMutex<Vec<MouseMove>>>,
+    mouse_clicks: Arc<ParkingMutex<Vec<MouseClick>>>,
+    start_time: Instant,
+    poll_interval: Duration,
+    unix_ms_fn: fn() -> u64,
+    path: ScriptedMousePath,
+) -> RecordingResult<std::thread::JoinHandle<()>> {
+    if path.points.is_empty() {
+        return Err(RecordingError::ConfigurationError(
+            "Scripted mouse path has no points".to_string(),
+        ));
+    }
+
+    let handle = std::thread::spawn(move || {
+        // Clicks before this session were replayed (or skipped) already
+        let mut last_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+
+        while is_recording.load(Ordering::SeqCst) {
+            let now_ms = start_time.elapsed().as_secs_f64() * 1000.0;
+            let (x, y) = path.position_at(now_ms);
+            let unix_time_ms = unix_ms_fn();
+
+            mouse_moves.lock().push(MouseMove {
+                x,
+                y,
+                cursor_id: "synthetic-arrow".to_string(),
+                active_modifiers: Vec::new(),
+                process_time_ms: now_ms,
+                unix_time_ms,
+            });
+
+            for point in path.clicks_between(last_ms, now_ms) {
+                let mut clicks = mouse_clicks.lock();
+                for event_type in ["down", "up"] {
+                    clicks.push(MouseClick {
+                        x: point.x,
+                        y: point.y,
+                        button: "left".to_string(),
+                        event_type: event_type.to_string(),
+                        click_count: 1,
+                        active_modifiers: Vec::new(),
+                        process_time_ms: now_ms,
+                        unix_time_ms,
+                    });
+                }
+            }
+
+            last_ms = now_ms;
+            std::thread::sleep(poll_interval);
+        }
+    });
+
+    Ok(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_counter_roundtrip() {
+        for index in [0u64, 1, 29, 30, 12345] {
+            let frame = render_test_pattern(320, 180, 30, index);
+            assert_eq!(frame.len(), 320 * 180 * 4);
+            assert_eq!(decode_frame_counter(&frame, 320), index);
+        }
+    }
+
+    #[test]
+    fn test_flash_on_whole_seconds() {
+        let bottom_left = |frame: &[u8]| frame[((179 * 320) * 4) as usize];
+
+        assert_eq!(bottom_left(&render_test_pattern(320, 180, 30, 30)), 255);
+        assert_ne!(bottom_left(&render_test_pattern(320, 180, 30, 31)), 255);
+    }
+
+    #[test]
+    fn test_clicks_on_interval() {
+        let signal = SyntheticSignal::Clicks { interval_ms: 1000 };
+        // Silent just before the second click, audible inside it
+        assert_eq!(signal_sample(&signal, 47_999), 0.0);
+        assert_ne!(signal_sample(&signal, 48_010), 0.0);
+    }
+
+    #[test]
+    fn test_scripted_path_interpolates_and_loops() {
+        let path = ScriptedMousePath {
+            points: vec![
+                ScriptedPoint {
+                    at_ms: 0.0,
+                    x: 0.0,
+                    y: 0.0,
+                    click: false,
+                },
+                ScriptedPoint {
+                    at_ms: 100.0,
+                    x: 100.0,
+                    y: 50.0,
+                    click: true,
+                },
+            ],
+        };
+
+        assert_eq!(path.position_at(50.0), (50.0, 25.0));
+        assert_eq!(path.position_at(150.0), (50.0, 25.0));
+        assert_eq!(path.clicks_between(0.0, 250.0).len(), 2);
+        assert_eq!(path.clicks_between(100.0, 150.0).len(), 0);
+    }
+}
@@ -51,7 +51,7 @@ pub struct WindowInfo {
 }
 
 /// Window bounds
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct WindowBounds {
     pub x: i32,
     pub y: i32,
@@ -59,8 +59,29 @@ pub struct WindowBounds {
     pub height: u32,
 }
 
+/// What a recording captures
+///
+/// Window bounds are reported in global screen coordinates, while region
+/// bounds are relative to the display in pixels (the same space as the
+/// recorded video and input events).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum CaptureTarget {
+    /// A whole display
+    #[serde(rename_all = "camelCase")]
+    Display { display_id: u32 },
+
+    /// A single window, followed as it moves
+    #[serde(rename_all = "camelCase")]
+    Window { window_id: u32 },
+
+    /// A fixed rectangle on a display
+    #[serde(rename_all = "camelCase")]
+    Region { display_id: u32, bounds: WindowBounds },
+}
+
 /// Information about an audio device
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AudioDeviceInfo {
     /// Unique device ID
@@ -91,7 +112,7 @@ pub struct CameraInfo {
 }
 
 /// Video resolution
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Resolution {
     pub width: u32,
     pub height: u32,
@@ -110,7 +131,12 @@ pub fn has_screen_recording_permission() -> bool {
         true
     }
     
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(target_os = "linux")]
+    {
+        crate::capture::linux::permissions::has_screen_recording_permission()
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     {
         false
     }
@@ -128,7 +154,12 @@ pub fn request_screen_recording_permission() -> bool {
         true
     }
     
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(target_os = "linux")]
+    {
+        crate::capture::linux::permissions::request_screen_recording_permission()
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     {
         false
     }
@@ -0,0 +1,422 @@
+//! Video encoding settings
+//!
+//! Display channels feed raw BGRA frames to FFmpeg. `VideoSettings` decides
+//! the capture frame rate and how those frames are encoded: output scale,
+//! codec, rate control and encoder speed. The defaults (30fps, native size,
+//! H.264 at CRF 18, veryfast) suit most screen recordings; cursor-heavy demos
+//! want 60fps, long meetings a low bitrate and a downscaled output.
+//!
+//! Scaling only changes the video; input events stay in capture pixels and
+//! the source's `capture_size` in `sources.json` records what those were.
+
+use crate::recorder::channel::{RecordingError, RecordingResult};
+use crate::recorder::segment::FRAGMENTED_MP4_ARGS;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// File extensions of every `VideoCodec`'s container, for finding recorded videos
+pub const VIDEO_EXTENSIONS: [&str; 2] = ["mp4", "mkv"];
+
+/// Video codec of a display recording
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum VideoCodec {
+    /// H.264 (libx264) in fragmented MP4
+    #[default]
+    H264,
+    /// HEVC (libx265) in fragmented MP4
+    Hevc,
+    /// VP9 (libvpx-vp9) in fragmented MP4
+    Vp9,
+    /// AV1 (SVT-AV1) in fragmented MP4
+    Av1,
+    /// Lossless FFV1 in Matroska (`.mkv`), large files meant for re-encoding
+    Ffv1,
+}
+
+impl VideoCodec {
+    /// FFmpeg encoder used for this codec
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            VideoCodec::H264 => "libx264",
+            VideoCodec::Hevc => "libx265",
+            VideoCodec::Vp9 => "libvpx-vp9",
+            VideoCodec::Av1 => "libsvtav1",
+            VideoCodec::Ffv1 => "ffv1",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            VideoCodec::Ffv1 => "mkv",
+            _ => "mp4",
+        }
+    }
+
+    /// FFmpeg muxer writing the codec's container
+    pub fn muxer(&self) -> &'static str {
+        match self {
+            VideoCodec::Ffv1 => "matroska",
+            _ => "mp4",
+        }
+    }
+
+    pub fn is_lossless(&self) -> bool {
+        matches!(self, VideoCodec::Ffv1)
+    }
+
+    /// CRF values the encoder accepts
+    fn crf_range(&self) -> std::ops::RangeInclusive<u32> {
+        match self {
+            VideoCodec::H264 | VideoCodec::Hevc => 0..=51,
+            _ => 0..=63,
+        }
+    }
+}
+
+/// Size of the encoded video relative to the captured frames
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputScale {
+    /// Encode at the captured size
+    #[default]
+    #[serde(rename = "native")]
+    Native,
+    /// Shorter side at most 1080 pixels
+    #[serde(rename = "1080p")]
+    Hd1080,
+    /// Shorter side at most 720 pixels
+    #[serde(rename = "720p")]
+    Hd720,
+}
+
+impl OutputScale {
+    /// Encoded size for a captured size
+    ///
+    /// Frames are only ever scaled down, keeping the aspect ratio. Scaled
+    /// dimensions are rounded to even numbers for yuv420p.
+    pub fn output_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let limit = match self {
+            OutputScale::Native => return (width, height),
+            OutputScale::Hd1080 => 1080,
+            OutputScale::Hd720 => 720,
+        };
+        let shorter = width.min(height);
+        if shorter <= limit {
+            return (width, height);
+        }
+        let scale = |side: u32| ((side as u64 * limit as u64 / shorter as u64) as u32 & !1).max(2);
+        (scale(width), scale(height))
+    }
+}
+
+/// Rate control of the encoder (ignored by lossless codecs)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(
+    tag = "mode",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum VideoQuality {
+    /// Constant quality; lower is better
+    Crf { crf: u32 },
+    /// Average bitrate
+    Bitrate { kbps: u32 },
+}
+
+impl Default for VideoQuality {
+    fn default() -> Self {
+        VideoQuality::Crf { crf: 18 }
+    }
+}
+
+/// Encoder speed, traded against compression
+///
+/// x264 and x265 take the preset by name; VP9 and AV1 map it onto their
+/// numeric speed settings.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum EncoderPreset {
+    Ultrafast,
+    #[default]
+    Veryfast,
+    Fast,
+    Medium,
+    Slow,
+}
+
+impl EncoderPreset {
+    fn x26x_name(&self) -> &'static str {
+        match self {
+            EncoderPreset::Ultrafast => "ultrafast",
+            EncoderPreset::Veryfast => "veryfast",
+            EncoderPreset::Fast => "fast",
+            EncoderPreset::Medium => "medium",
+            EncoderPreset::Slow => "slow",
+        }
+    }
+
+    /// libvpx `-cpu-used` in realtime mode
+    fn vp9_speed(&self) -> u32 {
+        match self {
+            EncoderPreset::Ultrafast => 8,
+            EncoderPreset::Veryfast => 7,
+            EncoderPreset::Fast => 6,
+            EncoderPreset::Medium => 5,
+            EncoderPreset::Slow => 4,
+        }
+    }
+
+    /// SVT-AV1 `-preset`
+    fn svt_av1_preset(&self) -> u32 {
+        match self {
+            EncoderPreset::Ultrafast => 12,
+            EncoderPreset::Veryfast => 10,
+            EncoderPreset::Fast => 8,
+            EncoderPreset::Medium => 6,
+            EncoderPreset::Slow => 4,
+        }
+    }
+}
+
+/// Frame rate and encoding of display recordings
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VideoSettings {
+    /// Capture and output frame rate
+    pub fps: u32,
+    pub scale: OutputScale,
+    pub codec: VideoCodec,
+    pub quality: VideoQuality,
+    pub preset: EncoderPreset,
+}
+
+impl Default for VideoSettings {
+    fn default() -> Self {
+        Self {
+            fps: 30,
+            scale: OutputScale::default(),
+            codec: VideoCodec::default(),
+            quality: VideoQuality::default(),
+            preset: EncoderPreset::default(),
+        }
+    }
+}
+
+impl VideoSettings {
+    /// Check the values are ones the encoder accepts
+    pub fn validate(&self) -> RecordingResult<()> {
+        if !(1..=240).contains(&self.fps) {
+            return Err(RecordingError::ConfigurationError(format!(
+                "Frame rate must be between 1 and 240 fps, got {}",
+                self.fps
+            )));
+        }
+        if self.codec.is_lossless() {
+            return Ok(());
+        }
+        match self.quality {
+            VideoQuality::Crf { crf } if !self.codec.crf_range().contains(&crf) => {
+                Err(RecordingError::ConfigurationError(format!(
+                    "CRF for {:?} must be at most {}, got {}",
+                    self.codec,
+                    self.codec.crf_range().end(),
+                    crf
+                )))
+            }
+            VideoQuality::Bitrate { kbps } if !(100..=500_000).contains(&kbps) => {
+                Err(RecordingError::ConfigurationError(format!(
+                    "Video bitrate must be between 100 and 500000 kbps, got {}",
+                    kbps
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Output file of a session
+    pub fn output_file(&self, output_dir: &Path, basename: &str) -> PathBuf {
+        output_dir.join(format!("{}.{}", basename, self.codec.extension()))
+    }
+
+    /// Rough bitrate of the video for frames of the given captured size
+    ///
+    /// Screen content at CRF 18 takes about 0.08 bits per pixel in H.264,
+    /// halving every 6 CRF steps; the newer codecs need around 60% of that.
+    /// Only a starting point for the disk space guard, which measures the
+    /// real rate once recording.
+    pub fn estimated_kbps(&self, width: u32, height: u32) -> u64 {
+        let (out_width, out_height) = self.scale.output_size(width, height);
+        let pixels_per_sec = out_width as f64 * out_height as f64 * self.fps as f64;
+        let bits_per_pixel = match (self.codec, self.quality) {
+            (VideoCodec::Ffv1, _) => 3.0,
+            (_, VideoQuality::Bitrate { kbps }) => return kbps as u64,
+            (codec, VideoQuality::Crf { crf }) => {
+                let h264 = 0.08 * 2f64.powf((18.0 - crf as f64) / 6.0);
+                if codec == VideoCodec::H264 {
+                    h264
+                } else {
+                    h264 * 0.6
+                }
+            }
+        };
+        (pixels_per_sec * bits_per_pixel / 1000.0).ceil() as u64
+    }
+
+    /// FFmpeg output arguments for frames of the given captured size
+    pub fn ffmpeg_args(&self, width: u32, height: u32) -> Vec<String> {
+        let mut args: Vec<String> = Vec::new();
+
+        let (out_width, out_height) = self.scale.output_size(width, height);
+        if (out_width, out_height) != (width, height) {
+            args.extend(["-vf".into(), format!("scale={}:{}", out_width, out_height)]);
+        }
+
+        args.extend(["-c:v".into(), self.codec.encoder().into()]);
+        match self.codec {
+            VideoCodec::H264 | VideoCodec::Hevc => {
+                args.extend(["-preset".into(), self.preset.x26x_name().into()]);
+            }
+            VideoCodec::Vp9 => args.extend([
+                "-deadline".into(),
+                "realtime".into(),
+                "-cpu-used".into(),
+                self.preset.vp9_speed().to_string(),
+                "-row-mt".into(),
+                "1".into(),
+            ]),
+            VideoCodec::Av1 => {
+                args.extend(["-preset".into(), self.preset.svt_av1_preset().to_string()]);
+            }
+            VideoCodec::Ffv1 => args.extend(["-level".into(), "3".into()]),
+        }
+
+        if !self.codec.is_lossless() {
+            match self.quality {
+                VideoQuality::Crf { crf } => {
+                    args.extend(["-crf".into(), crf.to_string()]);
+                    // libvpx only holds a constant quality without a target bitrate
+                    if self.codec == VideoCodec::Vp9 {
+                        args.extend(["-b:v".into(), "0".into()]);
+                    }
+                }
+                VideoQuality::Bitrate { kbps } => {
+                    args.extend(["-b:v".into(), format!("{}k", kbps)]);
+                }
+            }
+        }
+
+        // FFV1 keeps the captured RGB as is
+        let pix_fmt = if self.codec.is_lossless() {
+            "bgr0"
+        } else {
+            "yuv420p"
+        };
+        args.extend(["-pix_fmt".into(), pix_fmt.into()]);
+        // Keyframe every 2 seconds
+        args.extend(["-g".into(), (self.fps * 2).to_string()]);
+
+        // Tagged so Apple players accept HEVC in MP4
+        if self.codec == VideoCodec::Hevc {
+            args.extend(["-tag:v".into(), "hvc1".into()]);
+        }
+        // Matroska is written sequentially and readable after a crash anyway
+        if self.codec.extension() == "mp4" {
+            args.extend(FRAGMENTED_MP4_ARGS.iter().map(|s| s.to_string()));
+        }
+        args
+    }
+}
+
+/// Find the video of a session, whichever container it was recorded in
+pub fn find_video_file(recording_dir: &Path, basename: &str) -> Option<PathBuf> {
+    VIDEO_EXTENSIONS
+        .iter()
+        .map(|ext| recording_dir.join(format!("{}.{}", basename, ext)))
+        .find(|path| path.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_size() {
+        assert_eq!(OutputScale::Native.output_size(3840, 2160), (3840, 2160));
+        assert_eq!(OutputScale::Hd1080.output_size(3840, 2160), (1920, 1080));
+        assert_eq!(OutputScale::Hd720.output_size(2560, 1600), (1152, 720));
+        // Portrait windows are limited by their width
+        assert_eq!(OutputScale::Hd720.output_size(1080, 1920), (720, 1280));
+        // Never scaled up
+        assert_eq!(OutputScale::Hd1080.output_size(1280, 720), (1280, 720));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(VideoSettings::default().validate().is_ok());
+
+        let sixty = VideoSettings {
+            fps: 60,
+            codec: VideoCodec::Vp9,
+            quality: VideoQuality::Crf { crf: 60 },
+            ..Default::default()
+        };
+        assert!(sixty.validate().is_ok());
+        assert!(VideoSettings {
+            codec: VideoCodec::H264,
+            ..sixty.clone()
+        }
+        .validate()
+        .is_err());
+        assert!(VideoSettings {
+            fps: 0,
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_ffmpeg_args() {
+        let settings = VideoSettings {
+            scale: OutputScale::Hd720,
+            quality: VideoQuality::Bitrate { kbps: 1500 },
+            ..Default::default()
+        };
+        let args = settings.ffmpeg_args(1920, 1080).join(" ");
+        assert!(args.starts_with("-vf scale=1280:720 -c:v libx264 -preset veryfast -b:v 1500k"));
+        assert!(args.contains("-g 60"));
+        assert!(args.contains("empty_moov"));
+
+        let lossless = VideoSettings {
+            codec: VideoCodec::Ffv1,
+            ..Default::default()
+        };
+        let args = lossless.ffmpeg_args(1920, 1080).join(" ");
+        assert!(args.starts_with("-c:v ffv1 -level 3 -pix_fmt bgr0"));
+        assert!(!args.contains("movflags"));
+        assert_eq!(
+            lossless.output_file(Path::new("/rec"), "recording-0"),
+            Path::new("/rec/recording-0.mkv")
+        );
+    }
+
+    #[test]
+    fn test_estimated_kbps() {
+        let settings = VideoSettings::default();
+        assert_eq!(settings.estimated_kbps(1920, 1080), 4977);
+
+        // Six CRF steps halve it, and it's estimated at the output size
+        let smaller = VideoSettings {
+            scale: OutputScale::Hd1080,
+            quality: VideoQuality::Crf { crf: 24 },
+            ..Default::default()
+        };
+        assert_eq!(smaller.estimated_kbps(3840, 2160), 2489);
+
+        let bitrate = VideoSettings {
+            quality: VideoQuality::Bitrate { kbps: 1500 },
+            ..Default::default()
+        };
+        assert_eq!(bitrate.estimated_kbps(3840, 2160), 1500);
+    }
+}
@@ -1,4 +1,4 @@
-use crate::capture::input::types::{CursorInfo, MouseClick, MouseMove};
+use crate::capture::input::types::{CursorInfo, KeyEvent, MouseClick, MouseMove, MouseScroll};
 use crate::recorder::channel::{RecordingError, RecordingResult};
 use parking_lot::Mutex as ParkingMutex;
 use std::collections::HashMap;
@@ -11,6 +11,9 @@ pub fn start_input_tracking(
     _is_recording: Arc<AtomicBool>,
     _mouse_moves: Arc<ParkingMutex<Vec<MouseMove>>>,
     _mouse_clicks: Arc<ParkingMutex<Vec<MouseClick>>>,
+    _mouse_scrolls: Arc<ParkingMutex<Vec<MouseScroll>>>,
+    _keystrokes: Arc<ParkingMutex<Vec<KeyEvent>>>,
+    _track_keys: bool,
     _cursors: Arc<ParkingMutex<HashMap<String, CursorInfo>>>,
     _cursors_dir: PathBuf,
     _start_time: Instant,
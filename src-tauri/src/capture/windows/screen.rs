@@ -1,21 +1,29 @@
 //! Windows screen capture using GDI BitBlt
 //!
 //! This module provides screen capture functionality using the Windows GDI API.
-//! Frames are captured and encoded to H.264 using FFmpeg.
-
-use crate::capture::traits::DisplayInfo;
-use crate::recorder::channel::{ChannelType, RecordingChannel, RecordingError, RecordingResult};
+//! Frames are captured and encoded by the channel's `EncoderBackend`
+//! according to its `VideoSettings`.
+
+use crate::capture::encoder::{ffmpeg_backend, EncoderBackend, VideoOutput, VideoSpec};
+use crate::capture::frames::{FramePacer, FrameTimeline};
+use crate::capture::region::{crop_frame, resolve_crop, CaptureRegion};
+use crate::capture::traits::{CaptureTarget, DisplayInfo, Resolution, WindowBounds, WindowInfo};
+use crate::capture::video::VideoSettings;
+use crate::recorder::channel::{
+    ChannelErrorReporter, ChannelType, RecordingChannel, RecordingError, RecordingResult,
+};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
+use crate::recorder::sources::{file_basename, VideoSource};
 use async_trait::async_trait;
 use parking_lot::Mutex as ParkingMutex;
-use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::process::{Child, Command, Stdio};
-use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 #[cfg(target_os = "windows")]
 use windows::{
-    Win32::Foundation::{BOOL, LPARAM, RECT},
+    Win32::Foundation::{BOOL, HWND, LPARAM, RECT},
     Win32::Graphics::Gdi::{
         BitBlt, CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject,
         EnumDisplayMonitors, GetDIBits, GetMonitorInfoW, SelectObject, BITMAPINFO,
@@ -113,6 +121,221 @@ pub fn get_displays() -> Vec<DisplayInfo> {
     }]
 }
 
+/// Monitor rectangles in virtual screen coordinates, in display ID order
+#[cfg(target_os = "windows")]
+fn monitor_rects() -> Vec<RECT> {
+    let mut rects: Vec<RECT> = Vec::new();
+
+    unsafe extern "system" fn enum_rects_callback(
+        _hmonitor: HMONITOR,
+        _hdc: HDC,
+        rect: *mut RECT,
+        lparam: LPARAM,
+    ) -> BOOL {
+        let rects = &mut *(lparam.0 as *mut Vec<RECT>);
+        if let Some(rect) = rect.as_ref() {
+            rects.push(*rect);
+        }
+        BOOL::from(true)
+    }
+
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            HDC::default(),
+            None,
+            Some(enum_rects_callback),
+            LPARAM(&mut rects as *mut Vec<RECT> as isize),
+        );
+    }
+
+    rects
+}
+
+/// Name of the executable that owns a window (e.g. "chrome")
+#[cfg(target_os = "windows")]
+fn window_process_name(hwnd: HWND) -> String {
+    use windows::core::PWSTR;
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{
+        OpenProcess, QueryFullProcessImageNameW, PROCESS_NAME_WIN32,
+        PROCESS_QUERY_LIMITED_INFORMATION,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+    unsafe {
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid as *mut u32));
+        if pid == 0 {
+            return String::new();
+        }
+
+        let Ok(process) = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) else {
+            return String::new();
+        };
+
+        let mut buffer = [0u16; 1024];
+        let mut size = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(
+            process,
+            PROCESS_NAME_WIN32,
+            PWSTR(buffer.as_mut_ptr()),
+            &mut size,
+        );
+        let _ = CloseHandle(process);
+
+        if result.is_err() {
+            return String::new();
+        }
+
+        let path = String::from_utf16_lossy(&buffer[..size as usize]);
+        Path::new(&path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or(path)
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn hwnd_from_id(window_id: u32) -> HWND {
+    HWND(window_id as usize as *mut std::ffi::c_void)
+}
+
+/// Query a window's title, owner and bounds in virtual screen coordinates
+#[cfg(target_os = "windows")]
+fn query_window(hwnd: HWND) -> Option<WindowInfo> {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetWindowRect, GetWindowTextLengthW, GetWindowTextW, IsIconic, IsWindow,
+        IsWindowVisible,
+    };
+
+    unsafe {
+        if !IsWindow(hwnd).as_bool() {
+            return None;
+        }
+
+        let mut rect = RECT::default();
+        GetWindowRect(hwnd, &mut rect).ok()?;
+
+        let length = GetWindowTextLengthW(hwnd);
+        let mut title = vec![0u16; length.max(0) as usize + 1];
+        let copied = GetWindowTextW(hwnd, &mut title);
+        let title = String::from_utf16_lossy(&title[..copied.max(0) as usize]);
+
+        Some(WindowInfo {
+            // Window handles only use the low 32 bits, even on 64-bit Windows
+            id: hwnd.0 as usize as u32,
+            title,
+            app_name: window_process_name(hwnd),
+            bounds: WindowBounds {
+                x: rect.left,
+                y: rect.top,
+                width: (rect.right - rect.left).max(0) as u32,
+                height: (rect.bottom - rect.top).max(0) as u32,
+            },
+            is_on_screen: IsWindowVisible(hwnd).as_bool() && !IsIconic(hwnd).as_bool(),
+        })
+    }
+}
+
+/// Get list of capturable windows (visible top-level windows with a title)
+#[cfg(target_os = "windows")]
+pub fn get_windows() -> Vec<WindowInfo> {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindowLongW, GWL_EXSTYLE, WS_EX_TOOLWINDOW,
+    };
+
+    let mut handles: Vec<HWND> = Vec::new();
+
+    unsafe extern "system" fn enum_windows_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let handles = &mut *(lparam.0 as *mut Vec<HWND>);
+        handles.push(hwnd);
+        BOOL::from(true)
+    }
+
+    unsafe {
+        let _ = EnumWindows(
+            Some(enum_windows_callback),
+            LPARAM(&mut handles as *mut Vec<HWND> as isize),
+        );
+    }
+
+    handles
+        .into_iter()
+        .filter(|&hwnd| {
+            // Tool windows (palettes, tray popups) don't show up in the taskbar
+            let ex_style = unsafe { GetWindowLongW(hwnd, GWL_EXSTYLE) } as u32;
+            ex_style & WS_EX_TOOLWINDOW.0 == 0
+        })
+        .filter_map(query_window)
+        .filter(|info| {
+            info.is_on_screen
+                && !info.title.is_empty()
+                && info.bounds.width > 1
+                && info.bounds.height > 1
+        })
+        .collect()
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn get_windows() -> Vec<WindowInfo> {
+    Vec::new()
+}
+
+/// Get information about a single window
+#[cfg(target_os = "windows")]
+pub fn get_window_info(window_id: u32) -> Option<WindowInfo> {
+    query_window(hwnd_from_id(window_id))
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn get_window_info(_window_id: u32) -> Option<WindowInfo> {
+    None
+}
+
+/// Index of the display that contains the center of a window
+#[cfg(target_os = "windows")]
+pub fn display_for_window(window_id: u32) -> Option<u32> {
+    let window = get_window_info(window_id)?;
+    let center_x = window.bounds.x + window.bounds.width as i32 / 2;
+    let center_y = window.bounds.y + window.bounds.height as i32 / 2;
+
+    let rects = monitor_rects();
+    let index = rects
+        .iter()
+        .position(|r| {
+            center_x >= r.left && center_x < r.right && center_y >= r.top && center_y < r.bottom
+        })
+        .unwrap_or(0);
+
+    Some(index as u32)
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn display_for_window(_window_id: u32) -> Option<u32> {
+    None
+}
+
+/// Window bounds relative to a display
+#[cfg(target_os = "windows")]
+fn window_bounds_on_display(window_id: u32, display_id: u32) -> Option<WindowBounds> {
+    let window = get_window_info(window_id)?;
+    let (origin_x, origin_y) = monitor_rects()
+        .get(display_id as usize)
+        .map(|r| (r.left, r.top))
+        .unwrap_or((0, 0));
+
+    Some(WindowBounds {
+        x: window.bounds.x - origin_x,
+        y: window.bounds.y - origin_y,
+        ..window.bounds
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+fn window_bounds_on_display(_window_id: u32, _display_id: u32) -> Option<WindowBounds> {
+    None
+}
+
 /// Capture a single frame from the screen using BitBlt
 #[cfg(target_os = "windows")]
 fn capture_display_frame(display_id: u32) -> Option<(Vec<u8>, u32, u32)> {
@@ -210,134 +433,6 @@ fn capture_display_frame(_display_id: u32) -> Option<(Vec<u8>, u32, u32)> {
     None
 }
 
-/// FFmpeg encoder for MP4 output
-struct FFmpegEncoder {
-    process: ParkingMutex<Option<Child>>,
-    frame_count: AtomicU64,
-    running: AtomicBool,
-    output_dir: PathBuf,
-    session_index: usize,
-}
-
-impl FFmpegEncoder {
-    fn new(
-        width: u32,
-        height: u32,
-        fps: u32,
-        output_dir: &Path,
-        session_index: usize,
-    ) -> Result<Self, std::io::Error> {
-        std::fs::create_dir_all(output_dir)?;
-
-        let output_file = output_dir
-            .join(format!("recording-{session_index}.mp4"))
-            .to_string_lossy()
-            .to_string();
-
-        // Start FFmpeg process
-        let process = Command::new("ffmpeg")
-            .args([
-                "-y",
-                "-f",
-                "rawvideo",
-                "-pixel_format",
-                "bgra",
-                "-video_size",
-                &format!("{width}x{height}"),
-                "-framerate",
-                &fps.to_string(),
-                "-i",
-                "-",
-                "-c:v",
-                "libx264",
-                "-preset",
-                "veryfast",
-                "-pix_fmt",
-                "yuv420p",
-                "-crf",
-                "18",
-                "-g",
-                &(fps * 2).to_string(),
-                "-movflags",
-                "+faststart",
-                &output_file,
-            ])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn()?;
-
-        tracing::info!(
-            "Started FFmpeg encoder: {}x{} @ {}fps, output: {:?}",
-            width,
-            height,
-            fps,
-            output_dir
-        );
-
-        Ok(Self {
-            process: ParkingMutex::new(Some(process)),
-            frame_count: AtomicU64::new(0),
-            running: AtomicBool::new(true),
-            output_dir: output_dir.to_path_buf(),
-            session_index,
-        })
-    }
-
-    fn write_frame(&self, data: &[u8]) -> bool {
-        if !self.running.load(Ordering::Relaxed) {
-            return false;
-        }
-
-        let mut guard = self.process.lock();
-        if let Some(ref mut process) = *guard {
-            if let Some(ref mut stdin) = process.stdin {
-                if stdin.write_all(data).is_ok() {
-                    self.frame_count.fetch_add(1, Ordering::Relaxed);
-                    return true;
-                }
-            }
-        }
-        false
-    }
-
-    fn frame_count(&self) -> u64 {
-        self.frame_count.load(Ordering::Relaxed)
-    }
-
-    fn finish(&self) -> Result<Vec<String>, std::io::Error> {
-        self.running.store(false, Ordering::Relaxed);
-        let mut guard = self.process.lock();
-        if let Some(mut process) = guard.take() {
-            drop(process.stdin.take());
-            let output = process.wait_with_output()?;
-            if !output.status.success() {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                tracing::warn!("FFmpeg exited with status {}: {}", output.status, stderr);
-            }
-        }
-
-        let output_file = self
-            .output_dir
-            .join(format!("recording-{}.mp4", self.session_index))
-            .to_string_lossy()
-            .to_string();
-
-        let mut files = Vec::new();
-        if std::path::Path::new(&output_file).exists() {
-            files.push(output_file.clone());
-        }
-
-        tracing::info!(
-            "FFmpeg finished: {} frames, output: {}",
-            self.frame_count(),
-            output_file,
-        );
-
-        Ok(files)
-    }
-}
-
 /// Display capture channel for Windows
 pub struct DisplayCaptureChannel {
     id: String,
@@ -345,12 +440,19 @@ pub struct DisplayCaptureChannel {
     is_recording: Arc<AtomicBool>,
     output_dir: Option<PathBuf>,
     session_index: usize,
+    clock: RecordingClock,
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
     output_files: Arc<ParkingMutex<Vec<String>>>,
-    encoder: Option<Arc<FFmpegEncoder>>,
-    capture_handle: Option<tokio::task::JoinHandle<()>>,
+    encoders: Arc<dyn EncoderBackend>,
+    encoder: Option<Arc<VideoOutput>>,
+    capture_handle: Option<tokio::task::JoinHandle<FrameTimeline>>,
     width: u32,
     height: u32,
-    fps: u32,
+    video: VideoSettings,
+    target: Option<CaptureTarget>,
+    capture_region: Option<Arc<CaptureRegion>>,
+    file_suffix: Option<String>,
 }
 
 impl DisplayCaptureChannel {
@@ -361,14 +463,53 @@ impl DisplayCaptureChannel {
             is_recording: Arc::new(AtomicBool::new(false)),
             output_dir: None,
             session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
             output_files: Arc::new(ParkingMutex::new(Vec::new())),
+            encoders: ffmpeg_backend(),
             encoder: None,
             capture_handle: None,
             width: 1920,
             height: 1080,
-            fps: 30,
+            video: VideoSettings::default(),
+            target: None,
+            capture_region: None,
+            file_suffix: None,
         }
     }
+
+    /// Capture a window or region of the display instead of all of it
+    ///
+    /// The current crop origin is published to `capture_region` so input
+    /// tracking can use the same coordinate space.
+    pub fn with_target(mut self, target: CaptureTarget, capture_region: Arc<CaptureRegion>) -> Self {
+        self.target = Some(target);
+        self.capture_region = Some(capture_region);
+        self
+    }
+
+    /// Capture at the settings' frame rate and encode with them
+    pub fn with_video_settings(mut self, video: VideoSettings) -> Self {
+        self.video = video;
+        self
+    }
+
+    /// Encode through another backend (FFmpeg by default)
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
+    /// Record an additional display to `recording-N-{suffix}` files
+    pub fn with_file_suffix(mut self, suffix: String) -> Self {
+        self.file_suffix = Some(suffix);
+        self
+    }
+
+    fn basename(&self) -> String {
+        file_basename(self.session_index, self.file_suffix.as_deref())
+    }
 }
 
 #[async_trait]
@@ -381,13 +522,13 @@ impl RecordingChannel for DisplayCaptureChannel {
         ChannelType::Display
     }
 
-    async fn initialize(&mut self, output_dir: &Path, session_index: usize) -> RecordingResult<()> {
-        // Check if FFmpeg is available
-        if Command::new("ffmpeg").arg("-version").output().is_err() {
-            return Err(RecordingError::ConfigurationError(
-                "FFmpeg not found. Please install FFmpeg and add it to PATH.".to_string(),
-            ));
-        }
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
+        self.encoders.check_video(&self.video)?;
 
         // Get display info
         let displays = get_displays();
@@ -398,6 +539,7 @@ impl RecordingChannel for DisplayCaptureChannel {
 
         self.output_dir = Some(output_dir.to_path_buf());
         self.session_index = session_index;
+        self.clock = clock.clone();
 
         tracing::info!(
             "Windows display capture initialized for display {} ({}x{})",
@@ -418,6 +560,10 @@ impl RecordingChannel for DisplayCaptureChannel {
             .clone()
             .ok_or_else(|| RecordingError::ConfigurationError("Output directory not set".to_string()))?;
 
+        // Frame times are measured from the first capture
+        let mut pacer = FramePacer::new(self.video.fps, &self.clock, &self.id, self.session_index)
+            .with_health(self.health.clone());
+
         // Capture first frame to determine actual dimensions
         let (first_frame, actual_width, actual_height) = capture_display_frame(self.display_id)
             .ok_or_else(|| RecordingError::CaptureError("Failed to capture initial frame".to_string()))?;
@@ -431,16 +577,43 @@ impl RecordingChannel for DisplayCaptureChannel {
             actual_height
         );
 
-        // Create FFmpeg encoder
+        // Windows are re-resolved every session since they may have moved
+        let crop = resolve_crop(
+            self.target.as_ref(),
+            self.display_id,
+            actual_width,
+            actual_height,
+            |window_id| window_bounds_on_display(window_id, self.display_id),
+        )?;
+        let first_frame = match crop {
+            Some(rect) => {
+                self.width = rect.width;
+                self.height = rect.height;
+                if let Some(ref region) = self.capture_region {
+                    region.set_origin_now(rect.x as f64, rect.y as f64);
+                }
+                crop_frame(&first_frame, actual_width, &rect)
+            }
+            None => first_frame,
+        };
+
+        let spec = VideoSpec {
+            width: self.width,
+            height: self.height,
+            settings: self.video.clone(),
+            output_file: self.video.output_file(&output_dir, &self.basename()),
+        };
         let encoder = Arc::new(
-            FFmpegEncoder::new(self.width, self.height, self.fps, &output_dir, self.session_index)
-                .map_err(|e| RecordingError::CaptureError(format!("Failed to start FFmpeg: {}", e)))?,
+            self.encoders
+                .video_encoder(&spec)
+                .map(|encoder| VideoOutput::new(encoder).with_error_reporter(self.error_reporter.clone()))
+                .map_err(|e| RecordingError::CaptureError(format!("Failed to start encoder: {}", e)))?,
         );
 
         // Write first frame
         let expected_size = (self.width * self.height * 4) as usize;
         if first_frame.len() >= expected_size {
-            encoder.write_frame(&first_frame[..expected_size]);
+            pacer.push(&first_frame[..expected_size], |frame| encoder.write_frame(frame));
         }
 
         self.encoder = Some(encoder.clone());
@@ -449,20 +622,48 @@ impl RecordingChannel for DisplayCaptureChannel {
         // Start capture loop
         let is_recording = self.is_recording.clone();
         let display_id = self.display_id;
-        let fps = self.fps;
+        let fps = self.video.fps;
         let width = self.width;
         let height = self.height;
+        let follow_window = match self.target {
+            Some(CaptureTarget::Window { window_id }) => Some(window_id),
+            _ => None,
+        };
+        let capture_region = self.capture_region.clone();
 
         let handle = tokio::spawn(async move {
             let frame_interval = std::time::Duration::from_millis(1000 / fps as u64);
             let expected_size = (width * height * 4) as usize;
+            let mut current_rect = crop;
 
             while is_recording.load(Ordering::SeqCst) {
                 let start = std::time::Instant::now();
 
-                if let Some((data, _w, _h)) = capture_display_frame(display_id) {
+                // Keep the crop on the window as it moves
+                if let (Some(window_id), Some(rect)) = (follow_window, current_rect) {
+                    if let Some(bounds) = window_bounds_on_display(window_id, display_id) {
+                        let moved = rect.moved_to(
+                            bounds.x as i64,
+                            bounds.y as i64,
+                            actual_width,
+                            actual_height,
+                        );
+                        if moved != rect {
+                            current_rect = Some(moved);
+                            if let Some(ref region) = capture_region {
+                                region.set_origin_now(moved.x as f64, moved.y as f64);
+                            }
+                        }
+                    }
+                }
+
+                if let Some((data, frame_width, _h)) = capture_display_frame(display_id) {
+                    let data = match current_rect {
+                        Some(ref rect) => crop_frame(&data, frame_width, rect),
+                        None => data,
+                    };
                     if data.len() >= expected_size {
-                        encoder.write_frame(&data[..expected_size]);
+                        pacer.push(&data[..expected_size], |frame| encoder.write_frame(frame));
                     }
                 }
 
@@ -482,6 +683,8 @@ impl RecordingChannel for DisplayCaptureChannel {
                     tokio::time::sleep(frame_interval - elapsed).await;
                 }
             }
+
+            pacer.finish(|frame| encoder.write_frame(frame))
         });
 
         self.capture_handle = Some(handle);
@@ -491,7 +694,7 @@ impl RecordingChannel for DisplayCaptureChannel {
             self.display_id,
             self.width,
             self.height,
-            self.fps
+            self.video.fps
         );
         Ok(())
     }
@@ -503,9 +706,10 @@ impl RecordingChannel for DisplayCaptureChannel {
 
         self.is_recording.store(false, Ordering::SeqCst);
 
-        if let Some(handle) = self.capture_handle.take() {
-            let _ = handle.await;
-        }
+        let timeline = match self.capture_handle.take() {
+            Some(handle) => handle.await.ok(),
+            None => None,
+        };
 
         if let Some(ref encoder) = self.encoder {
             let files = encoder
@@ -515,6 +719,17 @@ impl RecordingChannel for DisplayCaptureChannel {
         }
         self.encoder = None;
 
+        if let (Some(timeline), Some(ref output_dir)) = (timeline, &self.output_dir) {
+            tracing::info!(
+                "Frame timing: {} captured, {} dropped, {} encoded",
+                timeline.frames.len(),
+                timeline.dropped_count(),
+                timeline.output_frame_count
+            );
+            let sidecar = timeline.write_sidecar(output_dir, &self.basename())?;
+            self.output_files.lock().push(sidecar);
+        }
+
         tracing::info!("Windows display capture stopped");
         Ok(())
     }
@@ -535,4 +750,24 @@ impl RecordingChannel for DisplayCaptureChannel {
     fn output_files(&self) -> Vec<String> {
         self.output_files.lock().clone()
     }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
+
+    fn video_source(&self) -> Option<VideoSource> {
+        Some(VideoSource {
+            channel_id: self.id.clone(),
+            display_id: Some(self.display_id),
+            file_suffix: self.file_suffix.clone(),
+            capture_size: Some(Resolution {
+                width: self.width,
+                height: self.height,
+            }),
+        })
+    }
 }
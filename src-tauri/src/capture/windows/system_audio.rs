@@ -3,8 +3,13 @@
 //! On Windows, we can capture system audio using WASAPI loopback mode,
 //! which captures the audio being played to an output device.
 
-use crate::capture::audio::AudioEncoder;
-use crate::recorder::channel::{ChannelType, RecordingChannel, RecordingError, RecordingResult};
+use crate::capture::audio::{AudioEncoder, AudioFormat};
+use crate::capture::encoder::{ffmpeg_backend, EncoderBackend};
+use crate::recorder::channel::{
+    ChannelErrorReporter, ChannelType, RecordingChannel, RecordingError, RecordingResult,
+};
+use crate::recorder::clock::RecordingClock;
+use crate::recorder::health::ChannelHealth;
 use async_trait::async_trait;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Device, SampleFormat, StreamConfig};
@@ -27,6 +32,11 @@ pub struct SystemAudioCaptureChannel {
     is_recording: Arc<AtomicBool>,
     output_dir: Option<PathBuf>,
     session_index: usize,
+    clock: RecordingClock,
+    error_reporter: ChannelErrorReporter,
+    health: ChannelHealth,
+    format: AudioFormat,
+    encoders: Arc<dyn EncoderBackend>,
     output_files: Arc<ParkingMutex<Vec<String>>>,
     encoder: Arc<ParkingMutex<Option<Arc<AudioEncoder>>>>,
     stream_handle: Arc<ParkingMutex<Option<std::thread::JoinHandle<()>>>>,
@@ -50,6 +60,11 @@ impl SystemAudioCaptureChannel {
             is_recording: Arc::new(AtomicBool::new(false)),
             output_dir: None,
             session_index: 0,
+            clock: RecordingClock::default(),
+            error_reporter: ChannelErrorReporter::default(),
+            health: ChannelHealth::default(),
+            format: AudioFormat::default(),
+            encoders: ffmpeg_backend(),
             output_files: Arc::new(ParkingMutex::new(Vec::new())),
             encoder: Arc::new(ParkingMutex::new(None)),
             stream_handle: Arc::new(ParkingMutex::new(None)),
@@ -59,6 +74,18 @@ impl SystemAudioCaptureChannel {
         }
     }
 
+    /// Codec to record system audio with (AAC by default)
+    pub fn with_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Encode through another backend (FFmpeg by default)
+    pub fn with_encoders(mut self, encoders: Arc<dyn EncoderBackend>) -> Self {
+        self.encoders = encoders;
+        self
+    }
+
     /// Check if system audio capture is available
     pub fn is_available(&self) -> bool {
         self.available
@@ -81,11 +108,17 @@ impl RecordingChannel for SystemAudioCaptureChannel {
         ChannelType::SystemAudio
     }
 
-    async fn initialize(&mut self, output_dir: &Path, session_index: usize) -> RecordingResult<()> {
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()> {
         if !self.available {
             tracing::warn!("System audio capture not available - no output device found");
             return Ok(());
         }
+        self.encoders.check_audio(&self.format)?;
 
         let device = get_default_output_device().ok_or_else(|| {
             RecordingError::DeviceNotFound("No default output device".to_string())
@@ -102,6 +135,7 @@ impl RecordingChannel for SystemAudioCaptureChannel {
         self.channels = config.channels();
         self.output_dir = Some(output_dir.to_path_buf());
         self.session_index = session_index;
+        self.clock = clock.clone();
 
         tracing::info!(
             "System audio channel initialized: {} ({}Hz, {}ch)",
@@ -129,12 +163,21 @@ impl RecordingChannel for SystemAudioCaptureChannel {
         // Create encoder
         let encoder = Arc::new(
             AudioEncoder::new(
+                self.encoders.as_ref(),
                 self.sample_rate,
                 self.channels,
                 &output_dir,
                 self.session_index,
                 "system",
+                self.format,
             )
+            .map(|encoder| {
+                encoder
+                    .with_clock(self.clock.clone(), &self.id)
+                    .with_error_reporter(self.error_reporter.clone())
+                    .with_health(self.health.clone())
+                    .with_levels(&self.id)
+            })
             .map_err(|e| {
                 RecordingError::CaptureError(format!("Failed to start audio encoder: {}", e))
             })?,
@@ -267,4 +310,12 @@ impl RecordingChannel for SystemAudioCaptureChannel {
     fn output_files(&self) -> Vec<String> {
         self.output_files.lock().clone()
     }
+
+    fn set_error_reporter(&mut self, reporter: ChannelErrorReporter) {
+        self.error_reporter = reporter;
+    }
+
+    fn set_health(&mut self, health: ChannelHealth) {
+        self.health = health;
+    }
 }
@@ -1,32 +1,155 @@
 //! Recording-related Tauri commands
 
-use crate::capture::audio::get_audio_input_devices;
-use crate::capture::traits::{AudioDeviceInfo, DisplayInfo, has_screen_recording_permission, request_screen_recording_permission};
+use crate::capture::audio::{get_audio_input_devices, AudioMonitor};
+use crate::capture::audio_devices::subscribe_audio_device_changes;
+use crate::capture::levels::subscribe_audio_levels;
+use crate::capture::region::CaptureRegion;
+use crate::capture::traits::{AudioDeviceInfo, CameraInfo, CaptureTarget, DisplayInfo, Resolution, WindowInfo, has_screen_recording_permission, request_screen_recording_permission};
+use crate::capture::video::find_video_file;
+use crate::recorder::clock::SyncManifest;
+use crate::recorder::disk_space;
+use crate::recorder::health::RecordingHealth;
+use crate::recorder::recovery::RecoveryReport;
+use crate::recorder::replay::ReplayConfig;
+use crate::recorder::sources::{display_file_suffix, SourcesManifest, VideoSource};
 use crate::recorder::state::{RecordingConfig, RecordingResult as RecordingOutput, RecordingState};
 use crate::recorder::RecordingCoordinator;
+use crate::utils::media_info::{read_video_metadata, VideoMetadata};
 use std::sync::Arc;
-use tauri::State;
-use tokio::sync::Mutex;
+use tauri::async_runtime::JoinHandle;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::sync::{broadcast, Mutex};
+
+/// Tauri event carrying each `RecordingEvent` to the frontend
+pub const RECORDING_EVENT: &str = "recording-event";
+
+/// Tauri event carrying `AudioLevels` of metered sources to the frontend
+pub const AUDIO_LEVELS_EVENT: &str = "audio-levels";
+
+/// Tauri event carrying the new input device list when devices change
+pub const AUDIO_DEVICES_CHANGED_EVENT: &str = "audio-devices-changed";
 
 /// Application state for recording
 pub struct RecorderState {
     pub coordinator: Arc<Mutex<RecordingCoordinator>>,
+    /// Pre-recording level monitor, if one is running
+    pub monitor: Mutex<Option<AudioMonitor>>,
+    /// Task cutting replay buffer segments, while the buffer runs
+    pub replay_rotation: Mutex<Option<JoinHandle<()>>>,
+    /// Task checking free space on the output volume until the recording ends
+    pub disk_space_check: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl Default for RecorderState {
     fn default() -> Self {
         Self {
             coordinator: Arc::new(Mutex::new(RecordingCoordinator::new())),
+            monitor: Mutex::new(None),
+            replay_rotation: Mutex::new(None),
+            disk_space_check: Mutex::new(None),
+        }
+    }
+}
+
+/// Emit everything received on `events` to every window as `name`
+async fn relay_events<T: serde::Serialize + Clone>(
+    app: &AppHandle,
+    name: &str,
+    mut events: broadcast::Receiver<T>,
+) {
+    loop {
+        match events.recv().await {
+            Ok(event) => {
+                if let Err(e) = app.emit(name, &event) {
+                    tracing::warn!("Failed to emit {}: {}", name, e);
+                }
+            }
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                tracing::warn!("Dropped {} {} events", skipped, name);
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
         }
     }
 }
 
+/// Relay coordinator events to every window as `recording-event`
+pub fn forward_recording_events(app: AppHandle) {
+    let coordinator = app.state::<RecorderState>().coordinator.clone();
+    
+    tauri::async_runtime::spawn(async move {
+        let events = coordinator.lock().await.subscribe();
+        relay_events(&app, RECORDING_EVENT, events).await;
+    });
+}
+
+/// Relay audio levels (recording channels and the monitor) as `audio-levels`
+pub fn forward_audio_levels(app: AppHandle) {
+    let levels = subscribe_audio_levels();
+    tauri::async_runtime::spawn(async move {
+        relay_events(&app, AUDIO_LEVELS_EVENT, levels).await;
+    });
+}
+
+/// Relay input device list changes as `audio-devices-changed`
+pub fn forward_audio_device_changes(app: AppHandle) {
+    let changes = subscribe_audio_device_changes();
+    tauri::async_runtime::spawn(async move {
+        relay_events(&app, AUDIO_DEVICES_CHANGED_EVENT, changes).await;
+    });
+}
+
+/// Start metering an input device before recording
+///
+/// Replaces a running monitor. Levels arrive as `audio-levels` events with
+/// source ID "monitor"; nothing is recorded.
+#[tauri::command]
+pub async fn start_audio_monitor(
+    state: State<'_, RecorderState>,
+    device_id: Option<String>,
+) -> Result<(), String> {
+    let mut monitor = state.monitor.lock().await;
+    // Release the device before opening it again
+    if let Some(previous) = monitor.take() {
+        previous.stop();
+    }
+    
+    let started = tokio::task::spawn_blocking(move || AudioMonitor::start(device_id))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    *monitor = Some(started);
+    Ok(())
+}
+
+/// Stop the pre-recording level monitor
+#[tauri::command]
+pub async fn stop_audio_monitor(state: State<'_, RecorderState>) -> Result<(), String> {
+    if let Some(monitor) = state.monitor.lock().await.take() {
+        monitor.stop();
+    }
+    Ok(())
+}
+
 /// Get list of available audio input devices (microphones)
 #[tauri::command]
 pub async fn get_audio_devices() -> Result<Vec<AudioDeviceInfo>, String> {
     Ok(get_audio_input_devices())
 }
 
+/// Get list of available cameras
+#[tauri::command]
+pub async fn get_cameras() -> Result<Vec<CameraInfo>, String> {
+    #[cfg(target_os = "linux")]
+    {
+        Ok(crate::capture::linux::webcam::get_cameras())
+    }
+    
+    #[cfg(not(target_os = "linux"))]
+    {
+        Ok(Vec::new())
+    }
+}
+
 /// Check if system audio capture is available
 #[tauri::command]
 pub async fn check_system_audio_available() -> Result<bool, String> {
@@ -41,7 +164,12 @@ pub async fn check_system_audio_available() -> Result<bool, String> {
         Ok(true)
     }
     
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(target_os = "linux")]
+    {
+        Ok(crate::capture::linux::system_audio::is_system_audio_available())
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     {
         Ok(false)
     }
@@ -60,12 +188,81 @@ pub async fn get_displays() -> Result<Vec<DisplayInfo>, String> {
         Ok(crate::capture::windows::screen::get_displays())
     }
     
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    #[cfg(target_os = "linux")]
+    {
+        Ok(crate::capture::linux::screen::get_displays())
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
     {
         Ok(vec![])
     }
 }
 
+/// Get list of windows that can be recorded
+#[tauri::command]
+pub async fn get_windows() -> Result<Vec<WindowInfo>, String> {
+    #[cfg(target_os = "macos")]
+    {
+        Ok(crate::capture::macos::screen::get_windows())
+    }
+    
+    #[cfg(target_os = "windows")]
+    {
+        Ok(crate::capture::windows::screen::get_windows())
+    }
+    
+    #[cfg(target_os = "linux")]
+    {
+        Ok(crate::capture::linux::screen::get_windows())
+    }
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    {
+        Ok(Vec::new())
+    }
+}
+
+/// Get current information (title, bounds) about a single window
+#[tauri::command]
+pub async fn get_window_info(window_id: u32) -> Result<WindowInfo, String> {
+    #[cfg(target_os = "macos")]
+    let info = crate::capture::macos::screen::get_window_info(window_id);
+    
+    #[cfg(target_os = "windows")]
+    let info = crate::capture::windows::screen::get_window_info(window_id);
+    
+    #[cfg(target_os = "linux")]
+    let info = crate::capture::linux::screen::get_window_info(window_id);
+    
+    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+    let info: Option<WindowInfo> = None;
+    
+    info.ok_or_else(|| format!("Window {} not found", window_id))
+}
+
+/// Display that a capture target is on
+fn target_display_id(target: &CaptureTarget) -> Result<u32, String> {
+    match *target {
+        CaptureTarget::Display { display_id } | CaptureTarget::Region { display_id, .. } => Ok(display_id),
+        CaptureTarget::Window { window_id } => {
+            #[cfg(target_os = "macos")]
+            let display_id = crate::capture::macos::screen::display_for_window(window_id);
+            
+            #[cfg(target_os = "windows")]
+            let display_id = crate::capture::windows::screen::display_for_window(window_id);
+            
+            #[cfg(target_os = "linux")]
+            let display_id = crate::capture::linux::screen::display_for_window(window_id);
+            
+            #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+            let display_id: Option<u32> = None;
+            
+            display_id.ok_or_else(|| format!("Window {} not found", window_id))
+        }
+    }
+}
+
 /// Check if screen recording permission is granted
 #[tauri::command]
 pub async fn check_screen_permission() -> Result<bool, String> {
@@ -84,42 +281,219 @@ pub async fn start_recording(
     state: State<'_, RecorderState>,
     config: RecordingConfig,
 ) -> Result<(), String> {
+    // The microphone channel meters while recording; free the device for it
+    if let Some(monitor) = state.monitor.lock().await.take() {
+        monitor.stop();
+    }
+    
+    {
+        let mut coordinator = state.coordinator.lock().await;
+        add_channels(&mut coordinator, &config)?;
+        coordinator.start(config).await.map_err(|e| e.to_string())?;
+    }
+    
+    // Stop before the output volume fills; the frontend hears `autoStopped`
+    let coordinator = state.coordinator.clone();
+    let check = tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(disk_space::CHECK_INTERVAL);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let mut coordinator = coordinator.lock().await;
+            if coordinator.state() == RecordingState::Idle {
+                break;
+            }
+            match coordinator.check_disk_space().await {
+                Ok(Some(_)) => break,
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Failed to stop recording on low disk space: {}", e),
+            }
+        }
+    });
+    if let Some(previous) = state.disk_space_check.lock().await.replace(check) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+/// Start recording the last few seconds into the replay buffer
+///
+/// `config.output_dir` is ignored; footage goes to the buffer directory until
+/// `save_replay` writes it out as a bundle.
+#[tauri::command]
+pub async fn start_replay_buffer(
+    state: State<'_, RecorderState>,
+    config: RecordingConfig,
+    replay: Option<ReplayConfig>,
+) -> Result<(), String> {
+    if let Some(monitor) = state.monitor.lock().await.take() {
+        monitor.stop();
+    }
+    
+    let replay = replay.unwrap_or_default();
+    let segment = replay.segment_duration();
+    {
+        let mut coordinator = state.coordinator.lock().await;
+        add_channels(&mut coordinator, &config)?;
+        coordinator.start_replay(config, replay).await.map_err(|e| e.to_string())?;
+    }
+    
+    // Cut a new segment every `segment_secs` until the buffer stops
+    let coordinator = state.coordinator.clone();
+    let rotation = tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(segment);
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            let mut coordinator = coordinator.lock().await;
+            if !coordinator.is_replay() {
+                break;
+            }
+            if let Err(e) = coordinator.rotate_replay().await {
+                tracing::warn!("Failed to rotate replay buffer: {}", e);
+            }
+        }
+    });
+    if let Some(previous) = state.replay_rotation.lock().await.replace(rotation) {
+        previous.abort();
+    }
+    Ok(())
+}
+
+/// Save the replay buffer as a recording bundle; the buffer keeps recording
+#[tauri::command]
+pub async fn save_replay(
+    state: State<'_, RecorderState>,
+    output_dir: String,
+) -> Result<RecordingOutput, String> {
+    let mut coordinator = state.coordinator.lock().await;
+    coordinator
+        .save_replay(std::path::Path::new(&output_dir))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Stop the replay buffer and discard its footage
+#[tauri::command]
+pub async fn stop_replay_buffer(state: State<'_, RecorderState>) -> Result<(), String> {
+    if let Some(rotation) = state.replay_rotation.lock().await.take() {
+        rotation.abort();
+    }
+    let mut coordinator = state.coordinator.lock().await;
+    coordinator.stop_replay().await.map_err(|e| e.to_string())
+}
+
+/// Replace the coordinator's channels with the ones `config` asks for
+fn add_channels(coordinator: &mut RecordingCoordinator, config: &RecordingConfig) -> Result<(), String> {
+    // Synthetic test sources need no devices or permissions
+    if let Some(ref sources) = config.test_sources {
+        coordinator.clear_channels();
+        for channel in crate::capture::synthetic::test_channels(config, sources) {
+            coordinator.add_channel(channel);
+        }
+        return Ok(());
+    }
+    
     // Check permission first
     if !has_screen_recording_permission() {
         request_screen_recording_permission();
         return Err("Screen recording permission not granted. Please allow in System Preferences and try again.".to_string());
     }
     
-    let mut coordinator = state.coordinator.lock().await;
-    
     // Clear existing channels and add display capture
     coordinator.clear_channels();
     
+    // Window and region targets crop the display; input coordinates follow the crop
+    let target = config.capture_target();
+    let display_id = target_display_id(&target)?;
+    let capture_region = match target {
+        CaptureTarget::Display { .. } => None,
+        _ => Some(Arc::new(CaptureRegion::new())),
+    };
+    
     #[cfg(target_os = "macos")]
     {
-        let display_channel = Box::new(crate::capture::macos::screen::DisplayCaptureChannel::new(config.display_id));
-        coordinator.add_channel(display_channel);
+        let mut display_channel = crate::capture::macos::screen::DisplayCaptureChannel::new(display_id)
+            .with_video_settings(config.video.clone());
+        if let Some(ref region) = capture_region {
+            display_channel = display_channel.with_target(target.clone(), region.clone());
+        }
+        coordinator.add_channel(Box::new(display_channel));
     }
     
     #[cfg(target_os = "windows")]
     {
-        let display_channel = Box::new(crate::capture::windows::screen::DisplayCaptureChannel::new(config.display_id));
-        coordinator.add_channel(display_channel);
+        let mut display_channel = crate::capture::windows::screen::DisplayCaptureChannel::new(display_id)
+            .with_video_settings(config.video.clone());
+        if let Some(ref region) = capture_region {
+            display_channel = display_channel.with_target(target.clone(), region.clone());
+        }
+        coordinator.add_channel(Box::new(display_channel));
+    }
+    
+    #[cfg(target_os = "linux")]
+    {
+        let mut display_channel = crate::capture::linux::screen::DisplayCaptureChannel::new(display_id)
+            .with_video_settings(config.video.clone());
+        if let Some(ref region) = capture_region {
+            display_channel = display_channel.with_target(target.clone(), region.clone());
+        }
+        coordinator.add_channel(Box::new(display_channel));
     }
     
     // Add input tracking channel (always-on for MVP)
     // Note: Windows implementation is currently stubbed.
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     {
-        let input_channel = Box::new(crate::capture::InputTrackingChannel::new(config.display_id));
-        coordinator.add_channel(input_channel);
+        let mut input_channel = crate::capture::InputTrackingChannel::new(display_id)
+            .with_keystroke_mode(config.keystroke_mode);
+        if let Some(ref region) = capture_region {
+            input_channel = input_channel.with_capture_region(region.clone());
+        }
+        coordinator.add_channel(Box::new(input_channel));
+    }
+    
+    // Additional displays are recorded whole, each with input in its own coordinates
+    for extra_display_id in config.additional_displays(display_id) {
+        let suffix = display_file_suffix(extra_display_id);
+        
+        #[cfg(target_os = "macos")]
+        coordinator.add_channel(Box::new(
+            crate::capture::macos::screen::DisplayCaptureChannel::new(extra_display_id)
+                .with_video_settings(config.video.clone())
+                .with_file_suffix(suffix.clone()),
+        ));
+        
+        #[cfg(target_os = "windows")]
+        coordinator.add_channel(Box::new(
+            crate::capture::windows::screen::DisplayCaptureChannel::new(extra_display_id)
+                .with_video_settings(config.video.clone())
+                .with_file_suffix(suffix.clone()),
+        ));
+        
+        #[cfg(target_os = "linux")]
+        coordinator.add_channel(Box::new(
+            crate::capture::linux::screen::DisplayCaptureChannel::new(extra_display_id)
+                .with_video_settings(config.video.clone())
+                .with_file_suffix(suffix.clone()),
+        ));
+        
+        // Keystrokes are only recorded once, with the main display
+        #[cfg(any(target_os = "macos", target_os = "linux"))]
+        coordinator.add_channel(Box::new(
+            crate::capture::InputTrackingChannel::new(extra_display_id)
+                .with_keystroke_mode(crate::capture::input::KeystrokeMode::Off)
+                .with_file_suffix(suffix),
+        ));
     }
 
     // Add microphone channel if enabled
     if config.capture_microphone {
-        let mic_channel = Box::new(crate::capture::audio::MicrophoneCaptureChannel::new(
-            config.microphone_device_id.clone(),
-        ));
+        let mic_channel = Box::new(
+            crate::capture::audio::MicrophoneCaptureChannel::new(config.microphone_device_id.clone())
+                .with_fallback(config.microphone_fallback)
+                .with_format(config.microphone_format),
+        );
         coordinator.add_channel(mic_channel);
     }
     
@@ -127,21 +501,45 @@ pub async fn start_recording(
     if config.capture_system_audio {
         #[cfg(target_os = "macos")]
         {
-            let system_audio_channel = Box::new(crate::capture::macos::system_audio::SystemAudioCaptureChannel::new(config.display_id));
+            let system_audio_channel = Box::new(crate::capture::macos::system_audio::SystemAudioCaptureChannel::new(display_id)
+                .with_format(config.system_audio_format));
             coordinator.add_channel(system_audio_channel);
         }
         
         #[cfg(target_os = "windows")]
         {
-            let system_audio_channel = Box::new(crate::capture::windows::system_audio::SystemAudioCaptureChannel::new());
+            let system_audio_channel = Box::new(crate::capture::windows::system_audio::SystemAudioCaptureChannel::new()
+                .with_format(config.system_audio_format));
             coordinator.add_channel(system_audio_channel);
         }
+        
+        #[cfg(target_os = "linux")]
+        {
+            let system_audio_channel = Box::new(crate::capture::linux::system_audio::SystemAudioCaptureChannel::new(
+                config.system_audio_device_id.clone(),
+            ).with_format(config.system_audio_format));
+            coordinator.add_channel(system_audio_channel);
+        }
+    }
+    
+    // Add webcam channel if enabled
+    // Note: only implemented on Linux so far.
+    if config.capture_webcam {
+        #[cfg(target_os = "linux")]
+        {
+            let webcam_channel = Box::new(crate::capture::linux::webcam::WebcamCaptureChannel::new(
+                config.webcam_device_id.clone(),
+            ).with_video_settings(config.video.clone()));
+            coordinator.add_channel(webcam_channel);
+        }
     }
     
-    coordinator.start(config).await.map_err(|e| e.to_string())
+    Ok(())
 }
 
 /// Stop recording
+///
+/// Fails while the replay buffer runs; it has `save_replay` and `stop_replay_buffer`.
 #[tauri::command]
 pub async fn stop_recording(
     state: State<'_, RecorderState>,
@@ -186,102 +584,29 @@ pub async fn get_recording_duration(
     Ok(coordinator.duration_ms())
 }
 
-/// Video metadata returned from FFprobe
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub struct VideoMetadata {
-    pub width: u32,
-    pub height: u32,
-    pub fps: f64,
-    pub duration_ms: f64,
-    pub codec: String,
+/// Get capture and encoder statistics of every channel
+///
+/// While recording these are live; afterwards they describe the last recording.
+#[tauri::command]
+pub async fn get_recording_health(
+    state: State<'_, RecorderState>,
+) -> Result<RecordingHealth, String> {
+    let coordinator = state.coordinator.lock().await;
+    Ok(coordinator.health())
 }
 
-/// Get video metadata using FFprobe
+/// Get video metadata by reading the MP4/MOV/MKV/WebM container
+///
+/// Works on recordings still being written or cut short by a crash, which
+/// report the frames up to their last complete fragment.
 #[tauri::command]
 pub async fn get_video_metadata(path: String) -> Result<VideoMetadata, String> {
-    use std::process::Command;
-    
-    // Run ffprobe to get video stream info in JSON format
-    let output = Command::new("ffprobe")
-        .args([
-            "-v", "quiet",
-            "-print_format", "json",
-            "-show_streams",
-            "-show_format",
-            "-select_streams", "v:0",
-            &path,
-        ])
-        .output()
-        .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
-    
-    if !output.status.success() {
-        return Err(format!(
-            "ffprobe failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
-    
-    let json_str = String::from_utf8_lossy(&output.stdout);
-    let json: serde_json::Value = serde_json::from_str(&json_str)
-        .map_err(|e| format!("Failed to parse ffprobe output: {}", e))?;
-    
-    // Extract video stream info
-    let streams = json.get("streams")
-        .and_then(|s| s.as_array())
-        .ok_or("No streams found in video")?;
-    
-    let video_stream = streams.first()
-        .ok_or("No video stream found")?;
-    
-    let width = video_stream.get("width")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as u32;
-    
-    let height = video_stream.get("height")
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as u32;
-    
-    let codec = video_stream.get("codec_name")
-        .and_then(|v| v.as_str())
-        .unwrap_or("unknown")
-        .to_string();
-    
-    // Parse frame rate (can be "30/1" or "29.97" format)
-    let fps = video_stream.get("r_frame_rate")
-        .and_then(|v| v.as_str())
-        .map(|s| {
-            if s.contains('/') {
-                let parts: Vec<&str> = s.split('/').collect();
-                if parts.len() == 2 {
-                    let num: f64 = parts[0].parse().unwrap_or(0.0);
-                    let den: f64 = parts[1].parse().unwrap_or(1.0);
-                    if den > 0.0 { num / den } else { 0.0 }
-                } else {
-                    0.0
-                }
-            } else {
-                s.parse().unwrap_or(0.0)
-            }
-        })
-        .unwrap_or(0.0);
-    
-    // Get duration from format section (more reliable)
-    let duration_secs = json.get("format")
-        .and_then(|f| f.get("duration"))
-        .and_then(|d| d.as_str())
-        .and_then(|s| s.parse::<f64>().ok())
-        .unwrap_or(0.0);
-    
-    let duration_ms = duration_secs * 1000.0;
-    
-    Ok(VideoMetadata {
-        width,
-        height,
-        fps,
-        duration_ms,
-        codec,
+    tokio::task::spawn_blocking(move || {
+        read_video_metadata(std::path::Path::new(&path))
+            .map_err(|e| format!("Failed to read video metadata of {}: {}", path, e))
     })
+    .await
+    .map_err(|e| e.to_string())?
 }
 
 /// Mouse move event from recording
@@ -310,6 +635,49 @@ pub struct MouseClickEvent {
     pub unix_time_ms: u64,
 }
 
+/// Scroll event from recording
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MouseScrollEvent {
+    pub x: f64,
+    pub y: f64,
+    pub delta_x: f64,
+    pub delta_y: f64,
+    pub delta_mode: String,
+    pub phase: Option<String>,
+    pub active_modifiers: Vec<String>,
+    pub process_time_ms: f64,
+    pub unix_time_ms: u64,
+}
+
+/// Drag event from recording
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MouseDragEvent {
+    pub x: f64,
+    pub y: f64,
+    pub start_x: f64,
+    pub start_y: f64,
+    pub button: String,
+    pub phase: String,
+    pub active_modifiers: Vec<String>,
+    pub process_time_ms: f64,
+    pub unix_time_ms: u64,
+}
+
+/// Keystroke event from recording
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KeystrokeEvent {
+    pub key_code: u32,
+    pub key: String,
+    pub character: Option<String>,
+    pub event_type: String,
+    pub active_modifiers: Vec<String>,
+    pub process_time_ms: f64,
+    pub unix_time_ms: u64,
+}
+
 /// Cursor image info from recording
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -322,6 +690,23 @@ pub struct CursorInfo {
     pub height: u32,
 }
 
+/// One recorded video of a bundle and the input files in its coordinates
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleVideoSource {
+    pub channel_id: String,
+    pub display_id: Option<u32>,
+    /// The main video, also in `RecordingBundle::video_path`
+    pub primary: bool,
+    pub video_path: String,
+    pub video_metadata: VideoMetadata,
+    /// Size of the captured frames the input files are relative to, when it
+    /// differs from the (downscaled) video
+    pub capture_size: Option<Resolution>,
+    pub mouse_moves_path: Option<String>,
+    pub mouse_clicks_path: Option<String>,
+}
+
 /// Complete recording bundle data
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -330,10 +715,20 @@ pub struct RecordingBundle {
     pub video_path: String,
     pub mic_audio_path: Option<String>,
     pub system_audio_path: Option<String>,
+    pub webcam_path: Option<String>,
     pub mouse_moves: Vec<MouseMoveEvent>,
     pub mouse_clicks: Vec<MouseClickEvent>,
+    pub mouse_scrolls: Vec<MouseScrollEvent>,
+    pub mouse_drags: Vec<MouseDragEvent>,
+    pub keystrokes: Vec<KeystrokeEvent>,
     pub cursors: std::collections::HashMap<String, CursorInfo>,
     pub video_metadata: VideoMetadata,
+    /// Per-channel start offsets, absent in bundles recorded before sync.json
+    pub sync: Option<SyncManifest>,
+    /// Final capture statistics, absent in bundles recorded before health.json
+    pub health: Option<RecordingHealth>,
+    /// Every recorded display, the main video first
+    pub video_sources: Vec<BundleVideoSource>,
 }
 
 /// Load a recording bundle from disk
@@ -352,18 +747,16 @@ pub async fn load_recording_bundle(bundle_path: String) -> Result<RecordingBundl
         bundle_dir.to_path_buf()
     };
     
-    // Find video file
-    let video_path = recording_dir.join("recording-0.mp4");
-    if !video_path.exists() {
-        return Err(format!("Video file not found: {:?}", video_path));
-    }
+    // Find video file (MKV when recorded with a lossless codec)
+    let video_path = find_video_file(&recording_dir, "recording-0")
+        .ok_or_else(|| format!("Video file not found in {:?}", recording_dir))?;
     
     // Get video metadata
     let video_metadata = get_video_metadata(video_path.to_string_lossy().to_string()).await?;
     
     // Load mouse moves
     let mouse_moves_path = recording_dir.join("recording-0-mouse-moves.json");
-    let mouse_moves: Vec<MouseMoveEvent> = if mouse_moves_path.exists() {
+    let mut mouse_moves: Vec<MouseMoveEvent> = if mouse_moves_path.exists() {
         let content = fs::read_to_string(&mouse_moves_path)
             .map_err(|e| format!("Failed to read mouse moves: {}", e))?;
         serde_json::from_str(&content)
@@ -374,7 +767,7 @@ pub async fn load_recording_bundle(bundle_path: String) -> Result<RecordingBundl
     
     // Load mouse clicks
     let mouse_clicks_path = recording_dir.join("recording-0-mouse-clicks.json");
-    let mouse_clicks: Vec<MouseClickEvent> = if mouse_clicks_path.exists() {
+    let mut mouse_clicks: Vec<MouseClickEvent> = if mouse_clicks_path.exists() {
         let content = fs::read_to_string(&mouse_clicks_path)
             .map_err(|e| format!("Failed to read mouse clicks: {}", e))?;
         serde_json::from_str(&content)
@@ -383,6 +776,38 @@ pub async fn load_recording_bundle(bundle_path: String) -> Result<RecordingBundl
         Vec::new()
     };
     
+    // Load scrolls and drags (absent in older recordings)
+    let mouse_scrolls_path = recording_dir.join("recording-0-mouse-scrolls.json");
+    let mut mouse_scrolls: Vec<MouseScrollEvent> = if mouse_scrolls_path.exists() {
+        let content = fs::read_to_string(&mouse_scrolls_path)
+            .map_err(|e| format!("Failed to read mouse scrolls: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse mouse scrolls: {}", e))?
+    } else {
+        Vec::new()
+    };
+    
+    let mouse_drags_path = recording_dir.join("recording-0-mouse-drags.json");
+    let mut mouse_drags: Vec<MouseDragEvent> = if mouse_drags_path.exists() {
+        let content = fs::read_to_string(&mouse_drags_path)
+            .map_err(|e| format!("Failed to read mouse drags: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse mouse drags: {}", e))?
+    } else {
+        Vec::new()
+    };
+    
+    // Load keystrokes (absent when keyboard capture was off)
+    let keystrokes_path = recording_dir.join("recording-0-keystrokes.json");
+    let keystrokes: Vec<KeystrokeEvent> = if keystrokes_path.exists() {
+        let content = fs::read_to_string(&keystrokes_path)
+            .map_err(|e| format!("Failed to read keystrokes: {}", e))?;
+        serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse keystrokes: {}", e))?
+    } else {
+        Vec::new()
+    };
+    
     // Load cursor info
     let cursors_path = recording_dir.join("recording-0-cursors.json");
     let cursors: HashMap<String, CursorInfo> = if cursors_path.exists() {
@@ -394,33 +819,143 @@ pub async fn load_recording_bundle(bundle_path: String) -> Result<RecordingBundl
         HashMap::new()
     };
     
-    // Find audio files
-    let mic_audio_path = recording_dir.join("recording-0-mic.m4a");
-    let system_audio_path = recording_dir.join("recording-0-system.m4a");
+    // Load channel alignment
+    let sync_path = recording_dir.join("sync.json");
+    let sync: Option<SyncManifest> = if sync_path.exists() {
+        let content = fs::read_to_string(&sync_path)
+            .map_err(|e| format!("Failed to read sync manifest: {}", e))?;
+        Some(serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse sync manifest: {}", e))?)
+    } else {
+        None
+    };
+    
+    // Load the final health report
+    let health_path = recording_dir.join("health.json");
+    let health: Option<RecordingHealth> = if health_path.exists() {
+        let content = fs::read_to_string(&health_path)
+            .map_err(|e| format!("Failed to read health report: {}", e))?;
+        Some(serde_json::from_str(&content)
+            .map_err(|e| format!("Failed to parse health report: {}", e))?)
+    } else {
+        None
+    };
+    
+    // Find audio files (their extension depends on the recorded format)
+    let mic_audio_path = crate::capture::audio::find_audio_track(&recording_dir, 0, "mic");
+    let system_audio_path = crate::capture::audio::find_audio_track(&recording_dir, 0, "system");
+    
+    // Find webcam video
+    let webcam_path = recording_dir.join("recording-0-webcam.mp4");
+    
+    // Describe every recorded display (only the main video before sources.json)
+    let sources = SourcesManifest::read(&recording_dir)
+        .map_err(|e| format!("Failed to read sources manifest: {}", e))?
+        .map(|manifest| manifest.sources)
+        .unwrap_or_else(|| vec![VideoSource {
+            channel_id: "display".to_string(),
+            display_id: None,
+            file_suffix: None,
+            capture_size: None,
+        }]);
+    let mut video_sources = Vec::new();
+    for source in sources {
+        let basename = source.basename(0);
+        let Some(source_video_path) = find_video_file(&recording_dir, &basename) else {
+            continue;
+        };
+        let source_metadata = if source.is_primary() {
+            video_metadata.clone()
+        } else {
+            get_video_metadata(source_video_path.to_string_lossy().to_string()).await?
+        };
+        let existing = |suffix: &str| {
+            let path = recording_dir.join(format!("{}-{}.json", basename, suffix));
+            path.exists().then(|| path.to_string_lossy().to_string())
+        };
+        let primary = source.is_primary();
+        let capture_size = source.capture_size.filter(|size| {
+            (size.width, size.height) != (source_metadata.width, source_metadata.height)
+        });
+        video_sources.push(BundleVideoSource {
+            primary,
+            channel_id: source.channel_id,
+            display_id: source.display_id,
+            video_path: source_video_path.to_string_lossy().to_string(),
+            video_metadata: source_metadata,
+            capture_size,
+            mouse_moves_path: existing("mouse-moves"),
+            mouse_clicks_path: existing("mouse-clicks"),
+        });
+    }
+    video_sources.sort_by_key(|source| !source.primary);
+    
+    // Input is recorded in capture pixels; bring it onto a downscaled main video
+    let primary_capture_size = video_sources
+        .iter()
+        .find(|source| source.primary)
+        .and_then(|source| source.capture_size.clone());
+    if let Some(size) = primary_capture_size {
+        let scale_x = video_metadata.width as f64 / size.width.max(1) as f64;
+        let scale_y = video_metadata.height as f64 / size.height.max(1) as f64;
+        for event in &mut mouse_moves {
+            (event.x, event.y) = (event.x * scale_x, event.y * scale_y);
+        }
+        for event in &mut mouse_clicks {
+            (event.x, event.y) = (event.x * scale_x, event.y * scale_y);
+        }
+        for event in &mut mouse_scrolls {
+            (event.x, event.y) = (event.x * scale_x, event.y * scale_y);
+        }
+        for event in &mut mouse_drags {
+            (event.x, event.y) = (event.x * scale_x, event.y * scale_y);
+            (event.start_x, event.start_y) = (event.start_x * scale_x, event.start_y * scale_y);
+        }
+    }
     
     tracing::info!(
-        "Loaded recording bundle: {} mouse moves, {} clicks, {} cursors",
+        "Loaded recording bundle: {} mouse moves, {} clicks, {} scrolls, {} drags, {} keystrokes, {} cursors",
         mouse_moves.len(),
         mouse_clicks.len(),
+        mouse_scrolls.len(),
+        mouse_drags.len(),
+        keystrokes.len(),
         cursors.len()
     );
     
     Ok(RecordingBundle {
         bundle_path: bundle_path.clone(),
         video_path: video_path.to_string_lossy().to_string(),
-        mic_audio_path: if mic_audio_path.exists() {
-            Some(mic_audio_path.to_string_lossy().to_string())
-        } else {
-            None
-        },
-        system_audio_path: if system_audio_path.exists() {
-            Some(system_audio_path.to_string_lossy().to_string())
+        mic_audio_path: mic_audio_path.map(|p| p.to_string_lossy().to_string()),
+        system_audio_path: system_audio_path.map(|p| p.to_string_lossy().to_string()),
+        webcam_path: if webcam_path.exists() {
+            Some(webcam_path.to_string_lossy().to_string())
         } else {
             None
         },
         mouse_moves,
         mouse_clicks,
+        mouse_scrolls,
+        mouse_drags,
+        keystrokes,
         cursors,
         video_metadata,
+        sync,
+        health,
+        video_sources,
+    })
+}
+
+/// Rebuild a recording bundle left half-written by a crash
+///
+/// Converts leftover input journals and remuxes fragmented media so the bundle
+/// can be opened with `load_recording_bundle`.
+#[tauri::command]
+pub async fn recover_recording(bundle_path: String) -> Result<RecoveryReport, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::recorder::recovery::recover_recording(std::path::Path::new(&bundle_path))
     })
+    .await
+    .map_err(|e| format!("Recovery task failed: {}", e))?
+    .map_err(|e| format!("Failed to recover recording: {}", e))
 }
@@ -2,6 +2,7 @@
 //!
 //! These commands provide system information like displays, audio devices, etc.
 
+use crate::capture::media_tools::{self, MediaToolPaths, MediaToolchain};
 use serde::{Deserialize, Serialize};
 
 /// Display information
@@ -34,6 +35,24 @@ pub async fn get_system_info() -> Result<SystemInfo, String> {
     })
 }
 
+/// Find FFmpeg and FFprobe and probe what FFmpeg can encode
+///
+/// Probes again on every call, so the UI can check after the user installs
+/// FFmpeg or points the app at another build.
+#[tauri::command]
+pub async fn get_media_toolchain() -> Result<MediaToolchain, String> {
+    tokio::task::spawn_blocking(|| (*media_tools::refresh_toolchain()).clone())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Use FFmpeg/FFprobe from the given paths (None for the bundled or `PATH` one)
+#[tauri::command]
+pub async fn set_media_tool_paths(paths: MediaToolPaths) -> Result<MediaToolchain, String> {
+    media_tools::configure(paths);
+    get_media_toolchain().await
+}
+
 fn get_os_version() -> String {
     #[cfg(target_os = "macos")]
     {
@@ -43,20 +43,32 @@ pub fn run() {
             commands::project::get_project,
             // System commands
             commands::system::get_system_info,
+            commands::system::get_media_toolchain,
+            commands::system::set_media_tool_paths,
             // Recording commands
             commands::recording::get_displays,
+            commands::recording::get_windows,
+            commands::recording::get_window_info,
             commands::recording::get_audio_devices,
+            commands::recording::get_cameras,
             commands::recording::check_system_audio_available,
             commands::recording::check_screen_permission,
             commands::recording::request_screen_permission,
             commands::recording::start_recording,
+            commands::recording::start_replay_buffer,
+            commands::recording::save_replay,
+            commands::recording::stop_replay_buffer,
             commands::recording::stop_recording,
             commands::recording::pause_recording,
             commands::recording::resume_recording,
             commands::recording::get_recording_state,
             commands::recording::get_recording_duration,
+            commands::recording::get_recording_health,
+            commands::recording::start_audio_monitor,
+            commands::recording::stop_audio_monitor,
             commands::recording::get_video_metadata,
             commands::recording::load_recording_bundle,
+            commands::recording::recover_recording,
             // Processing commands
             commands::processing::smooth_cursor,
             commands::processing::process_cursor_smoothing,
@@ -70,6 +82,11 @@ pub fn run() {
             commands::window::restore_toolbar,
         ])
         .setup(|app| {
+            // Push recording progress and errors to the windows instead of polling
+            commands::recording::forward_recording_events(app.handle().clone());
+            commands::recording::forward_audio_levels(app.handle().clone());
+            commands::recording::forward_audio_device_changes(app.handle().clone());
+            
             // Set up transparent background for toolbar window on macOS
             #[cfg(target_os = "macos")]
             {
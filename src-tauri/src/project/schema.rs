@@ -303,6 +303,21 @@ pub struct Slice {
     pub disable_cursor_smoothing: bool,
 }
 
+impl Slice {
+    /// A slice playing a whole session at normal speed
+    pub fn full_length(duration_ms: f64) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            source_start_ms: 0.0,
+            source_end_ms: duration_ms,
+            time_scale: 1.0,
+            volume: 1.0,
+            hide_cursor: false,
+            disable_cursor_smoothing: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum ZoomType {
@@ -367,6 +382,21 @@ pub struct Scene {
     pub layouts: Vec<Layout>,
 }
 
+impl Scene {
+    /// A scene showing one recorded session in full
+    pub fn recording(session_index: usize, duration_ms: f64) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            name: format!("Session {}", session_index + 1),
+            scene_type: SceneType::Recording,
+            session_index,
+            slices: vec![Slice::full_length(duration_ms)],
+            zoom_ranges: Vec::new(),
+            layouts: Vec::new(),
+        }
+    }
+}
+
 // =============================================================================
 // Project
 // =============================================================================
@@ -2,9 +2,15 @@
 //!
 //! Defines the interface for different recording channels (display, audio, webcam, input).
 
+use super::clock::RecordingClock;
+use super::coordinator::RecordingEvent;
+use super::health::ChannelHealth;
+use super::sources::VideoSource;
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 use thiserror::Error;
+use tokio::sync::broadcast;
 
 /// Errors that can occur during recording
 #[derive(Error, Debug)]
@@ -35,11 +41,92 @@ pub enum RecordingError {
 
     #[error("Configuration error: {0}")]
     ConfigurationError(String),
+
+    #[error("Not enough disk space: {0}")]
+    InsufficientDiskSpace(String),
+
+    #[error("Channel '{channel_id}' failed to {operation}: {source}")]
+    ChannelFailed {
+        channel_id: String,
+        operation: ChannelOperation,
+        source: Box<RecordingError>,
+    },
+}
+
+impl RecordingError {
+    /// Attribute this error to a channel operation
+    pub fn in_channel(self, channel_id: &str, operation: ChannelOperation) -> Self {
+        RecordingError::ChannelFailed {
+            channel_id: channel_id.to_string(),
+            operation,
+            source: Box::new(self),
+        }
+    }
+
+    /// The channel that caused this error, if it came from one
+    pub fn channel_id(&self) -> Option<&str> {
+        match self {
+            RecordingError::ChannelFailed { channel_id, .. } => Some(channel_id),
+            _ => None,
+        }
+    }
 }
 
 /// Result type for recording operations
 pub type RecordingResult<T> = Result<T, RecordingError>;
 
+/// Channel lifecycle step, for error reporting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelOperation {
+    Initialize,
+    Start,
+    Stop,
+    Pause,
+    Resume,
+}
+
+impl std::fmt::Display for ChannelOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChannelOperation::Initialize => write!(f, "initialize"),
+            ChannelOperation::Start => write!(f, "start"),
+            ChannelOperation::Stop => write!(f, "stop"),
+            ChannelOperation::Pause => write!(f, "pause"),
+            ChannelOperation::Resume => write!(f, "resume"),
+        }
+    }
+}
+
+/// Reports failures a channel notices while capturing (an encoder exiting,
+/// a device disappearing) as `RecordingEvent::ChannelError`
+#[derive(Debug, Clone, Default)]
+pub struct ChannelErrorReporter {
+    channel_id: String,
+    event_tx: Option<broadcast::Sender<RecordingEvent>>,
+}
+
+impl ChannelErrorReporter {
+    pub fn new(channel_id: &str, event_tx: broadcast::Sender<RecordingEvent>) -> Self {
+        Self {
+            channel_id: channel_id.to_string(),
+            event_tx: Some(event_tx),
+        }
+    }
+
+    /// Log the failure and broadcast it to subscribers
+    pub fn report(&self, message: impl Into<String>) {
+        let message = message.into();
+        tracing::error!("Channel {} error: {}", self.channel_id, message);
+        if let Some(ref event_tx) = self.event_tx {
+            let _ = event_tx.send(RecordingEvent::ChannelError {
+                channel_id: self.channel_id.clone(),
+                message,
+            });
+        }
+    }
+}
+
 /// Frame data from a capture source
 #[derive(Debug)]
 pub struct CapturedFrame {
@@ -71,8 +158,16 @@ pub trait RecordingChannel: Send + Sync {
     /// Get the channel type
     fn channel_type(&self) -> ChannelType;
     
-    /// Initialize the channel with the output directory
-    async fn initialize(&mut self, output_dir: &Path, session_index: usize) -> RecordingResult<()>;
+    /// Initialize the channel with the output directory and the recording's clock
+    ///
+    /// Channels measure their timestamps on `clock` and mark the time of their
+    /// first sample or frame in each session with `mark_first_sample`.
+    async fn initialize(
+        &mut self,
+        output_dir: &Path,
+        session_index: usize,
+        clock: &RecordingClock,
+    ) -> RecordingResult<()>;
     
     /// Start recording
     async fn start(&mut self) -> RecordingResult<()>;
@@ -91,6 +186,25 @@ pub trait RecordingChannel: Send + Sync {
     
     /// Get output files created by this channel
     fn output_files(&self) -> Vec<String>;
+    
+    /// Receive the reporter for failures that happen in the background
+    ///
+    /// Called when the channel is added to a coordinator. Channels whose
+    /// capture can fail after `start` returned keep it; the default ignores it.
+    fn set_error_reporter(&mut self, _reporter: ChannelErrorReporter) {}
+    
+    /// Receive the counters to record capture and encoder statistics on
+    ///
+    /// Called when the channel is added to a coordinator; the counters are
+    /// reset for every recording. The default ignores them.
+    fn set_health(&mut self, _health: ChannelHealth) {}
+    
+    /// Video this channel records, listed in `sources.json`
+    ///
+    /// Display channels return their source; the default is None.
+    fn video_source(&self) -> Option<VideoSource> {
+        None
+    }
 }
 
 /// Types of recording channels
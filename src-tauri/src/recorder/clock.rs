@@ -0,0 +1,222 @@
+//! Shared recording clock
+//!
+//! The coordinator creates one `RecordingClock` per recording and hands it to
+//! every channel. All process times (frame stamps, input events, session
+//! boundaries) are measured on it, and each channel marks the clock time of
+//! the first sample or frame it produced in a session. Those marks are written
+//! to `sync.json` so export and the editor can line up the channel files.
+
+use parking_lot::Mutex as ParkingMutex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Clock time of a channel's first sample or frame in a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FirstSample {
+    pub channel_id: String,
+    pub session_index: usize,
+    pub clock_ms: f64,
+}
+
+#[derive(Debug)]
+struct ClockInner {
+    start: Instant,
+    start_unix_ms: u64,
+    first_samples: ParkingMutex<Vec<FirstSample>>,
+}
+
+/// Monotonic time base shared by the coordinator and all channels
+#[derive(Debug, Clone)]
+pub struct RecordingClock {
+    inner: Arc<ClockInner>,
+}
+
+impl RecordingClock {
+    /// Start a clock at zero now
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(ClockInner {
+                start: Instant::now(),
+                start_unix_ms: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64,
+                first_samples: ParkingMutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Milliseconds since the clock started
+    pub fn now_ms(&self) -> f64 {
+        self.inner.start.elapsed().as_secs_f64() * 1000.0
+    }
+
+    /// The instant the clock started, for code that measures `elapsed()` itself
+    pub fn start_instant(&self) -> Instant {
+        self.inner.start
+    }
+
+    /// Unix time at clock zero
+    pub fn start_unix_ms(&self) -> u64 {
+        self.inner.start_unix_ms
+    }
+
+    /// Convert a wall-clock timestamp (e.g. reported by FFmpeg) to clock time
+    pub fn ms_from_unix(&self, unix_ms: f64) -> f64 {
+        unix_ms - self.inner.start_unix_ms as f64
+    }
+
+    /// Record when a channel produced its first sample or frame of a session
+    ///
+    /// Only the first mark per channel and session is kept.
+    pub fn mark_first_sample(&self, channel_id: &str, session_index: usize, clock_ms: f64) {
+        let mut first_samples = self.inner.first_samples.lock();
+        let seen = first_samples
+            .iter()
+            .any(|s| s.channel_id == channel_id && s.session_index == session_index);
+        if !seen {
+            first_samples.push(FirstSample {
+                channel_id: channel_id.to_string(),
+                session_index,
+                clock_ms,
+            });
+        }
+    }
+
+    /// First sample time of a channel in a session, if it produced any
+    pub fn first_sample(&self, channel_id: &str, session_index: usize) -> Option<f64> {
+        self.inner
+            .first_samples
+            .lock()
+            .iter()
+            .find(|s| s.channel_id == channel_id && s.session_index == session_index)
+            .map(|s| s.clock_ms)
+    }
+}
+
+impl Default for RecordingClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Alignment of one channel within a session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelSync {
+    pub channel_id: String,
+    pub channel_type: String,
+    /// Clock time of the channel's first sample/frame, None if it produced nothing
+    pub first_sample_ms: Option<f64>,
+    /// Delay from the session start to the first sample (where its file starts)
+    pub offset_ms: Option<f64>,
+}
+
+/// Clock times of one session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSync {
+    pub index: usize,
+    pub start_ms: f64,
+    pub end_ms: f64,
+    pub channels: Vec<ChannelSync>,
+}
+
+/// Contents of `sync.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncManifest {
+    /// Unix time at clock zero
+    pub clock_start_unix_ms: u64,
+    pub sessions: Vec<SessionSync>,
+}
+
+impl SyncManifest {
+    /// Collect the first sample marks for each session
+    ///
+    /// `sessions` are (index, start_ms, end_ms) and `channels` are (id, type).
+    pub fn build(
+        clock: &RecordingClock,
+        sessions: &[(usize, f64, f64)],
+        channels: &[(String, String)],
+    ) -> Self {
+        let sessions = sessions
+            .iter()
+            .map(|&(index, start_ms, end_ms)| SessionSync {
+                index,
+                start_ms,
+                end_ms,
+                channels: channels
+                    .iter()
+                    .map(|(channel_id, channel_type)| {
+                        let first_sample_ms = clock.first_sample(channel_id, index);
+                        ChannelSync {
+                            channel_id: channel_id.clone(),
+                            channel_type: channel_type.clone(),
+                            first_sample_ms,
+                            offset_ms: first_sample_ms.map(|ms| ms - start_ms),
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Self {
+            clock_start_unix_ms: clock.start_unix_ms(),
+            sessions,
+        }
+    }
+
+    /// Write the manifest as `sync.json` in the recording directory
+    pub fn write(&self, recording_dir: &Path) -> std::io::Result<String> {
+        let path = recording_dir.join("sync.json");
+        let data = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(&path, data)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_first_mark_is_kept() {
+        let clock = RecordingClock::new();
+        clock.mark_first_sample("microphone", 0, 12.0);
+        clock.mark_first_sample("microphone", 0, 40.0);
+        clock.mark_first_sample("microphone", 1, 2040.0);
+
+        assert_eq!(clock.first_sample("microphone", 0), Some(12.0));
+        assert_eq!(clock.first_sample("microphone", 1), Some(2040.0));
+        assert_eq!(clock.first_sample("display-0", 0), None);
+    }
+
+    #[test]
+    fn test_manifest_offsets_are_session_relative() {
+        let clock = RecordingClock::new();
+        clock.mark_first_sample("display-0", 0, 5.0);
+        clock.mark_first_sample("microphone", 0, 25.0);
+        clock.mark_first_sample("microphone", 1, 1530.0);
+
+        let manifest = SyncManifest::build(
+            &clock,
+            &[(0, 0.0, 1000.0), (1, 1500.0, 2500.0)],
+            &[
+                ("display-0".to_string(), "display".to_string()),
+                ("microphone".to_string(), "microphone".to_string()),
+            ],
+        );
+
+        let first = &manifest.sessions[0].channels;
+        assert_eq!(first[0].offset_ms, Some(5.0));
+        assert_eq!(first[1].offset_ms, Some(25.0));
+
+        let second = &manifest.sessions[1].channels;
+        assert_eq!(second[0].first_sample_ms, None);
+        assert_eq!(second[1].offset_ms, Some(30.0));
+    }
+}
@@ -2,16 +2,35 @@
 //!
 //! Orchestrates multiple recording channels and manages the recording lifecycle.
 
-use super::channel::{RecordingChannel, RecordingError, RecordingResult};
-use super::state::{RecordingConfig, RecordingResult as RecordingOutput, RecordingSession, RecordingState};
+use super::channel::{
+    ChannelErrorReporter, ChannelOperation, RecordingChannel, RecordingError, RecordingResult,
+};
+use super::clock::{RecordingClock, SyncManifest};
+use super::disk_space::{self, DiskSpaceCheck, DiskSpaceGuard};
+use super::health::{ChannelHealth, RecordingHealth};
+use super::replay::{self, ReplayConfig};
+use super::sessions::{project_name, ChannelFiles, SessionsManifest, StopReason};
+use super::sources::SourcesManifest;
+use super::state::{ChannelFailure, RecordingConfig, RecordingResult as RecordingOutput, RecordingSession, RecordingState};
+use crate::project::bundle::write_project;
 use parking_lot::RwLock;
-use std::path::PathBuf;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::Duration;
+use tempfile::TempDir;
 use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// How often `Progress` is broadcast while recording
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// `Health` is broadcast every this many `Progress` events
+const HEALTH_EVERY_PROGRESS: u32 = 4;
 
 /// Events emitted during recording
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase", rename_all_fields = "camelCase")]
 pub enum RecordingEvent {
     /// Recording started
     Started,
@@ -22,9 +41,38 @@ pub enum RecordingEvent {
     /// Recording resumed
     Resumed,
     /// Error occurred
-    Error(String),
-    /// Recording progress update (duration in ms)
-    Progress(f64),
+    Error { message: String },
+    /// A channel failed, in a lifecycle operation or while capturing
+    ChannelError { channel_id: String, message: String },
+    /// Recording progress update (recorded duration, excluding pauses)
+    Progress { duration_ms: f64 },
+    /// Capture and encoder statistics of every channel
+    Health(RecordingHealth),
+    /// The replay buffer was saved as a bundle
+    ReplaySaved { bundle_path: String },
+    /// Free space on the output volume is running low
+    DiskSpaceLow { free_bytes: u64, remaining_secs: f64 },
+    /// The recording was stopped and finalized without a stop request
+    AutoStopped { reason: StopReason, bundle_path: String },
+}
+
+/// Recorded time so far, shared with the progress ticker
+#[derive(Debug, Clone, Copy, Default)]
+struct ProgressBase {
+    /// Duration of the sessions that have ended
+    completed_ms: f64,
+    /// Clock time the current session started, None while paused
+    session_start_ms: Option<f64>,
+}
+
+impl ProgressBase {
+    fn recorded_ms(&self, clock: &RecordingClock) -> f64 {
+        let current = self
+            .session_start_ms
+            .map(|start| clock.now_ms() - start)
+            .unwrap_or(0.0);
+        self.completed_ms + current
+    }
 }
 
 /// Manages multiple recording channels
@@ -35,6 +83,9 @@ pub struct RecordingCoordinator {
     /// Recording channels
     channels: Vec<Box<dyn RecordingChannel>>,
     
+    /// Health counters of each channel, in channel order
+    health: Vec<ChannelHealth>,
+    
     /// Recording sessions (one per pause/resume cycle)
     sessions: Vec<RecordingSession>,
     
@@ -44,11 +95,29 @@ pub struct RecordingCoordinator {
     /// Output directory for the current recording
     output_dir: Option<PathBuf>,
     
-    /// Time when recording started (for process time calculation)
-    start_time: Option<Instant>,
+    /// Clock shared with the channels (for process time calculation)
+    clock: Option<RecordingClock>,
     
     /// Event broadcaster
     event_tx: broadcast::Sender<RecordingEvent>,
+    
+    /// Recorded time for `Progress` events
+    progress: Arc<RwLock<ProgressBase>>,
+    
+    /// Task broadcasting `Progress` while a recording is active
+    progress_task: Option<JoinHandle<()>>,
+    
+    /// Replay buffer settings while recording in replay mode
+    replay: Option<ReplayConfig>,
+    
+    /// Scratch bundle the replay buffer records into, deleted when it stops
+    replay_buffer: Option<TempDir>,
+    
+    /// Free space tracking of the current recording
+    disk_space: Option<DiskSpaceGuard>,
+    
+    /// Reads free space on the output volume
+    free_space: fn(&Path) -> std::io::Result<u64>,
 }
 
 impl RecordingCoordinator {
@@ -58,17 +127,28 @@ impl RecordingCoordinator {
         Self {
             state: Arc::new(RwLock::new(RecordingState::Idle)),
             channels: Vec::new(),
+            health: Vec::new(),
             sessions: Vec::new(),
             current_session: 0,
             output_dir: None,
-            start_time: None,
+            clock: None,
             event_tx,
+            progress: Arc::new(RwLock::new(ProgressBase::default())),
+            progress_task: None,
+            replay: None,
+            replay_buffer: None,
+            disk_space: None,
+            free_space: disk_space::available_space,
         }
     }
     
     /// Add a recording channel
-    pub fn add_channel(&mut self, channel: Box<dyn RecordingChannel>) {
+    pub fn add_channel(&mut self, mut channel: Box<dyn RecordingChannel>) {
         tracing::info!("Adding channel: {}", channel.id());
+        channel.set_error_reporter(ChannelErrorReporter::new(channel.id(), self.event_tx.clone()));
+        let health = ChannelHealth::new(channel.id(), &channel.channel_type().to_string());
+        channel.set_health(health.clone());
+        self.health.push(health);
         self.channels.push(channel);
     }
     
@@ -84,17 +164,153 @@ impl RecordingCoordinator {
     
     /// Get the current process time in milliseconds
     fn process_time_ms(&self) -> f64 {
-        self.start_time
-            .map(|t| t.elapsed().as_secs_f64() * 1000.0)
+        self.clock
+            .as_ref()
+            .map(RecordingClock::now_ms)
             .unwrap_or(0.0)
     }
     
+    /// Broadcast `Progress` and `Health` until the recording ends
+    fn start_progress_task(&mut self, clock: RecordingClock) {
+        let state = self.state.clone();
+        let progress = self.progress.clone();
+        let health = self.health.clone();
+        let event_tx = self.event_tx.clone();
+        
+        self.progress_task = Some(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PROGRESS_INTERVAL);
+            let mut ticks = 0u32;
+            loop {
+                interval.tick().await;
+                match *state.read() {
+                    RecordingState::Recording => {}
+                    RecordingState::Paused => continue,
+                    _ => break,
+                }
+                
+                let duration_ms = progress.read().recorded_ms(&clock);
+                let _ = event_tx.send(RecordingEvent::Progress { duration_ms });
+                
+                ticks += 1;
+                if ticks.is_multiple_of(HEALTH_EVERY_PROGRESS) {
+                    let report = RecordingHealth::collect(&health, duration_ms);
+                    let _ = event_tx.send(RecordingEvent::Health(report));
+                }
+            }
+        }));
+    }
+    
+    /// Sync manifest of the given sessions
+    fn sync_manifest(&self, clock: &RecordingClock, sessions: &[RecordingSession]) -> SyncManifest {
+        let sessions: Vec<_> = sessions
+            .iter()
+            .map(|s| (s.index, s.process_time_start_ms, s.process_time_end_ms))
+            .collect();
+        let channels: Vec<_> = self.channels
+            .iter()
+            .map(|c| (c.id().to_string(), c.channel_type().to_string()))
+            .collect();
+        SyncManifest::build(clock, &sessions, &channels)
+    }
+    
+    /// Videos recorded by the channels, for `sources.json`
+    fn sources_manifest(&self) -> SourcesManifest {
+        SourcesManifest {
+            sources: self.channels.iter().filter_map(|c| c.video_source()).collect(),
+        }
+    }
+    
+    /// Every channel's output files
+    fn channel_files(&self) -> Vec<ChannelFiles> {
+        self.channels
+            .iter()
+            .map(|c| ChannelFiles {
+                channel_id: c.id().to_string(),
+                channel_type: c.channel_type().to_string(),
+                files: c.output_files(),
+            })
+            .collect()
+    }
+    
+    /// Write `sessions.json` and the editor project, making the bundle ready to open
+    fn write_project_files(&self, bundle_dir: &Path, sessions: &SessionsManifest) -> Vec<String> {
+        let mut files = Vec::new();
+        match sessions.write(&bundle_dir.join("recording")) {
+            Ok(path) => files.push(path),
+            Err(e) => tracing::warn!("Failed to write session manifest: {}", e),
+        }
+        
+        let project = sessions.to_project(&project_name(bundle_dir));
+        match write_project(&project, bundle_dir) {
+            Ok(()) => files.extend(
+                ["project.json", "meta.json", "markers.json"]
+                    .map(|name| bundle_dir.join(name).to_string_lossy().to_string()),
+            ),
+            Err(e) => {
+                tracing::warn!("Failed to write project: {}", e);
+                let _ = self.event_tx.send(RecordingEvent::Error {
+                    message: format!("Failed to write project: {}", e),
+                });
+            }
+        }
+        files
+    }
+    
+    /// Log and broadcast a channel failure that doesn't abort the operation
+    fn report_failure(
+        event_tx: &broadcast::Sender<RecordingEvent>,
+        channel_id: &str,
+        operation: ChannelOperation,
+        error: RecordingError,
+    ) -> (ChannelFailure, RecordingError) {
+        let error = error.in_channel(channel_id, operation);
+        tracing::error!("{}", error);
+        let _ = event_tx.send(RecordingEvent::ChannelError {
+            channel_id: channel_id.to_string(),
+            message: error.to_string(),
+        });
+        (ChannelFailure::new(channel_id, operation, &error), error)
+    }
+    
+    /// Stop the channels a failed start already got running and remove their files
+    ///
+    /// A directory that existed before is kept along with anything the
+    /// channels don't list in `output_files`.
+    async fn roll_back_start(&mut self, failed: usize, recording_dir: &Path, created_dir: bool) {
+        for channel in self.channels[..=failed].iter_mut().rev() {
+            if channel.is_recording() {
+                if let Err(e) = channel.stop().await {
+                    tracing::warn!("Failed to stop channel {} during rollback: {}", channel.id(), e);
+                }
+            }
+            for file in channel.output_files() {
+                if let Err(e) = std::fs::remove_file(&file) {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        tracing::warn!("Failed to remove {}: {}", file, e);
+                    }
+                }
+            }
+        }
+        
+        // Only remove the directory if this start created it
+        if created_dir {
+            if let Err(e) = std::fs::remove_dir_all(recording_dir) {
+                tracing::warn!("Failed to remove {:?}: {}", recording_dir, e);
+            }
+        }
+    }
+    
     /// Start recording
+    ///
+    /// All channels are initialized before any starts capturing. If a channel
+    /// fails to start, the ones already running are stopped again and the
+    /// coordinator stays idle; the error names the failing channel.
     pub async fn start(&mut self, config: RecordingConfig) -> RecordingResult<()> {
         let current_state = *self.state.read();
         if current_state != RecordingState::Idle {
             return Err(RecordingError::AlreadyRecording);
         }
+        config.validate()?;
         
         tracing::info!("Starting recording to: {}", config.output_dir);
         
@@ -104,32 +320,105 @@ impl RecordingCoordinator {
         
         // Create recording subdirectory
         let recording_dir = output_dir.join("recording");
+        let created_dir = !recording_dir.exists();
         std::fs::create_dir_all(&recording_dir)?;
         
+        let clock = RecordingClock::new();
+        for health in &self.health {
+            health.reset();
+        }
+        
+        // Initialize every channel before any starts capturing
+        for channel in &mut self.channels {
+            if let Err(e) = channel.initialize(&recording_dir, 0, &clock).await {
+                let error = e.in_channel(channel.id(), ChannelOperation::Initialize);
+                tracing::error!("{}", error);
+                if created_dir {
+                    let _ = std::fs::remove_dir_all(&recording_dir);
+                }
+                return Err(error);
+            }
+        }
+        
+        // Refuse to start when the recording would be stopped right away
+        let mut guard = self.disk_space_guard(&config);
+        let mut low_space = None;
+        match (self.free_space)(&output_dir) {
+            Ok(free_bytes) => match guard.check(free_bytes, 0.0) {
+                DiskSpaceCheck::Exhausted { free_bytes } => {
+                    let error = RecordingError::InsufficientDiskSpace(format!(
+                        "{} MB free on the output volume, {} MB must stay free",
+                        free_bytes / (1024 * 1024),
+                        config.disk_space.reserve_mb
+                    ));
+                    tracing::error!("{}", error);
+                    if created_dir {
+                        let _ = std::fs::remove_dir_all(&recording_dir);
+                    }
+                    return Err(error);
+                }
+                DiskSpaceCheck::Low { free_bytes, remaining_secs } => {
+                    guard.warning_due(0.0);
+                    low_space = Some(RecordingEvent::DiskSpaceLow { free_bytes, remaining_secs });
+                }
+                DiskSpaceCheck::Enough => {}
+            },
+            Err(e) => tracing::warn!("Failed to read free space of {:?}: {}", output_dir, e),
+        }
+        
+        // Start all channels, rolling back if any fails
+        for index in 0..self.channels.len() {
+            if let Err(e) = self.channels[index].start().await {
+                let error = e.in_channel(self.channels[index].id(), ChannelOperation::Start);
+                tracing::error!("{}", error);
+                self.roll_back_start(index, &recording_dir, created_dir).await;
+                return Err(error);
+            }
+        }
+        
         self.output_dir = Some(output_dir);
-        self.start_time = Some(Instant::now());
+        self.clock = Some(clock.clone());
         self.current_session = 0;
         self.sessions.clear();
         
-        // Create first session
+        // The first session starts with the clock
         let session = RecordingSession::new(0, 0.0);
         self.sessions.push(session);
         
-        // Initialize and start all channels
-        for channel in &mut self.channels {
-            channel.initialize(&recording_dir, 0).await?;
-            channel.start().await?;
-        }
-        
+        *self.progress.write() = ProgressBase {
+            completed_ms: 0.0,
+            session_start_ms: Some(0.0),
+        };
         *self.state.write() = RecordingState::Recording;
+        self.disk_space = Some(guard);
+        self.start_progress_task(clock);
         let _ = self.event_tx.send(RecordingEvent::Started);
+        if let Some(warning) = low_space {
+            let _ = self.event_tx.send(warning);
+        }
         
         tracing::info!("Recording started");
         Ok(())
     }
     
     /// Stop recording
+    ///
+    /// Every channel is stopped even if one fails; failures are listed in the
+    /// result instead of discarding the recording.
+    ///
+    /// The replay buffer isn't a recording to keep: it is saved with
+    /// `save_replay` and stopped with `stop_replay`, which deletes it.
     pub async fn stop(&mut self) -> RecordingResult<RecordingOutput> {
+        if self.replay.is_some() {
+            return Err(RecordingError::ConfigurationError(
+                "The replay buffer is running; save it or stop the replay buffer instead".to_string(),
+            ));
+        }
+        self.finish(StopReason::Requested).await
+    }
+    
+    /// Stop recording and write the bundle, noting why it ended in `sessions.json`
+    async fn finish(&mut self, reason: StopReason) -> RecordingResult<RecordingOutput> {
         let current_state = *self.state.read();
         if current_state == RecordingState::Idle {
             return Err(RecordingError::NotRecording);
@@ -137,15 +426,34 @@ impl RecordingCoordinator {
         
         tracing::info!("Stopping recording");
         
-        // End current session
-        let end_time = self.process_time_ms();
-        if let Some(session) = self.sessions.last_mut() {
-            session.end(end_time);
+        // The final duration is sent once all sessions have ended
+        if let Some(task) = self.progress_task.take() {
+            task.abort();
         }
         
-        // Stop all channels
-        for channel in &mut self.channels {
-            channel.stop().await?;
+        let mut failed_channels = Vec::new();
+        
+        // When paused, the session has ended and the channels are already stopped
+        if current_state == RecordingState::Recording {
+            let end_time = self.process_time_ms();
+            if let Some(session) = self.sessions.last_mut() {
+                session.end(end_time);
+            }
+            
+            for channel in &mut self.channels {
+                match channel.stop().await {
+                    Ok(()) | Err(RecordingError::NotRecording) => {}
+                    Err(e) => {
+                        let (failure, _) = Self::report_failure(
+                            &self.event_tx,
+                            channel.id(),
+                            ChannelOperation::Stop,
+                            e,
+                        );
+                        failed_channels.push(failure);
+                    }
+                }
+            }
         }
         
         // Collect output files
@@ -154,9 +462,51 @@ impl RecordingCoordinator {
             output_files.extend(channel.output_files());
         }
         
+        // Record where each channel's files start on the shared clock
+        if let (Some(clock), Some(output_dir)) = (&self.clock, &self.output_dir) {
+            let manifest = self.sync_manifest(clock, &self.sessions);
+            match manifest.write(&output_dir.join("recording")) {
+                Ok(path) => output_files.push(path),
+                Err(e) => {
+                    tracing::warn!("Failed to write sync manifest: {}", e);
+                    let _ = self.event_tx.send(RecordingEvent::Error {
+                        message: format!("Failed to write sync manifest: {}", e),
+                    });
+                }
+            }
+        }
+        
+        // List the videos so the editor can tell the displays apart
+        if let Some(output_dir) = &self.output_dir {
+            match self.sources_manifest().write(&output_dir.join("recording")) {
+                Ok(path) => output_files.push(path),
+                Err(e) => tracing::warn!("Failed to write sources manifest: {}", e),
+            }
+        }
+        
+        // Sessions and a project, so the bundle opens in the editor as is
+        if let Some(output_dir) = &self.output_dir {
+            let mut sessions = SessionsManifest::build(
+                &self.sessions,
+                &self.channel_files(),
+                &output_dir.join("recording"),
+            );
+            sessions.stop_reason = reason;
+            output_files.extend(self.write_project_files(output_dir, &sessions));
+        }
+        
         // Calculate total duration
         let total_duration_ms: f64 = self.sessions.iter().map(|s| s.duration_ms).sum();
         
+        // Keep the final statistics with the recording
+        let health = RecordingHealth::collect(&self.health, total_duration_ms);
+        if let Some(output_dir) = &self.output_dir {
+            match health.write(&output_dir.join("recording")) {
+                Ok(path) => output_files.push(path),
+                Err(e) => tracing::warn!("Failed to write health report: {}", e),
+            }
+        }
+        
         let result = RecordingOutput {
             bundle_path: self.output_dir
                 .as_ref()
@@ -165,21 +515,103 @@ impl RecordingCoordinator {
             total_duration_ms,
             session_count: self.sessions.len(),
             output_files,
+            failed_channels,
         };
         
+        let _ = self.event_tx.send(RecordingEvent::Progress { duration_ms: total_duration_ms });
+        let _ = self.event_tx.send(RecordingEvent::Health(health));
+        
         *self.state.write() = RecordingState::Complete;
         let _ = self.event_tx.send(RecordingEvent::Stopped);
         
         // Reset state
         self.output_dir = None;
-        self.start_time = None;
+        self.clock = None;
+        self.replay = None;
+        self.disk_space = None;
         *self.state.write() = RecordingState::Idle;
         
         tracing::info!("Recording stopped. Duration: {}ms", total_duration_ms);
         Ok(result)
     }
     
+    /// Disk space guard with each channel's estimated bitrate
+    ///
+    /// Display channels only know their captured size once initialized.
+    fn disk_space_guard(&self, config: &RecordingConfig) -> DiskSpaceGuard {
+        let estimated_kbps = self.channels
+            .iter()
+            .map(|c| {
+                let kbps = disk_space::estimate_bitrate_kbps(
+                    c.channel_type(),
+                    c.video_source().as_ref(),
+                    config,
+                );
+                tracing::debug!("Estimated bitrate of {}: {} kbps", c.id(), kbps);
+                kbps
+            })
+            .sum();
+        DiskSpaceGuard::new(config.disk_space.clone(), estimated_kbps)
+    }
+    
+    /// Check free space on the output volume
+    ///
+    /// Call every `disk_space::CHECK_INTERVAL` while recording. Broadcasts
+    /// `DiskSpaceLow` while little recording time is left; before the
+    /// reserve would be reached the recording is stopped and finalized like
+    /// `stop`, `AutoStopped` is broadcast and the result returned. Does
+    /// nothing while paused or in replay mode, whose buffer doesn't grow.
+    pub async fn check_disk_space(&mut self) -> RecordingResult<Option<RecordingOutput>> {
+        if *self.state.read() != RecordingState::Recording || self.replay.is_some() {
+            return Ok(None);
+        }
+        let (Some(output_dir), Some(clock), Some(guard)) =
+            (&self.output_dir, &self.clock, self.disk_space.as_mut())
+        else {
+            return Ok(None);
+        };
+        
+        let free_bytes = match (self.free_space)(output_dir) {
+            Ok(free_bytes) => free_bytes,
+            Err(e) => {
+                tracing::warn!("Failed to read free space of {:?}: {}", output_dir, e);
+                return Ok(None);
+            }
+        };
+        let recorded_ms = self.progress.read().recorded_ms(clock);
+        
+        match guard.check(free_bytes, recorded_ms) {
+            DiskSpaceCheck::Enough => Ok(None),
+            DiskSpaceCheck::Low { free_bytes, remaining_secs } => {
+                if guard.warning_due(recorded_ms) {
+                    tracing::warn!(
+                        "Low disk space: {} MB free, about {:.0}s of recording left",
+                        free_bytes / (1024 * 1024),
+                        remaining_secs
+                    );
+                    let _ = self.event_tx.send(RecordingEvent::DiskSpaceLow { free_bytes, remaining_secs });
+                }
+                Ok(None)
+            }
+            DiskSpaceCheck::Exhausted { free_bytes } => {
+                tracing::warn!(
+                    "Stopping recording: {} MB free on the output volume",
+                    free_bytes / (1024 * 1024)
+                );
+                let result = self.finish(StopReason::LowDiskSpace).await?;
+                let _ = self.event_tx.send(RecordingEvent::AutoStopped {
+                    reason: StopReason::LowDiskSpace,
+                    bundle_path: result.bundle_path.clone(),
+                });
+                Ok(Some(result))
+            }
+        }
+    }
+    
     /// Pause recording
+    ///
+    /// The pause completes even if a channel fails to pause; the first failure
+    /// is returned afterwards.
     pub async fn pause(&mut self) -> RecordingResult<()> {
         let current_state = *self.state.read();
         if current_state != RecordingState::Recording {
@@ -192,20 +624,34 @@ impl RecordingCoordinator {
         let end_time = self.process_time_ms();
         if let Some(session) = self.sessions.last_mut() {
             session.end(end_time);
+            let mut progress = self.progress.write();
+            progress.completed_ms += session.duration_ms;
+            progress.session_start_ms = None;
         }
         
         // Pause all channels
+        let mut first_error = None;
         for channel in &mut self.channels {
-            channel.pause().await?;
+            match channel.pause().await {
+                Ok(()) | Err(RecordingError::NotRecording) => {}
+                Err(e) => {
+                    let (_, error) =
+                        Self::report_failure(&self.event_tx, channel.id(), ChannelOperation::Pause, e);
+                    first_error.get_or_insert(error);
+                }
+            }
         }
         
         *self.state.write() = RecordingState::Paused;
         let _ = self.event_tx.send(RecordingEvent::Paused);
         
-        Ok(())
+        first_error.map_or(Ok(()), Err)
     }
     
     /// Resume recording
+    ///
+    /// Channels that fail to resume sit out the new session while the others
+    /// keep recording; the first failure is returned afterwards.
     pub async fn resume(&mut self) -> RecordingResult<()> {
         let current_state = *self.state.read();
         if current_state != RecordingState::Paused {
@@ -217,16 +663,233 @@ impl RecordingCoordinator {
         // Create new session
         self.current_session += 1;
         let session = RecordingSession::new(self.current_session, self.process_time_ms());
+        self.progress.write().session_start_ms = Some(session.process_time_start_ms);
         self.sessions.push(session);
         
         // Resume all channels
+        let mut first_error = None;
         for channel in &mut self.channels {
-            channel.resume(self.current_session).await?;
+            if let Err(e) = channel.resume(self.current_session).await {
+                let (_, error) =
+                    Self::report_failure(&self.event_tx, channel.id(), ChannelOperation::Resume, e);
+                first_error.get_or_insert(error);
+            }
         }
         
         *self.state.write() = RecordingState::Recording;
         let _ = self.event_tx.send(RecordingEvent::Resumed);
         
+        first_error.map_or(Ok(()), Err)
+    }
+    
+    /// End the current session and start the next one on every channel
+    ///
+    /// Like pause followed by resume, without the state change. Channels
+    /// that fail sit out the new session.
+    async fn next_session(&mut self) -> Vec<ChannelFailure> {
+        let mut failed_channels = Vec::new();
+        
+        let end_time = self.process_time_ms();
+        if let Some(session) = self.sessions.last_mut() {
+            session.end(end_time);
+        }
+        for channel in &mut self.channels {
+            match channel.pause().await {
+                Ok(()) | Err(RecordingError::NotRecording) => {}
+                Err(e) => {
+                    let (failure, _) =
+                        Self::report_failure(&self.event_tx, channel.id(), ChannelOperation::Pause, e);
+                    failed_channels.push(failure);
+                }
+            }
+        }
+        
+        self.current_session += 1;
+        let session = RecordingSession::new(self.current_session, self.process_time_ms());
+        self.sessions.push(session);
+        for channel in &mut self.channels {
+            if let Err(e) = channel.resume(self.current_session).await {
+                let (failure, _) =
+                    Self::report_failure(&self.event_tx, channel.id(), ChannelOperation::Resume, e);
+                failed_channels.push(failure);
+            }
+        }
+        
+        failed_channels
+    }
+    
+    /// Start recording into the replay buffer
+    ///
+    /// Records like `start`, but into a new scratch bundle under the
+    /// configured buffer directory. Call `rotate_replay` every `segment_secs` to cut segments and
+    /// drop the ones older than the window.
+    pub async fn start_replay(&mut self, mut config: RecordingConfig, replay: ReplayConfig) -> RecordingResult<()> {
+        if *self.state.read() != RecordingState::Idle {
+            return Err(RecordingError::AlreadyRecording);
+        }
+        replay.validate()?;
+        
+        // Dropped, and deleted, if the recording doesn't start
+        let buffer = replay.create_buffer()?;
+        config.output_dir = buffer.path().to_string_lossy().to_string();
+        
+        self.start(config).await?;
+        self.replay = Some(replay);
+        self.replay_buffer = Some(buffer);
+        Ok(())
+    }
+    
+    /// Whether the coordinator is recording into the replay buffer
+    pub fn is_replay(&self) -> bool {
+        self.replay.is_some()
+    }
+    
+    /// Scratch bundle of the running replay buffer
+    pub fn replay_buffer_dir(&self) -> Option<&Path> {
+        self.replay_buffer.as_ref().map(|buffer| buffer.path())
+    }
+    
+    /// Start a new replay segment and delete the ones older than the window
+    ///
+    /// Does nothing while paused.
+    pub async fn rotate_replay(&mut self) -> RecordingResult<()> {
+        let Some(replay) = self.replay.clone() else {
+            return Err(RecordingError::NotRecording);
+        };
+        if *self.state.read() != RecordingState::Recording {
+            return Ok(());
+        }
+        
+        self.next_session().await;
+        
+        let expired = replay::expired_sessions(&self.sessions, self.process_time_ms(), replay.window_ms());
+        if let Some(output_dir) = &self.output_dir {
+            let recording_dir = output_dir.join("recording");
+            for index in &expired {
+                if let Err(e) = replay::remove_session(&recording_dir, *index) {
+                    tracing::warn!("Failed to remove replay segment {}: {}", index, e);
+                }
+            }
+        }
+        self.sessions.retain(|s| !expired.contains(&s.index));
+        
+        // Progress reports how much footage the buffer holds
+        self.reset_progress();
+        Ok(())
+    }
+    
+    /// Recompute `Progress` after sessions were added or dropped mid-recording
+    fn reset_progress(&mut self) {
+        let completed_ms = self.sessions
+            .iter()
+            .take(self.sessions.len().saturating_sub(1))
+            .map(|s| s.duration_ms)
+            .sum();
+        *self.progress.write() = ProgressBase {
+            completed_ms,
+            session_start_ms: self.sessions.last().map(|s| s.process_time_start_ms),
+        };
+    }
+    
+    /// Write the buffered window out as a recording bundle in `output_dir`
+    ///
+    /// The current segment is cut first so every saved session is complete,
+    /// and the buffer carries on recording into the next one. Sessions are
+    /// numbered from 0 in the new bundle. `health.json` is left out since the
+    /// counters cover the whole time the buffer ran.
+    pub async fn save_replay(&mut self, output_dir: &Path) -> RecordingResult<RecordingOutput> {
+        let Some(replay) = self.replay.clone() else {
+            return Err(RecordingError::NotRecording);
+        };
+        let (Some(clock), Some(buffer_dir)) = (self.clock.clone(), self.output_dir.clone()) else {
+            return Err(RecordingError::NotRecording);
+        };
+        
+        tracing::info!("Saving replay to: {:?}", output_dir);
+        
+        // When paused every session has already ended
+        let recording = *self.state.read() == RecordingState::Recording;
+        let failed_channels = if recording {
+            let failed_channels = self.next_session().await;
+            self.reset_progress();
+            failed_channels
+        } else {
+            Vec::new()
+        };
+        let ended = &self.sessions[..self.sessions.len() - usize::from(recording)];
+        let expired = replay::expired_sessions(ended, self.process_time_ms(), replay.window_ms());
+        let kept: Vec<RecordingSession> = ended
+            .iter()
+            .filter(|s| !expired.contains(&s.index))
+            .cloned()
+            .collect();
+        
+        let buffer_recording_dir = buffer_dir.join("recording");
+        let recording_dir = output_dir.join("recording");
+        std::fs::create_dir_all(&recording_dir)?;
+        
+        let mut output_files = Vec::new();
+        for (new_index, session) in kept.iter().enumerate() {
+            output_files.extend(replay::copy_session(
+                &buffer_recording_dir,
+                session.index,
+                &recording_dir,
+                new_index,
+            )?);
+        }
+        
+        // First sample marks are per buffer session; renumber them like the files
+        let mut manifest = self.sync_manifest(&clock, &kept);
+        for (new_index, session) in manifest.sessions.iter_mut().enumerate() {
+            session.index = new_index;
+        }
+        output_files.push(manifest.write(&recording_dir)?);
+        output_files.push(self.sources_manifest().write(&recording_dir)?);
+        
+        let mut sessions = SessionsManifest::build(&kept, &self.channel_files(), &buffer_recording_dir);
+        for (new_index, record) in sessions.sessions.iter_mut().enumerate() {
+            let old_index = record.session.index;
+            record.session.index = new_index;
+            for channel in &mut record.channels {
+                for file in &mut channel.files {
+                    if let Some(name) = replay::renumbered(file, old_index, new_index) {
+                        *file = name;
+                    }
+                }
+            }
+        }
+        output_files.extend(self.write_project_files(output_dir, &sessions));
+        
+        let bundle_path = output_dir.to_string_lossy().to_string();
+        let total_duration_ms = kept.iter().map(|s| s.duration_ms).sum();
+        tracing::info!("Replay saved: {} sessions, {}ms", kept.len(), total_duration_ms);
+        let _ = self.event_tx.send(RecordingEvent::ReplaySaved {
+            bundle_path: bundle_path.clone(),
+        });
+        
+        Ok(RecordingOutput {
+            bundle_path,
+            total_duration_ms,
+            session_count: kept.len(),
+            output_files,
+            failed_channels,
+        })
+    }
+    
+    /// Stop the replay buffer and delete its footage
+    pub async fn stop_replay(&mut self) -> RecordingResult<()> {
+        if self.replay.is_none() {
+            return Err(RecordingError::NotRecording);
+        }
+        let buffer = self.replay_buffer.take();
+        self.finish(StopReason::Requested).await?;
+        
+        if let Some(buffer) = buffer {
+            let buffer_dir = buffer.path().to_path_buf();
+            if let Err(e) = buffer.close() {
+                tracing::warn!("Failed to remove replay buffer {:?}: {}", buffer_dir, e);
+            }
+        }
         Ok(())
     }
     
@@ -248,9 +911,15 @@ impl RecordingCoordinator {
         completed + current
     }
     
+    /// Current capture and encoder statistics of every channel
+    pub fn health(&self) -> RecordingHealth {
+        RecordingHealth::collect(&self.health, self.duration_ms())
+    }
+    
     /// Clear all channels
     pub fn clear_channels(&mut self) {
         self.channels.clear();
+        self.health.clear();
     }
 }
 
@@ -259,3 +928,659 @@ impl Default for RecordingCoordinator {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::capture::audio::AudioFormat;
+    use crate::capture::encoder::{EncoderBackend, RawDumpBackend};
+    use crate::capture::media_tools::{locate, MediaTool};
+    use crate::capture::input::InputTrackingChannel;
+    use crate::capture::synthetic::{
+        self, ScriptedMousePath, SyntheticAudioChannel, SyntheticDisplayChannel, SyntheticSignal,
+        TestSourceConfig,
+    };
+    use crate::recorder::channel::ChannelType;
+    use std::path::Path;
+    use std::time::Duration;
+
+    const FPS: u32 = 30;
+
+    fn test_config(output_dir: &Path) -> RecordingConfig {
+        RecordingConfig {
+            display_id: 0,
+            additional_display_ids: Vec::new(),
+            target: None,
+            capture_system_audio: false,
+            system_audio_device_id: None,
+            capture_microphone: true,
+            microphone_device_id: None,
+            microphone_fallback: Default::default(),
+            microphone_format: AudioFormat::lossless(),
+            system_audio_format: AudioFormat::default(),
+            video: Default::default(),
+            capture_webcam: false,
+            webcam_device_id: None,
+            track_input: true,
+            keystroke_mode: Default::default(),
+            output_dir: output_dir.to_string_lossy().to_string(),
+            disk_space: Default::default(),
+            test_sources: Some(TestSourceConfig {
+                width: 320,
+                height: 180,
+                fps: FPS,
+                ..TestSourceConfig::default()
+            }),
+        }
+    }
+
+    /// Channel that fails one lifecycle operation
+    struct FailingChannel {
+        fail_on: ChannelOperation,
+        recording: bool,
+    }
+
+    impl FailingChannel {
+        fn new(fail_on: ChannelOperation) -> Self {
+            Self { fail_on, recording: false }
+        }
+
+        fn check(&self, operation: ChannelOperation) -> RecordingResult<()> {
+            if operation == self.fail_on {
+                Err(RecordingError::DeviceNotFound("test device".to_string()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl RecordingChannel for FailingChannel {
+        fn id(&self) -> &str {
+            "broken"
+        }
+
+        fn channel_type(&self) -> ChannelType {
+            ChannelType::Microphone
+        }
+
+        async fn initialize(
+            &mut self,
+            _output_dir: &Path,
+            _session_index: usize,
+            _clock: &RecordingClock,
+        ) -> RecordingResult<()> {
+            self.check(ChannelOperation::Initialize)
+        }
+
+        async fn start(&mut self) -> RecordingResult<()> {
+            self.check(ChannelOperation::Start)?;
+            self.recording = true;
+            Ok(())
+        }
+
+        async fn stop(&mut self) -> RecordingResult<()> {
+            self.recording = false;
+            self.check(ChannelOperation::Stop)
+        }
+
+        async fn pause(&mut self) -> RecordingResult<()> {
+            self.recording = false;
+            self.check(ChannelOperation::Pause)
+        }
+
+        async fn resume(&mut self, _session_index: usize) -> RecordingResult<()> {
+            self.check(ChannelOperation::Resume)?;
+            self.recording = true;
+            Ok(())
+        }
+
+        fn is_recording(&self) -> bool {
+            self.recording
+        }
+
+        fn output_files(&self) -> Vec<String> {
+            Vec::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pause_resume_creates_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let display = SyntheticDisplayChannel::new(320, 180, FPS);
+        let frames = display.stats();
+        let mic = SyntheticAudioChannel::new(
+            ChannelType::Microphone,
+            SyntheticSignal::Tone { frequency_hz: 440.0 },
+        );
+        let samples = mic.stats();
+
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(display));
+        coordinator.add_channel(Box::new(mic));
+        coordinator.add_channel(Box::new(InputTrackingChannel::scripted(
+            ScriptedMousePath::tour(320, 180),
+        )));
+
+        coordinator.start(test_config(dir.path())).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        coordinator.pause().await.unwrap();
+        assert_eq!(coordinator.state(), RecordingState::Paused);
+        let paused_frames = frames.total();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(frames.total(), paused_frames, "frames generated while paused");
+
+        coordinator.resume().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let result = coordinator.stop().await.unwrap();
+        assert_eq!(result.session_count, 2);
+        assert_eq!(coordinator.state(), RecordingState::Idle);
+        assert_eq!(frames.session_count(), 2);
+        assert_eq!(samples.session_count(), 2);
+
+        let recording_dir = dir.path().join("recording");
+        for session in 0..2 {
+            for suffix in ["mouse-moves", "mouse-clicks", "cursors"] {
+                let path = recording_dir.join(format!("recording-{}-{}.json", session, suffix));
+                assert!(path.exists(), "missing {:?}", path);
+            }
+        }
+
+        // Each session becomes a full-length scene of the project
+        let project = crate::project::bundle::read_project(dir.path()).unwrap();
+        assert_eq!(project.scenes.len(), 2);
+        assert_eq!(project.scenes[1].session_index, 1);
+        assert!(project.scenes[1].slices[0].source_end_ms > 0.0);
+
+        let sessions: SessionsManifest =
+            serde_json::from_slice(&std::fs::read(recording_dir.join("sessions.json")).unwrap())
+                .unwrap();
+        for session in 0..2 {
+            let input_files = sessions.sessions[session]
+                .channels
+                .iter()
+                .find(|c| c.channel_id == "input")
+                .map(|c| c.files.clone())
+                .unwrap();
+            let moves = format!("recording-{}-mouse-moves.json", session);
+            assert!(input_files.contains(&moves), "session {} lacks {}", session, moves);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_audio_and_video_stay_in_sync() {
+        let dir = tempfile::tempdir().unwrap();
+        let display = SyntheticDisplayChannel::new(320, 180, FPS);
+        let frames = display.stats();
+        let mic = SyntheticAudioChannel::new(
+            ChannelType::Microphone,
+            SyntheticSignal::Clicks { interval_ms: 1000 },
+        );
+        let samples = mic.stats();
+
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(display));
+        coordinator.add_channel(Box::new(mic));
+
+        coordinator.start(test_config(dir.path())).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let result = coordinator.stop().await.unwrap();
+
+        let frame_ms = 1000.0 / FPS as f64;
+        let video_ms = frames.session(0) as f64 * frame_ms;
+        let audio_ms = samples.session(0) as f64 * 1000.0 / 48000.0;
+
+        assert!(
+            (video_ms - audio_ms).abs() <= frame_ms * 2.0,
+            "video {:.1}ms vs audio {:.1}ms",
+            video_ms,
+            audio_ms
+        );
+        assert!(
+            (audio_ms - result.total_duration_ms).abs() <= 100.0,
+            "audio {:.1}ms vs session {:.1}ms",
+            audio_ms,
+            result.total_duration_ms
+        );
+
+        // Both channels start within a frame of the session start
+        let sync: SyncManifest = serde_json::from_slice(
+            &std::fs::read(dir.path().join("recording").join("sync.json")).unwrap(),
+        )
+        .unwrap();
+        for channel in &sync.sessions[0].channels {
+            let offset = channel.offset_ms.expect("channel produced no samples");
+            assert!(
+                (0.0..=frame_ms * 2.0).contains(&offset),
+                "{} starts {:.1}ms into the session",
+                channel.channel_id,
+                offset
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_raw_dump_backend_records_every_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let encoders: Arc<dyn EncoderBackend> = Arc::new(RawDumpBackend);
+        let display = SyntheticDisplayChannel::new(64, 48, FPS).with_encoders(encoders.clone());
+        let frames = display.stats();
+        let mic = SyntheticAudioChannel::new(
+            ChannelType::Microphone,
+            SyntheticSignal::Tone { frequency_hz: 440.0 },
+        )
+        .with_encoders(encoders);
+        let samples = mic.stats();
+
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(display));
+        coordinator.add_channel(Box::new(mic));
+
+        coordinator.start(test_config(dir.path())).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        coordinator.stop().await.unwrap();
+
+        let recording_dir = dir.path().join("recording");
+        let video = std::fs::metadata(recording_dir.join("recording-0.bgra")).unwrap();
+        assert_eq!(video.len(), frames.session(0) * 64 * 48 * 4);
+        let audio = std::fs::metadata(recording_dir.join("recording-0-mic.f32le")).unwrap();
+        assert_eq!(audio.len(), samples.session(0) * 2 * 4);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_transitions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(SyntheticDisplayChannel::new(64, 64, FPS)));
+
+        assert!(matches!(coordinator.pause().await, Err(RecordingError::NotRecording)));
+        assert!(matches!(coordinator.stop().await, Err(RecordingError::NotRecording)));
+
+        coordinator.start(test_config(dir.path())).await.unwrap();
+        assert!(matches!(
+            coordinator.start(test_config(dir.path())).await,
+            Err(RecordingError::AlreadyRecording)
+        ));
+        assert!(matches!(coordinator.resume().await, Err(RecordingError::NotRecording)));
+
+        coordinator.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_start_rolls_back_started_channels() {
+        let dir = tempfile::tempdir().unwrap();
+        let display = SyntheticDisplayChannel::new(64, 64, FPS);
+        let frames = display.stats();
+
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(display));
+        coordinator.add_channel(Box::new(FailingChannel::new(ChannelOperation::Start)));
+
+        let error = coordinator.start(test_config(dir.path())).await.unwrap_err();
+        assert_eq!(error.channel_id(), Some("broken"));
+        assert_eq!(coordinator.state(), RecordingState::Idle);
+        assert!(!dir.path().join("recording").exists());
+
+        // The display channel was started first and must not keep rendering
+        let rendered = frames.total();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(frames.total(), rendered, "display still running after rollback");
+
+        // In a directory that was already there only the new files go
+        let recording_dir = dir.path().join("recording");
+        std::fs::create_dir_all(&recording_dir).unwrap();
+        std::fs::write(recording_dir.join("notes.txt"), "keep").unwrap();
+        coordinator.clear_channels();
+        coordinator.add_channel(Box::new(SyntheticDisplayChannel::new(64, 64, FPS)));
+        coordinator.add_channel(Box::new(FailingChannel::new(ChannelOperation::Start)));
+        coordinator.start(test_config(dir.path())).await.unwrap_err();
+        let names: Vec<String> = std::fs::read_dir(&recording_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, vec!["notes.txt"]);
+
+        coordinator.clear_channels();
+        coordinator.add_channel(Box::new(SyntheticDisplayChannel::new(64, 64, FPS)));
+        coordinator.start(test_config(dir.path())).await.unwrap();
+        coordinator.stop().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stop_continues_past_failing_channel() {
+        let dir = tempfile::tempdir().unwrap();
+        let display = SyntheticDisplayChannel::new(64, 64, FPS);
+        let frames = display.stats();
+
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(FailingChannel::new(ChannelOperation::Stop)));
+        coordinator.add_channel(Box::new(display));
+
+        coordinator.start(test_config(dir.path())).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let result = coordinator.stop().await.unwrap();
+        assert_eq!(coordinator.state(), RecordingState::Idle);
+        assert_eq!(result.failed_channels.len(), 1);
+        assert_eq!(result.failed_channels[0].channel_id, "broken");
+        assert_eq!(result.failed_channels[0].operation, ChannelOperation::Stop);
+        assert_eq!(frames.session_count(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stop_while_paused() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(SyntheticDisplayChannel::new(64, 64, FPS)));
+        coordinator.add_channel(Box::new(FailingChannel::new(ChannelOperation::Pause)));
+
+        coordinator.start(test_config(dir.path())).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // The pause still happens; the failure is reported afterwards
+        let error = coordinator.pause().await.unwrap_err();
+        assert_eq!(error.channel_id(), Some("broken"));
+        assert_eq!(coordinator.state(), RecordingState::Paused);
+
+        let paused_ms = coordinator.duration_ms();
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let result = coordinator.stop().await.unwrap();
+        assert_eq!(result.session_count, 1);
+        assert_eq!(result.total_duration_ms, paused_ms);
+        assert!(result.failed_channels.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_progress_and_channel_errors_are_broadcast() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(SyntheticDisplayChannel::new(64, 64, FPS)));
+        coordinator.add_channel(Box::new(FailingChannel::new(ChannelOperation::Pause)));
+        let mut events = coordinator.subscribe();
+
+        coordinator.start(test_config(dir.path())).await.unwrap();
+        tokio::time::sleep(PROGRESS_INTERVAL * 3).await;
+        let _ = coordinator.pause().await;
+        let result = coordinator.stop().await.unwrap();
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert!(matches!(received.first(), Some(RecordingEvent::Started)));
+        assert!(received.iter().any(|e| matches!(
+            e,
+            RecordingEvent::ChannelError { channel_id, .. } if channel_id == "broken"
+        )));
+
+        // Progress only moves forward and ends at the recorded duration
+        let progress: Vec<f64> = received
+            .iter()
+            .filter_map(|e| match e {
+                RecordingEvent::Progress { duration_ms } => Some(*duration_ms),
+                _ => None,
+            })
+            .collect();
+        assert!(progress.len() >= 2);
+        assert!(progress.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(progress.last(), Some(&result.total_duration_ms));
+    }
+
+    #[tokio::test]
+    async fn test_health_report_is_written() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(SyntheticDisplayChannel::new(64, 64, FPS)));
+
+        coordinator.start(test_config(dir.path())).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let live = coordinator.health();
+        assert!(live.channels[0].frames_captured > 0);
+
+        let result = coordinator.stop().await.unwrap();
+        let path = dir.path().join("recording").join("health.json");
+        assert!(result.output_files.contains(&path.to_string_lossy().to_string()));
+
+        let report: RecordingHealth =
+            serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        let display = &report.channels[0];
+        assert_eq!(display.channel_id, "display-synthetic");
+        assert_eq!(display.target_fps, Some(FPS));
+        assert_eq!(display.frames_dropped, 0);
+        assert!(display.frames_captured >= live.channels[0].frames_captured);
+    }
+
+    /// Free space `test_low_disk_space_stops_recording` reports
+    static FREE_SPACE: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+
+    #[tokio::test]
+    async fn test_low_disk_space_stops_recording() {
+        const MB: u64 = 1024 * 1024;
+        let set_free_space = |bytes| FREE_SPACE.store(bytes, std::sync::atomic::Ordering::SeqCst);
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = RecordingConfig {
+            disk_space: crate::recorder::disk_space::DiskSpaceSettings {
+                reserve_mb: 100,
+                warn_remaining_secs: 3600,
+            },
+            ..test_config(dir.path())
+        };
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.free_space = |_| Ok(FREE_SPACE.load(std::sync::atomic::Ordering::SeqCst));
+        coordinator.add_channel(Box::new(SyntheticDisplayChannel::new(64, 64, FPS)));
+        let mut events = coordinator.subscribe();
+
+        // Already under the reserve
+        set_free_space(50 * MB);
+        let error = coordinator.start(config.clone()).await.unwrap_err();
+        assert!(matches!(error, RecordingError::InsufficientDiskSpace(_)));
+        assert_eq!(coordinator.state(), RecordingState::Idle);
+
+        set_free_space(10_000 * MB);
+        coordinator.start(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        assert!(coordinator.check_disk_space().await.unwrap().is_none());
+
+        // Half a megabyte above the reserve lasts minutes at 64x64, and warns
+        set_free_space(100 * MB + MB / 2);
+        assert!(coordinator.check_disk_space().await.unwrap().is_none());
+        assert_eq!(coordinator.state(), RecordingState::Recording);
+
+        // At the reserve the recording is finalized
+        set_free_space(100 * MB);
+        let result = coordinator.check_disk_space().await.unwrap().unwrap();
+        assert_eq!(coordinator.state(), RecordingState::Idle);
+        assert_eq!(result.session_count, 1);
+
+        let sessions: SessionsManifest = serde_json::from_slice(
+            &std::fs::read(dir.path().join("recording/sessions.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(sessions.stop_reason, StopReason::LowDiskSpace);
+
+        let mut received = Vec::new();
+        while let Ok(event) = events.try_recv() {
+            received.push(event);
+        }
+        assert!(received.iter().any(|e| matches!(
+            e,
+            RecordingEvent::DiskSpaceLow { remaining_secs, .. } if *remaining_secs < 3600.0
+        )));
+        assert!(matches!(
+            received.last(),
+            Some(RecordingEvent::AutoStopped { reason: StopReason::LowDiskSpace, .. })
+        ));
+    }
+
+    /// Full record -> load bundle -> cursor smoothing run (needs FFmpeg)
+    #[tokio::test]
+    async fn test_synthetic_recording_loads_as_bundle() {
+        if locate(MediaTool::Ffmpeg).is_none() {
+            eprintln!("skipping: ffmpeg not installed");
+            return;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = test_config(dir.path());
+        let sources = config.test_sources.clone().unwrap();
+
+        let mut coordinator = RecordingCoordinator::new();
+        for channel in synthetic::test_channels(&config, &sources) {
+            coordinator.add_channel(channel);
+        }
+
+        coordinator.start(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1200)).await;
+        let result = coordinator.stop().await.unwrap();
+
+        let bundle = crate::commands::recording::load_recording_bundle(result.bundle_path)
+            .await
+            .unwrap();
+        assert_eq!(bundle.video_metadata.width, 320);
+        assert_eq!(bundle.video_metadata.height, 180);
+        assert!((bundle.video_metadata.fps - FPS as f64).abs() < 0.5);
+        assert!(bundle.video_metadata.fragmented);
+        assert!(bundle.video_metadata.keyframe_count >= 1);
+        assert!(bundle.mic_audio_path.is_some());
+        assert!(!bundle.mouse_moves.is_empty());
+        assert!(!bundle.mouse_clicks.is_empty());
+
+        let moves_path = dir.path().join("recording/recording-0-mouse-moves.json");
+        let moves: Vec<crate::capture::input::MouseMove> =
+            serde_json::from_str(&std::fs::read_to_string(moves_path).unwrap()).unwrap();
+        let smoothed = crate::processing::cursor_smoothing::smooth_cursor_data(
+            &moves,
+            &crate::project::schema::SpringConfig::default(),
+            FPS as f64,
+        );
+        assert!(!smoothed.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_additional_displays_get_their_own_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = RecordingConfig {
+            additional_display_ids: vec![1, 0, 1],
+            capture_microphone: false,
+            ..test_config(dir.path())
+        };
+        let sources = config.test_sources.clone().unwrap();
+
+        let mut coordinator = RecordingCoordinator::new();
+        for channel in synthetic::test_channels(&config, &sources) {
+            coordinator.add_channel(channel);
+        }
+
+        coordinator.start(config).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        coordinator.stop().await.unwrap();
+
+        let recording_dir = dir.path().join("recording");
+        for name in [
+            "recording-0-frames.json",
+            "recording-0-mouse-moves.json",
+            "recording-0-display-1-frames.json",
+            "recording-0-display-1-mouse-moves.json",
+        ] {
+            assert!(recording_dir.join(name).exists(), "missing {}", name);
+        }
+
+        let manifest = SourcesManifest::read(&recording_dir).unwrap().unwrap();
+        let suffixes: Vec<_> = manifest
+            .sources
+            .iter()
+            .map(|s| s.file_suffix.as_deref())
+            .collect();
+        assert_eq!(suffixes, vec![None, Some("display-1")]);
+    }
+
+    #[tokio::test]
+    async fn test_replay_buffer_keeps_the_window() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = ReplayConfig {
+            duration_secs: 1,
+            segment_secs: 1,
+            buffer_dir: Some(dir.path().join("buffer").to_string_lossy().to_string()),
+        };
+
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(SyntheticDisplayChannel::new(320, 180, FPS)));
+        coordinator.add_channel(Box::new(InputTrackingChannel::scripted(
+            ScriptedMousePath::tour(320, 180),
+        )));
+
+        coordinator.start_replay(test_config(dir.path()), replay).await.unwrap();
+        let buffer = coordinator.replay_buffer_dir().unwrap().to_path_buf();
+        assert_eq!(buffer.parent(), Some(dir.path().join("buffer").as_path()));
+        let buffer_dir = buffer.join("recording");
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        coordinator.rotate_replay().await.unwrap();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        coordinator.rotate_replay().await.unwrap();
+
+        // The first segment ended more than a second ago
+        assert!(!buffer_dir.join("recording-0-mouse-moves.json").exists());
+        assert!(buffer_dir.join("recording-1-mouse-moves.json").exists());
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let saved_dir = dir.path().join("saved");
+        let result = coordinator.save_replay(&saved_dir).await.unwrap();
+        assert_eq!(result.session_count, 2);
+        assert_eq!(coordinator.state(), RecordingState::Recording);
+
+        let recording_dir = saved_dir.join("recording");
+        for session in 0..2 {
+            let path = recording_dir.join(format!("recording-{}-mouse-moves.json", session));
+            assert!(path.exists(), "missing {:?}", path);
+        }
+        let sync: SyncManifest =
+            serde_json::from_slice(&std::fs::read(recording_dir.join("sync.json")).unwrap())
+                .unwrap();
+        assert_eq!(
+            sync.sessions.iter().map(|s| s.index).collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        let project = crate::project::bundle::read_project(&saved_dir).unwrap();
+        assert_eq!(project.scenes.len(), 2);
+
+        coordinator.stop_replay().await.unwrap();
+        assert!(!coordinator.is_replay());
+        assert!(!buffer.exists());
+        assert!(coordinator.replay_buffer_dir().is_none());
+        assert!(dir.path().join("buffer").exists());
+        assert!(recording_dir.join("recording-0-mouse-moves.json").exists());
+    }
+
+    #[tokio::test]
+    async fn test_stop_refuses_the_replay_buffer() {
+        let dir = tempfile::tempdir().unwrap();
+        let replay = ReplayConfig {
+            duration_secs: 1,
+            segment_secs: 1,
+            buffer_dir: Some(dir.path().join("buffer").to_string_lossy().to_string()),
+        };
+
+        let mut coordinator = RecordingCoordinator::new();
+        coordinator.add_channel(Box::new(InputTrackingChannel::scripted(
+            ScriptedMousePath::tour(320, 180),
+        )));
+        coordinator.start_replay(test_config(dir.path()), replay).await.unwrap();
+        let buffer = coordinator.replay_buffer_dir().unwrap().to_path_buf();
+
+        // Stopping would hand back a bundle inside the buffer, which is deleted
+        assert!(matches!(
+            coordinator.stop().await,
+            Err(RecordingError::ConfigurationError(_))
+        ));
+        assert_eq!(coordinator.state(), RecordingState::Recording);
+        assert!(coordinator.is_replay());
+        assert!(buffer.exists());
+
+        coordinator.stop_replay().await.unwrap();
+        assert_eq!(coordinator.state(), RecordingState::Idle);
+        assert!(!buffer.exists());
+    }
+}
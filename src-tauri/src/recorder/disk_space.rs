@@ -0,0 +1,261 @@
+//! Disk space guard
+//!
+//! Display channels pipe raw frames into FFmpeg, and once the volume fills
+//! FFmpeg can't write and the recording ends wherever the disk did. The
+//! coordinator checks free space on the output volume before starting and
+//! every `CHECK_INTERVAL` while recording: it warns with `DiskSpaceLow` once
+//! the recording time left drops under `warn_remaining_secs`, and stops the
+//! recording, finalizing every channel, before free space falls under
+//! `reserve_mb`. `sessions.json` records that the disk ended the recording.
+//!
+//! How fast the recording uses the disk is estimated per channel from its
+//! settings until enough has been recorded to measure it from the free space
+//! itself.
+
+use super::channel::ChannelType;
+use super::sources::VideoSource;
+use super::state::RecordingConfig;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// How often free space is checked while recording
+pub const CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Recorded time after which the measured rate replaces the estimate
+const MEASURE_AFTER_MS: f64 = 10_000.0;
+
+/// How often `DiskSpaceLow` is repeated while space stays low
+const WARNING_INTERVAL_MS: f64 = 60_000.0;
+
+/// Webcam recordings, H.264 at the camera's resolution (typically 720p)
+const WEBCAM_KBPS: u64 = 4_000;
+
+/// Input event files and cursor images
+const INPUT_KBPS: u64 = 64;
+
+/// Size assumed for displays that don't report one
+const DEFAULT_DISPLAY_SIZE: (u32, u32) = (1920, 1080);
+
+/// Free space limits of a recording
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiskSpaceSettings {
+    /// Free space to leave on the volume; the recording stops before less is left
+    pub reserve_mb: u64,
+    /// Warn once less than this much recording time is left above the reserve
+    pub warn_remaining_secs: u64,
+}
+
+impl Default for DiskSpaceSettings {
+    fn default() -> Self {
+        Self {
+            reserve_mb: 500,
+            warn_remaining_secs: 300,
+        }
+    }
+}
+
+impl DiskSpaceSettings {
+    pub fn reserve_bytes(&self) -> u64 {
+        self.reserve_mb.saturating_mul(1024 * 1024)
+    }
+}
+
+/// Free space on the volume holding `path`
+pub fn available_space(path: &Path) -> std::io::Result<u64> {
+    fs4::available_space(path)
+}
+
+/// Estimated bitrate of a channel's files in kbps
+///
+/// Display channels are estimated from their captured size (known once
+/// initialized) and the video settings.
+pub fn estimate_bitrate_kbps(
+    channel_type: ChannelType,
+    source: Option<&VideoSource>,
+    config: &RecordingConfig,
+) -> u64 {
+    match channel_type {
+        ChannelType::Display => {
+            let (width, height) = source
+                .and_then(|s| s.capture_size.as_ref())
+                .map(|size| (size.width, size.height))
+                .unwrap_or(DEFAULT_DISPLAY_SIZE);
+            config.video.estimated_kbps(width, height)
+        }
+        ChannelType::Microphone => config.microphone_format.estimated_kbps(),
+        ChannelType::SystemAudio => config.system_audio_format.estimated_kbps(),
+        ChannelType::Webcam => WEBCAM_KBPS,
+        ChannelType::Input => INPUT_KBPS,
+    }
+}
+
+/// Outcome of a free space check
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DiskSpaceCheck {
+    /// More than `warn_remaining_secs` of recording time left
+    Enough,
+    /// Running low; `remaining_secs` is the recording time left above the reserve
+    Low {
+        free_bytes: u64,
+        remaining_secs: f64,
+    },
+    /// The reserve would be reached before the next check
+    Exhausted { free_bytes: u64 },
+}
+
+/// Tracks free space over one recording
+#[derive(Debug, Clone)]
+pub struct DiskSpaceGuard {
+    settings: DiskSpaceSettings,
+    /// Sum of the channels' estimated rates
+    estimated_bytes_per_sec: f64,
+    /// Free space at the first check
+    initial_free: Option<u64>,
+    /// Recorded time of the last `DiskSpaceLow`
+    last_warning_ms: Option<f64>,
+}
+
+impl DiskSpaceGuard {
+    /// Guard for channels recording at `estimated_kbps` in total
+    pub fn new(settings: DiskSpaceSettings, estimated_kbps: u64) -> Self {
+        Self {
+            settings,
+            estimated_bytes_per_sec: estimated_kbps as f64 * 1000.0 / 8.0,
+            initial_free: None,
+            last_warning_ms: None,
+        }
+    }
+
+    /// Bytes the recording writes per second, measured once enough is recorded
+    fn bytes_per_sec(&self, free_bytes: u64, recorded_ms: f64) -> f64 {
+        match self.initial_free {
+            Some(initial) if recorded_ms >= MEASURE_AFTER_MS => {
+                initial.saturating_sub(free_bytes) as f64 * 1000.0 / recorded_ms
+            }
+            _ => self.estimated_bytes_per_sec,
+        }
+    }
+
+    /// Assess the free space after `recorded_ms` of recording
+    ///
+    /// The first check (before starting) is the baseline the rate is
+    /// measured against.
+    pub fn check(&mut self, free_bytes: u64, recorded_ms: f64) -> DiskSpaceCheck {
+        self.initial_free.get_or_insert(free_bytes);
+        let rate = self.bytes_per_sec(free_bytes, recorded_ms);
+
+        // Stop while the reserve is still intact at the next check
+        let usable = free_bytes.saturating_sub(self.settings.reserve_bytes()) as f64;
+        if usable <= rate * CHECK_INTERVAL.as_secs_f64() {
+            return DiskSpaceCheck::Exhausted { free_bytes };
+        }
+
+        if rate <= 0.0 {
+            return DiskSpaceCheck::Enough;
+        }
+        let remaining_secs = usable / rate;
+        if remaining_secs < self.settings.warn_remaining_secs as f64 {
+            DiskSpaceCheck::Low {
+                free_bytes,
+                remaining_secs,
+            }
+        } else {
+            DiskSpaceCheck::Enough
+        }
+    }
+
+    /// Whether to send `DiskSpaceLow` now, at most once per `WARNING_INTERVAL_MS`
+    pub fn warning_due(&mut self, recorded_ms: f64) -> bool {
+        let due = self
+            .last_warning_ms
+            .is_none_or(|last| recorded_ms - last >= WARNING_INTERVAL_MS);
+        if due {
+            self.last_warning_ms = Some(recorded_ms);
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MB: u64 = 1024 * 1024;
+
+    fn test_guard() -> DiskSpaceGuard {
+        // 8000 kbps is 1 MB/s (decimal); a 100 MB reserve, warn under 60s left
+        DiskSpaceGuard::new(
+            DiskSpaceSettings {
+                reserve_mb: 100,
+                warn_remaining_secs: 60,
+            },
+            8_000,
+        )
+    }
+
+    #[test]
+    fn test_estimate_decides_until_measured() {
+        let mut guard = test_guard();
+        assert_eq!(guard.check(10_000 * MB, 0.0), DiskSpaceCheck::Enough);
+
+        // 130 MB above the reserve is ~136s at the estimated 1 MB/s
+        assert_eq!(guard.check(230 * MB, 5_000.0), DiskSpaceCheck::Enough);
+
+        // 30 MB above it is ~31s
+        match guard.check(130 * MB, 5_000.0) {
+            DiskSpaceCheck::Low { remaining_secs, .. } => {
+                assert!((remaining_secs - 31.5).abs() < 0.1)
+            }
+            other => panic!("expected Low, got {:?}", other),
+        }
+
+        // Less than one check interval's worth
+        assert_eq!(
+            guard.check(104 * MB, 5_000.0),
+            DiskSpaceCheck::Exhausted {
+                free_bytes: 104 * MB
+            }
+        );
+    }
+
+    #[test]
+    fn test_measured_rate_replaces_estimate() {
+        let mut guard = test_guard();
+        guard.check(1_000 * MB, 0.0);
+
+        // 600 MB written in 20s is 30 MB/s: 300 MB above the reserve lasts
+        // 10s, 140 MB runs out before the next check
+        assert!(matches!(
+            guard.check(400 * MB, 20_000.0),
+            DiskSpaceCheck::Low { .. }
+        ));
+        assert!(matches!(
+            guard.check(240 * MB, 20_000.0),
+            DiskSpaceCheck::Exhausted { .. }
+        ));
+
+        // Nothing written measures as no rate at all
+        let mut idle = test_guard();
+        idle.check(200 * MB, 0.0);
+        assert_eq!(idle.check(200 * MB, 20_000.0), DiskSpaceCheck::Enough);
+    }
+
+    #[test]
+    fn test_below_reserve_is_exhausted() {
+        let mut guard = DiskSpaceGuard::new(DiskSpaceSettings::default(), 0);
+        assert!(matches!(
+            guard.check(499 * MB, 0.0),
+            DiskSpaceCheck::Exhausted { .. }
+        ));
+    }
+
+    #[test]
+    fn test_warnings_are_spaced_out() {
+        let mut guard = test_guard();
+        assert!(guard.warning_due(1_000.0));
+        assert!(!guard.warning_due(30_000.0));
+        assert!(guard.warning_due(61_000.0));
+    }
+}
@@ -0,0 +1,258 @@
+//! Live recording health
+//!
+//! Each channel gets a `ChannelHealth` handle from the coordinator and counts
+//! what happens on its capture path: frames captured, dropped by the pacer or
+//! repeated to fill gaps, bytes and audio written to the encoder, and how long
+//! each encoder write blocked. A slow or dying FFmpeg shows up as high write
+//! latency and write failures; a stalled capture source as low achieved fps
+//! or missing audio.
+//!
+//! The coordinator turns the counters into a `RecordingHealth` report on
+//! demand, broadcasts it while recording and writes the final one to
+//! `health.json` in the recording directory.
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[derive(Debug, Default)]
+struct HealthCounters {
+    target_fps: AtomicU32,
+    frames_captured: AtomicU64,
+    frames_dropped: AtomicU64,
+    frames_repeated: AtomicU64,
+    bytes_written: AtomicU64,
+    sample_rate: AtomicU32,
+    audio_frames_written: AtomicU64,
+    write_count: AtomicU64,
+    write_failures: AtomicU64,
+    write_time_us: AtomicU64,
+    max_write_time_us: AtomicU64,
+}
+
+/// Counters of one channel, shared between the channel's capture threads
+/// and the coordinator
+#[derive(Debug, Clone, Default)]
+pub struct ChannelHealth {
+    channel_id: String,
+    channel_type: String,
+    counters: Arc<HealthCounters>,
+}
+
+impl ChannelHealth {
+    pub fn new(channel_id: &str, channel_type: &str) -> Self {
+        Self {
+            channel_id: channel_id.to_string(),
+            channel_type: channel_type.to_string(),
+            counters: Arc::new(HealthCounters::default()),
+        }
+    }
+
+    /// Clear the counters for a new recording
+    pub fn reset(&self) {
+        let c = &self.counters;
+        for counter in [
+            &c.frames_captured,
+            &c.frames_dropped,
+            &c.frames_repeated,
+            &c.bytes_written,
+            &c.audio_frames_written,
+            &c.write_count,
+            &c.write_failures,
+            &c.write_time_us,
+            &c.max_write_time_us,
+        ] {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Frame rate the channel is trying to capture at
+    pub fn set_target_fps(&self, fps: u32) {
+        self.counters.target_fps.store(fps, Ordering::Relaxed);
+    }
+
+    /// Count a captured frame by where the frame pacer placed it
+    ///
+    /// `placement` is the number of repeats written before the frame, None if
+    /// it was dropped.
+    pub fn record_frame(&self, placement: Option<u64>) {
+        match placement {
+            Some(repeats) => self.record_frames(1, 0, repeats),
+            None => self.record_frames(1, 1, 0),
+        }
+    }
+
+    /// Count frames for sources that report totals (e.g. FFmpeg's own capture)
+    pub fn record_frames(&self, captured: u64, dropped: u64, repeated: u64) {
+        let c = &self.counters;
+        c.frames_captured.fetch_add(captured, Ordering::Relaxed);
+        c.frames_dropped.fetch_add(dropped, Ordering::Relaxed);
+        c.frames_repeated.fetch_add(repeated, Ordering::Relaxed);
+    }
+
+    /// Count one write to an encoder's stdin and how long it blocked
+    pub fn record_write(&self, bytes: usize, elapsed: Duration, accepted: bool) {
+        let c = &self.counters;
+        let micros = elapsed.as_micros() as u64;
+        c.write_count.fetch_add(1, Ordering::Relaxed);
+        c.write_time_us.fetch_add(micros, Ordering::Relaxed);
+        c.max_write_time_us.fetch_max(micros, Ordering::Relaxed);
+        if accepted {
+            c.bytes_written.fetch_add(bytes as u64, Ordering::Relaxed);
+        } else {
+            c.write_failures.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Count bytes written by an encoder that doesn't go through `record_write`
+    pub fn record_bytes(&self, bytes: u64) {
+        self.counters
+            .bytes_written
+            .fetch_add(bytes, Ordering::Relaxed);
+    }
+
+    /// Count audio frames (one sample per channel) handed to the encoder
+    pub fn record_audio(&self, frames: u64, sample_rate: u32) {
+        let c = &self.counters;
+        c.sample_rate.store(sample_rate, Ordering::Relaxed);
+        c.audio_frames_written.fetch_add(frames, Ordering::Relaxed);
+    }
+
+    /// Current counters, with rates over the given recorded duration
+    pub fn report(&self, recorded_ms: f64) -> ChannelHealthReport {
+        let c = &self.counters;
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        let seconds = recorded_ms / 1000.0;
+
+        let frames_captured = load(&c.frames_captured);
+        let frames_dropped = load(&c.frames_dropped);
+        let write_count = load(&c.write_count);
+        let target_fps = c.target_fps.load(Ordering::Relaxed);
+        let sample_rate = c.sample_rate.load(Ordering::Relaxed);
+
+        ChannelHealthReport {
+            channel_id: self.channel_id.clone(),
+            channel_type: self.channel_type.clone(),
+            target_fps: (target_fps > 0).then_some(target_fps),
+            frames_captured,
+            frames_dropped,
+            frames_repeated: load(&c.frames_repeated),
+            achieved_fps: if seconds > 0.0 {
+                frames_captured.saturating_sub(frames_dropped) as f64 / seconds
+            } else {
+                0.0
+            },
+            bytes_written: load(&c.bytes_written),
+            sample_rate: (sample_rate > 0).then_some(sample_rate),
+            audio_frames_written: load(&c.audio_frames_written),
+            audio_frames_expected: (recorded_ms * sample_rate as f64 / 1000.0) as u64,
+            write_count,
+            write_failures: load(&c.write_failures),
+            avg_write_latency_ms: if write_count > 0 {
+                load(&c.write_time_us) as f64 / write_count as f64 / 1000.0
+            } else {
+                0.0
+            },
+            max_write_latency_ms: load(&c.max_write_time_us) as f64 / 1000.0,
+        }
+    }
+}
+
+/// Health of one channel at a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelHealthReport {
+    pub channel_id: String,
+    pub channel_type: String,
+    /// Capture frame rate, None for channels without video
+    pub target_fps: Option<u32>,
+    pub frames_captured: u64,
+    /// Captured frames that landed in an already-filled output slot
+    pub frames_dropped: u64,
+    /// Output frames filled by repeating the previous frame (capture too slow)
+    pub frames_repeated: u64,
+    /// Distinct captured frames per recorded second
+    pub achieved_fps: f64,
+    pub bytes_written: u64,
+    /// Audio sample rate, None for channels without audio
+    pub sample_rate: Option<u32>,
+    pub audio_frames_written: u64,
+    /// Audio frames the recorded duration should hold; a shortfall means overruns
+    pub audio_frames_expected: u64,
+    pub write_count: u64,
+    /// Writes the encoder didn't accept (it exited or its pipe closed)
+    pub write_failures: u64,
+    pub avg_write_latency_ms: f64,
+    pub max_write_latency_ms: f64,
+}
+
+/// Health of all channels, as returned by `get_recording_health` and
+/// written to `health.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordingHealth {
+    /// Recorded duration the rates are measured over (excluding pauses)
+    pub duration_ms: f64,
+    pub channels: Vec<ChannelHealthReport>,
+}
+
+impl RecordingHealth {
+    pub fn collect(channels: &[ChannelHealth], duration_ms: f64) -> Self {
+        Self {
+            duration_ms,
+            channels: channels.iter().map(|c| c.report(duration_ms)).collect(),
+        }
+    }
+
+    /// Write the report as `health.json` in the recording directory
+    pub fn write(&self, recording_dir: &Path) -> std::io::Result<String> {
+        let path = recording_dir.join("health.json");
+        let data = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(&path, data)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frame_placement_counts() {
+        let health = ChannelHealth::new("display-0", "display");
+        health.set_target_fps(30);
+        health.record_frame(Some(0));
+        health.record_frame(Some(2));
+        health.record_frame(None);
+        health.record_frame(Some(0));
+
+        let report = health.report(100.0);
+        assert_eq!(report.target_fps, Some(30));
+        assert_eq!(report.frames_captured, 4);
+        assert_eq!(report.frames_dropped, 1);
+        assert_eq!(report.frames_repeated, 2);
+        assert_eq!(report.achieved_fps, 30.0);
+        assert_eq!(report.sample_rate, None);
+    }
+
+    #[test]
+    fn test_audio_shortfall_and_write_latency() {
+        let health = ChannelHealth::new("microphone", "microphone");
+        health.record_audio(24_000, 48_000);
+        health.record_write(1000, Duration::from_millis(2), true);
+        health.record_write(1000, Duration::from_millis(6), false);
+
+        let report = health.report(1000.0);
+        assert_eq!(report.audio_frames_expected, 48_000);
+        assert_eq!(report.audio_frames_written, 24_000);
+        assert_eq!(report.bytes_written, 1000);
+        assert_eq!(report.write_failures, 1);
+        assert_eq!(report.avg_write_latency_ms, 4.0);
+        assert_eq!(report.max_write_latency_ms, 6.0);
+
+        health.reset();
+        assert_eq!(health.report(1000.0).write_count, 0);
+    }
+}
@@ -3,12 +3,27 @@
 //! This module implements the multi-channel recording architecture:
 //! - RecordingChannel trait for different capture sources
 //! - RecordingCoordinator to orchestrate multiple channels
-//! - Segment writer for HLS/fMP4 output
+//! - RecordingClock shared by all channels as a common time base
+//! - Fragmented MP4 output settings and crash recovery
+//! - Per-channel health counters (dropped frames, encoder latency, audio overruns)
+//! - Instant replay buffer of the last few seconds
+//! - Video sources of multi-display recordings
+//! - Session manifest and the project written at stop
+//! - Disk space guard stopping before the output volume fills
 
 pub mod channel;
+pub mod clock;
 pub mod coordinator;
+pub mod disk_space;
+pub mod health;
+pub mod recovery;
+pub mod replay;
+pub mod segment;
+pub mod sessions;
+pub mod sources;
 pub mod state;
 
 pub use channel::RecordingChannel;
+pub use clock::RecordingClock;
 pub use coordinator::RecordingCoordinator;
 pub use state::{RecordingState, RecordingSession};
@@ -0,0 +1,473 @@
+//! Recovery of recordings interrupted by a crash
+//!
+//! A recording cut off before stop leaves fragmented media files without a
+//! final index and input events only in their `.jsonl` journals. Recovery
+//! converts the journals into the regular event files, derives the drag
+//! events stop would have written, and remuxes each media file that was cut
+//! off up to its last complete fragment. It then writes the `sessions.json`
+//! and project stop would have, so the bundle loads like any other.
+//!
+//! Pauses aren't recorded anywhere until stop, so recovered sessions follow
+//! each other directly, each as long as its longest video.
+
+use super::channel::{ChannelType, RecordingError, RecordingResult};
+use super::sessions::{project_name, ChannelFiles, SessionsManifest};
+use super::sources::display_file_suffix;
+use super::state::RecordingSession;
+use crate::capture::input::drags::detect_drags;
+use crate::capture::input::journal::{read_journal, JOURNAL_EXTENSION};
+use crate::capture::input::types::{MouseClick, MouseMove};
+use crate::capture::media_tools::{self, MediaTool};
+use crate::project::bundle::write_project;
+use crate::utils::media_info;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+/// Outcome of `recover_recording`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryReport {
+    pub bundle_path: String,
+    /// Sessions found in the recording directory
+    pub session_count: usize,
+    /// Files rebuilt from journals or remuxed, and manifests written
+    pub recovered_files: Vec<String>,
+    /// Media files that could not be read back
+    pub failed_files: Vec<String>,
+}
+
+/// Rebuild a loadable bundle from a half-written recording directory
+pub fn recover_recording(bundle_path: &Path) -> RecordingResult<RecoveryReport> {
+    let recording_dir = if bundle_path.join("recording").is_dir() {
+        bundle_path.join("recording")
+    } else {
+        bundle_path.to_path_buf()
+    };
+    if !recording_dir.is_dir() {
+        return Err(RecordingError::ConfigurationError(format!(
+            "Recording directory not found: {:?}",
+            recording_dir
+        )));
+    }
+
+    let mut report = RecoveryReport {
+        bundle_path: bundle_path.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+
+    let files = list_files(&recording_dir)?;
+    let mut sessions = BTreeSet::new();
+
+    for path in &files {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if let Some(index) = session_index(name) {
+            sessions.insert(index);
+        }
+
+        match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext == JOURNAL_EXTENSION => {
+                if let Some(json_path) = recover_journal(path)? {
+                    report.recovered_files.push(json_path);
+                }
+            }
+            // Ogg Opus is streamable and needs no repair
+            Some("mp4" | "mkv" | "m4a" | "flac" | "wav" | "caf") => {
+                if media_info::is_complete(path).unwrap_or(false) {
+                    continue;
+                }
+                match remux(path) {
+                    Ok(()) => report
+                        .recovered_files
+                        .push(path.to_string_lossy().to_string()),
+                    Err(e) => {
+                        tracing::warn!("Failed to recover {:?}: {}", path, e);
+                        report.failed_files.push(path.to_string_lossy().to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Drags are derived at stop, which never happened; every input channel
+    // (one per display) has its own moves and clicks
+    for path in list_files(&recording_dir)? {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+        if let Some(base) = name.strip_suffix("-mouse-moves.json") {
+            if let Some(drags_path) = recover_drags(&recording_dir, base)? {
+                report.recovered_files.push(drags_path);
+            }
+        }
+    }
+
+    report.session_count = sessions.len();
+    report
+        .recovered_files
+        .extend(write_manifests(bundle_path, &recording_dir, &sessions)?);
+
+    tracing::info!(
+        "Recovered {:?}: {} sessions, {} files recovered, {} failed",
+        recording_dir,
+        report.session_count,
+        report.recovered_files.len(),
+        report.failed_files.len()
+    );
+    Ok(report)
+}
+
+fn list_files(dir: &Path) -> RecordingResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_file() {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Session index from a `recording-N...` file name
+pub fn session_index(name: &str) -> Option<usize> {
+    let rest = name.strip_prefix("recording-")?;
+    let digits: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+/// Write the final JSON file for a journal left behind, then remove the journal
+///
+/// Returns the path of the JSON file if it had to be rebuilt.
+fn recover_journal(journal_path: &Path) -> RecordingResult<Option<String>> {
+    let json_path = journal_path.with_extension("json");
+
+    // The final file is written before the journal is removed, so it's complete
+    let rebuilt = if json_path.exists() {
+        None
+    } else {
+        let events: Vec<serde_json::Value> = read_journal(journal_path)?;
+        let data = serde_json::to_vec_pretty(&events).map_err(std::io::Error::other)?;
+        std::fs::write(&json_path, data)?;
+        Some(json_path.to_string_lossy().to_string())
+    };
+
+    std::fs::remove_file(journal_path)?;
+    Ok(rebuilt)
+}
+
+/// Derive the drags file of an input channel's session from its recovered
+/// moves and clicks, `base` being their name without the `-mouse-*` part
+fn recover_drags(recording_dir: &Path, base: &str) -> RecordingResult<Option<String>> {
+    let moves_path = recording_dir.join(format!("{}-mouse-moves.json", base));
+    let clicks_path = recording_dir.join(format!("{}-mouse-clicks.json", base));
+    let drags_path = recording_dir.join(format!("{}-mouse-drags.json", base));
+    if drags_path.exists() || !moves_path.exists() || !clicks_path.exists() {
+        return Ok(None);
+    }
+
+    let moves: Vec<MouseMove> = read_json(&moves_path)?;
+    let clicks: Vec<MouseClick> = read_json(&clicks_path)?;
+
+    let drags = detect_drags(&moves, &clicks);
+    let data = serde_json::to_vec_pretty(&drags).map_err(std::io::Error::other)?;
+    std::fs::write(&drags_path, data)?;
+    Ok(Some(drags_path.to_string_lossy().to_string()))
+}
+
+/// Write `sessions.json` and the project if the recording never got them
+///
+/// The project is only written when `recording_dir` is the `recording`
+/// directory of a bundle. Returns the files written.
+fn write_manifests(
+    bundle_path: &Path,
+    recording_dir: &Path,
+    indexes: &BTreeSet<usize>,
+) -> RecordingResult<Vec<String>> {
+    let mut written = Vec::new();
+    let sessions_path = recording_dir.join("sessions.json");
+    let manifest = if sessions_path.exists() {
+        read_json(&sessions_path)?
+    } else {
+        let files = list_files(recording_dir)?;
+        let mut sessions = Vec::new();
+        let mut start_ms = 0.0;
+        for &index in indexes {
+            let mut session = RecordingSession::new(index, start_ms);
+            start_ms += session_duration_ms(&files, index);
+            session.end(start_ms);
+            sessions.push(session);
+        }
+        let manifest = SessionsManifest::build(&sessions, &channel_files(&files), recording_dir);
+        written.push(manifest.write(recording_dir)?);
+        manifest
+    };
+
+    if recording_dir != bundle_path && !bundle_path.join("project.json").exists() {
+        let project = manifest.to_project(&project_name(bundle_path));
+        write_project(&project, bundle_path).map_err(std::io::Error::other)?;
+        written.push(
+            bundle_path
+                .join("project.json")
+                .to_string_lossy()
+                .to_string(),
+        );
+    }
+    Ok(written)
+}
+
+/// Length of a session, from its longest video or else its mouse moves
+fn session_duration_ms(files: &[PathBuf], index: usize) -> f64 {
+    let session_files = || {
+        files.iter().filter(move |path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(session_index)
+                == Some(index)
+        })
+    };
+
+    let video_ms = session_files()
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|e| e.to_str()),
+                Some("mp4" | "mkv" | "webm")
+            )
+        })
+        .filter_map(|path| media_info::read_video_metadata(path).ok())
+        .map(|metadata| metadata.duration_ms)
+        .fold(0.0, f64::max);
+    if video_ms > 0.0 {
+        return video_ms;
+    }
+
+    session_files()
+        .filter(|path| path.to_string_lossy().ends_with("-mouse-moves.json"))
+        .filter_map(|path| read_json::<Vec<MouseMove>>(path).ok())
+        .filter_map(|moves| Some(moves.last()?.process_time_ms - moves.first()?.process_time_ms))
+        .fold(0.0, f64::max)
+}
+
+/// Group recording files by the channel that wrote them, going by their names
+fn channel_files(files: &[PathBuf]) -> Vec<ChannelFiles> {
+    let mut channels: Vec<ChannelFiles> = Vec::new();
+    for path in files {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        let Some((channel_id, channel_type)) = file_channel(name) else {
+            continue;
+        };
+        let file = path.to_string_lossy().to_string();
+        match channels.iter_mut().find(|c| c.channel_id == channel_id) {
+            Some(channel) => channel.files.push(file),
+            None => channels.push(ChannelFiles {
+                channel_id,
+                channel_type: channel_type.to_string(),
+                files: vec![file],
+            }),
+        }
+    }
+    channels
+}
+
+/// ID and type of the channel that writes a `recording-N...` file
+///
+/// The ID of the main display's channel names the display, which the files
+/// don't, so it is recovered as plain `display`.
+fn file_channel(name: &str) -> Option<(String, ChannelType)> {
+    let rest = name
+        .strip_prefix("recording-")?
+        .trim_start_matches(|c: char| c.is_ascii_digit());
+
+    // Files of additional displays carry the display's suffix
+    let (display, rest) = match rest.strip_prefix("-display-") {
+        Some(rest) => {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let display_id: u32 = rest[..digits].parse().ok()?;
+            (Some(display_file_suffix(display_id)), &rest[digits..])
+        }
+        None => (None, rest),
+    };
+
+    let with_display = |id: &str| match &display {
+        Some(suffix) => format!("{}-{}", id, suffix),
+        None => id.to_string(),
+    };
+    if rest.starts_with("-mouse-")
+        || rest.starts_with("-keystrokes")
+        || rest.starts_with("-cursors")
+    {
+        Some((with_display("input"), ChannelType::Input))
+    } else if rest.starts_with("-mic.") {
+        Some(("microphone".to_string(), ChannelType::Microphone))
+    } else if rest.starts_with("-system.") {
+        Some(("system-audio".to_string(), ChannelType::SystemAudio))
+    } else if rest.starts_with("-webcam") {
+        Some(("webcam".to_string(), ChannelType::Webcam))
+    } else if rest.is_empty() || rest.starts_with('.') || rest.starts_with("-frames") {
+        Some((
+            display.unwrap_or_else(|| "display".to_string()),
+            ChannelType::Display,
+        ))
+    } else {
+        None
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> RecordingResult<T> {
+    let data = std::fs::read(path)?;
+    Ok(serde_json::from_slice(&data).map_err(std::io::Error::other)?)
+}
+
+/// Rewrite a media file with a complete header, keeping every complete
+/// fragment (MP4) or frame (MKV, FLAC, WAV, CAF)
+fn remux(path: &Path) -> Result<(), String> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("mp4");
+    let temp_path = path.with_extension(format!("recovered.{}", extension));
+
+    let mut command = media_tools::require(MediaTool::Ffmpeg).map_err(|e| e.to_string())?;
+    command
+        .args(["-y", "-v", "error", "-i"])
+        .arg(path)
+        .args(["-c", "copy"]);
+    if matches!(extension, "mp4" | "m4a") {
+        command.args(["-movflags", "+faststart"]);
+    }
+    let output = command
+        .arg(&temp_path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run FFmpeg: {}", e))?;
+
+    let written = std::fs::metadata(&temp_path)
+        .map(|m| m.len() > 0)
+        .unwrap_or(false);
+    if !output.status.success() || !written {
+        let _ = std::fs::remove_file(&temp_path);
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+
+    std::fs::rename(&temp_path, path).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn mouse_move(x: f64, t: f64) -> MouseMove {
+        MouseMove {
+            x,
+            y: 10.0,
+            cursor_id: "arrow".to_string(),
+            active_modifiers: Vec::new(),
+            process_time_ms: t,
+            unix_time_ms: t as u64,
+        }
+    }
+
+    #[test]
+    fn test_session_index_from_file_name() {
+        assert_eq!(session_index("recording-0.mp4"), Some(0));
+        assert_eq!(session_index("recording-12-mouse-moves.jsonl"), Some(12));
+        assert_eq!(session_index("sync.json"), None);
+    }
+
+    #[test]
+    fn test_journals_become_event_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let recording_dir = dir.path().join("recording");
+        std::fs::create_dir_all(&recording_dir).unwrap();
+
+        // Two complete moves and one cut off mid-write
+        let moves_journal = recording_dir.join("recording-0-mouse-moves.jsonl");
+        let mut file = std::fs::File::create(&moves_journal).unwrap();
+        for event in [mouse_move(10.0, 0.0), mouse_move(20.0, 10.0)] {
+            serde_json::to_writer(&mut file, &event).unwrap();
+            file.write_all(b"\n").unwrap();
+        }
+        file.write_all(b"{\"x\":30.0,\"y\"").unwrap();
+        std::fs::write(recording_dir.join("recording-0-mouse-clicks.jsonl"), "").unwrap();
+
+        let report = recover_recording(dir.path()).unwrap();
+        assert_eq!(report.session_count, 1);
+        assert!(report.failed_files.is_empty());
+
+        let moves: Vec<MouseMove> = serde_json::from_slice(
+            &std::fs::read(recording_dir.join("recording-0-mouse-moves.json")).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(moves.len(), 2);
+        assert!(!moves_journal.exists());
+        assert!(recording_dir.join("recording-0-mouse-drags.json").exists());
+    }
+
+    #[test]
+    fn test_recovered_bundle_has_sessions_and_project() {
+        let dir = tempfile::tempdir().unwrap();
+        let recording_dir = dir.path().join("recording");
+        std::fs::create_dir_all(&recording_dir).unwrap();
+
+        // Only the input channel of a second display got to write anything
+        let mut journal = Vec::new();
+        for event in [mouse_move(10.0, 100.0), mouse_move(20.0, 1100.0)] {
+            serde_json::to_writer(&mut journal, &event).unwrap();
+            journal.push(b'\n');
+        }
+        let base = recording_dir.join("recording-0-display-2");
+        std::fs::write(
+            base.with_file_name("recording-0-display-2-mouse-moves.jsonl"),
+            journal,
+        )
+        .unwrap();
+        std::fs::write(
+            base.with_file_name("recording-0-display-2-mouse-clicks.jsonl"),
+            "",
+        )
+        .unwrap();
+
+        recover_recording(dir.path()).unwrap();
+        assert!(recording_dir
+            .join("recording-0-display-2-mouse-drags.json")
+            .exists());
+
+        let sessions: SessionsManifest = read_json(&recording_dir.join("sessions.json")).unwrap();
+        assert_eq!(sessions.sessions.len(), 1);
+        assert_eq!(sessions.sessions[0].session.duration_ms, 1000.0);
+        let channel = &sessions.sessions[0].channels[0];
+        assert_eq!(channel.channel_id, "input-display-2");
+        assert!(channel
+            .files
+            .contains(&"recording-0-display-2-mouse-drags.json".to_string()));
+
+        let project = crate::project::bundle::read_project(dir.path()).unwrap();
+        assert_eq!(project.scenes.len(), 1);
+    }
+
+    #[test]
+    fn test_channels_from_file_names() {
+        let channel = |name| file_channel(name).map(|(id, kind)| (id, kind.to_string()));
+        let expect = |id: &str, kind: &str| Some((id.to_string(), kind.to_string()));
+        assert_eq!(channel("recording-3.mp4"), expect("display", "display"));
+        assert_eq!(
+            channel("recording-3-display-1.mp4"),
+            expect("display-1", "display")
+        );
+        assert_eq!(
+            channel("recording-3-mic.flac"),
+            expect("microphone", "microphone")
+        );
+        assert_eq!(
+            channel("recording-3-display-1-cursors.json"),
+            expect("input-display-1", "input")
+        );
+        assert_eq!(channel("sync.json"), None);
+    }
+}
@@ -0,0 +1,216 @@
+//! Instant replay buffer
+//!
+//! In replay mode the coordinator records into a scratch bundle and starts a
+//! new session every few seconds, the way pause and resume would. Sessions
+//! that ended before the replay window are deleted, so the disk only holds
+//! the last `duration_secs` plus the segment being recorded.
+//!
+//! `save_replay` copies the kept sessions into a new bundle, numbered from 0
+//! and with their own `sync.json`, so a saved replay opens like a recording
+//! that was paused and resumed. The buffer keeps recording afterwards.
+
+use super::channel::{RecordingError, RecordingResult};
+use super::state::RecordingSession;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Settings of the replay buffer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReplayConfig {
+    /// How much recent footage to keep
+    pub duration_secs: u32,
+    /// Length of each buffered session; a saved replay starts on a segment
+    /// boundary, so it can be up to this much longer than `duration_secs`
+    pub segment_secs: u32,
+    /// Directory to create the buffer's scratch bundle in (defaults to the
+    /// temp dir); every buffer records into a new subdirectory of its own
+    pub buffer_dir: Option<String>,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self {
+            duration_secs: 30,
+            segment_secs: 5,
+            buffer_dir: None,
+        }
+    }
+}
+
+impl ReplayConfig {
+    pub fn validate(&self) -> RecordingResult<()> {
+        if self.duration_secs == 0 || self.segment_secs == 0 {
+            return Err(RecordingError::ConfigurationError(
+                "Replay duration and segment length must be at least one second".to_string(),
+            ));
+        }
+        if self.segment_secs > self.duration_secs {
+            return Err(RecordingError::ConfigurationError(format!(
+                "Replay segments ({}s) can't be longer than the replay ({}s)",
+                self.segment_secs, self.duration_secs
+            )));
+        }
+        Ok(())
+    }
+
+    pub fn window_ms(&self) -> f64 {
+        self.duration_secs as f64 * 1000.0
+    }
+
+    pub fn segment_duration(&self) -> Duration {
+        Duration::from_secs(self.segment_secs as u64)
+    }
+
+    /// Create a new scratch bundle for the buffer
+    ///
+    /// Only this directory is ever deleted, so a mistyped `buffer_dir` can't
+    /// lose anything and two buffers never share one. It is removed when the
+    /// returned `TempDir` is closed or dropped.
+    pub fn create_buffer(&self) -> std::io::Result<TempDir> {
+        let parent = self
+            .buffer_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+        std::fs::create_dir_all(&parent)?;
+        tempfile::Builder::new()
+            .prefix("open-screenstudio-replay-")
+            .tempdir_in(parent)
+    }
+}
+
+/// Ended sessions that lie entirely before the replay window
+pub fn expired_sessions(sessions: &[RecordingSession], now_ms: f64, window_ms: f64) -> Vec<usize> {
+    let window_start = now_ms - window_ms;
+    sessions
+        .iter()
+        .filter(|s| s.duration_ms > 0.0 && s.process_time_end_ms <= window_start)
+        .map(|s| s.index)
+        .collect()
+}
+
+/// Name of a session's file under another session index
+///
+/// Returns None if the file doesn't belong to `from_index`.
+pub fn renumbered(name: &str, from_index: usize, to_index: usize) -> Option<String> {
+    let rest = name.strip_prefix("recording-")?;
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    if rest[..digits].parse::<usize>().ok()? != from_index {
+        return None;
+    }
+    Some(format!("recording-{}{}", to_index, &rest[digits..]))
+}
+
+/// Files of a session in a recording directory
+fn session_files(recording_dir: &Path, index: usize) -> std::io::Result<Vec<(PathBuf, String)>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(recording_dir)? {
+        let path = entry?.path();
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if path.is_file() && renumbered(name, index, index).is_some() {
+            files.push((path.clone(), name.to_string()));
+        }
+    }
+    Ok(files)
+}
+
+/// Copy a session's files into another recording directory as `to_index`
+pub fn copy_session(
+    from_dir: &Path,
+    from_index: usize,
+    to_dir: &Path,
+    to_index: usize,
+) -> std::io::Result<Vec<String>> {
+    let mut copied = Vec::new();
+    for (path, name) in session_files(from_dir, from_index)? {
+        if let Some(new_name) = renumbered(&name, from_index, to_index) {
+            let target = to_dir.join(new_name);
+            std::fs::copy(&path, &target)?;
+            copied.push(target.to_string_lossy().to_string());
+        }
+    }
+    Ok(copied)
+}
+
+/// Delete a session's files from a recording directory
+pub fn remove_session(recording_dir: &Path, index: usize) -> std::io::Result<()> {
+    for (path, _) in session_files(recording_dir, index)? {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recorder::sessions::tests::session;
+
+    #[test]
+    fn test_expired_sessions() {
+        // The session being recorded hasn't ended yet
+        let current = RecordingSession::new(3, 15_000.0);
+        let sessions = vec![
+            session(0, 0.0, 5_000.0),
+            session(1, 5_000.0, 10_000.0),
+            session(2, 10_000.0, 15_000.0),
+            current,
+        ];
+
+        // 8s window at 16s: session 1 overlaps it and is kept
+        assert_eq!(expired_sessions(&sessions, 16_000.0, 8_000.0), vec![0]);
+        assert_eq!(
+            expired_sessions(&sessions, 16_000.0, 30_000.0),
+            Vec::<usize>::new()
+        );
+    }
+
+    #[test]
+    fn test_copy_session_renumbers_files() {
+        let from = tempfile::tempdir().unwrap();
+        let to = tempfile::tempdir().unwrap();
+        for name in [
+            "recording-1.mp4",
+            "recording-1-mic.flac",
+            "recording-1-mouse-moves.json",
+            "recording-12.mp4",
+            "sync.json",
+        ] {
+            std::fs::write(from.path().join(name), name).unwrap();
+        }
+
+        let copied = copy_session(from.path(), 1, to.path(), 0).unwrap();
+        assert_eq!(copied.len(), 3);
+        assert!(to.path().join("recording-0.mp4").exists());
+        assert!(to.path().join("recording-0-mic.flac").exists());
+        assert!(to.path().join("recording-0-mouse-moves.json").exists());
+
+        remove_session(from.path(), 1).unwrap();
+        assert!(!from.path().join("recording-1.mp4").exists());
+        assert!(from.path().join("recording-12.mp4").exists());
+    }
+
+    #[test]
+    fn test_buffer_never_deletes_the_configured_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "keep").unwrap();
+        let config = ReplayConfig {
+            buffer_dir: Some(dir.path().to_string_lossy().to_string()),
+            ..Default::default()
+        };
+
+        let first = config.create_buffer().unwrap();
+        let second = config.create_buffer().unwrap();
+        assert_ne!(first.path(), second.path());
+        assert_eq!(first.path().parent(), Some(dir.path()));
+
+        first.close().unwrap();
+        drop(second);
+        assert!(dir.path().join("notes.txt").exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
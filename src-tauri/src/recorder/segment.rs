@@ -0,0 +1,17 @@
+//! Crash-safe output settings
+//!
+//! Encoders write fragmented MP4: an empty `moov` goes out up front and
+//! samples follow in self-contained `moof` fragments. A file cut off by a
+//! crash or a killed process stays readable up to its last complete fragment,
+//! which `recover_recording` remuxes into a regular MP4.
+
+/// FFmpeg muxer arguments for fragmented MP4/M4A output
+///
+/// Video fragments start at each keyframe; audio-only files are cut about
+/// once a second.
+pub const FRAGMENTED_MP4_ARGS: [&str; 4] = [
+    "-movflags",
+    "+frag_keyframe+empty_moov+default_base_moof",
+    "-frag_duration",
+    "1000000",
+];
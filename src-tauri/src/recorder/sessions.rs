@@ -0,0 +1,204 @@
+//! Session manifest and the initial project of a recording
+//!
+//! At stop the coordinator writes `sessions.json`, listing each session's
+//! timings and the files every channel wrote during it, and a project with
+//! one scene per session (each a single full-length slice). With both in
+//! place a finished recording is a valid `.osp` bundle that opens in the
+//! editor straight away. The manifest also records why the recording ended.
+
+use super::recovery::session_index;
+use super::state::RecordingSession;
+use crate::project::schema::{Project, Scene};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Files a channel wrote, relative to the recording directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelFiles {
+    pub channel_id: String,
+    pub channel_type: String,
+    pub files: Vec<String>,
+}
+
+/// One session of `sessions.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRecord {
+    #[serde(flatten)]
+    pub session: RecordingSession,
+    /// Channels that wrote files in this session
+    pub channels: Vec<ChannelFiles>,
+}
+
+/// Why a recording ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StopReason {
+    /// Stopped by the user (or the app)
+    #[default]
+    Requested,
+    /// Stopped by the disk space guard before the volume filled up
+    LowDiskSpace,
+}
+
+/// Contents of `sessions.json`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionsManifest {
+    pub sessions: Vec<SessionRecord>,
+    #[serde(default)]
+    pub stop_reason: StopReason,
+}
+
+impl SessionsManifest {
+    /// Assign each channel's files to the session in their name
+    ///
+    /// `channels` are the channels' full output file lists. Files that no
+    /// longer exist (dropped replay segments) are left out.
+    pub fn build(
+        sessions: &[RecordingSession],
+        channels: &[ChannelFiles],
+        recording_dir: &Path,
+    ) -> Self {
+        let sessions = sessions
+            .iter()
+            .map(|session| SessionRecord {
+                session: session.clone(),
+                channels: channels
+                    .iter()
+                    .filter_map(|channel| {
+                        let files: Vec<String> = channel
+                            .files
+                            .iter()
+                            .filter_map(|file| relative_name(file, recording_dir))
+                            .filter(|name| session_index(name) == Some(session.index))
+                            .filter(|name| recording_dir.join(name).exists())
+                            .collect();
+                        (!files.is_empty()).then(|| ChannelFiles {
+                            files,
+                            ..channel.clone()
+                        })
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self {
+            sessions,
+            stop_reason: StopReason::default(),
+        }
+    }
+
+    /// Total duration of all sessions
+    pub fn duration_ms(&self) -> f64 {
+        self.sessions.iter().map(|s| s.session.duration_ms).sum()
+    }
+
+    /// Project with one full-length scene per session
+    pub fn to_project(&self, name: &str) -> Project {
+        let mut project = Project::new(name.to_string());
+        project.scenes = self
+            .sessions
+            .iter()
+            .map(|s| Scene::recording(s.session.index, s.session.duration_ms))
+            .collect();
+        project.config.recording_range = (0.0, self.duration_ms());
+        project
+    }
+
+    /// Write the manifest as `sessions.json` in the recording directory
+    pub fn write(&self, recording_dir: &Path) -> std::io::Result<String> {
+        let path = recording_dir.join("sessions.json");
+        let data = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(&path, data)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+}
+
+/// Path of an output file relative to the recording directory
+fn relative_name(file: &str, recording_dir: &Path) -> Option<String> {
+    let path = Path::new(file);
+    let relative = path.strip_prefix(recording_dir).unwrap_or(path);
+    relative.to_str().map(|s| s.replace('\\', "/"))
+}
+
+/// Project name for a bundle directory (`Demo.osp` is "Demo")
+pub fn project_name(bundle_dir: &Path) -> String {
+    bundle_dir
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or("Untitled Recording")
+        .to_string()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A session that ran between the given process times
+    pub(crate) fn session(index: usize, start_ms: f64, end_ms: f64) -> RecordingSession {
+        let mut session = RecordingSession::new(index, start_ms);
+        session.end(end_ms);
+        session
+    }
+
+    #[test]
+    fn test_files_are_grouped_by_session() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in [
+            "recording-0.mp4",
+            "recording-1.mp4",
+            "recording-1-frames.json",
+        ] {
+            std::fs::write(dir.path().join(name), "").unwrap();
+        }
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        let display = ChannelFiles {
+            channel_id: "display-0".to_string(),
+            channel_type: "display".to_string(),
+            files: vec![
+                path("recording-0.mp4"),
+                path("recording-1.mp4"),
+                path("recording-1-frames.json"),
+                // Written once but since deleted
+                path("recording-2.mp4"),
+            ],
+        };
+
+        let sessions = [session(0, 0.0, 1000.0), session(1, 1500.0, 2000.0)];
+        let manifest = SessionsManifest::build(&sessions, &[display], dir.path());
+
+        assert_eq!(
+            manifest.sessions[0].channels[0].files,
+            vec!["recording-0.mp4"]
+        );
+        assert_eq!(
+            manifest.sessions[1].channels[0].files,
+            vec!["recording-1.mp4", "recording-1-frames.json"]
+        );
+        assert_eq!(manifest.duration_ms(), 1500.0);
+    }
+
+    #[test]
+    fn test_project_has_a_scene_per_session() {
+        let manifest = SessionsManifest {
+            sessions: [session(0, 0.0, 1000.0), session(1, 1500.0, 2000.0)]
+                .into_iter()
+                .map(|session| SessionRecord {
+                    session,
+                    channels: Vec::new(),
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let project = manifest.to_project("Demo");
+        assert_eq!(project.name, "Demo");
+        assert_eq!(project.scenes.len(), 2);
+        assert_eq!(project.scenes[1].session_index, 1);
+        assert_eq!(project.scenes[1].slices[0].source_end_ms, 500.0);
+        assert_eq!(project.config.recording_range, (0.0, 1500.0));
+        assert_eq!(project_name(Path::new("/tmp/Demo.osp")), "Demo");
+    }
+}
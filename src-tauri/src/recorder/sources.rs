@@ -0,0 +1,110 @@
+//! Video sources of a recording
+//!
+//! A recording can capture several displays at once. The main capture target
+//! writes `recording-N.mp4` as before; each additional display writes
+//! `recording-N-display-<id>.mp4`, its own frames sidecar and, when input is
+//! tracked, its own input event files in that display's coordinates. The
+//! coordinator lists the sources in `sources.json` so the editor knows which
+//! files belong together.
+
+use crate::capture::traits::Resolution;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Start of the file names of a session, e.g. `recording-0` or
+/// `recording-0-display-1`
+pub fn file_basename(session_index: usize, file_suffix: Option<&str>) -> String {
+    match file_suffix {
+        Some(suffix) => format!("recording-{}-{}", session_index, suffix),
+        None => format!("recording-{}", session_index),
+    }
+}
+
+/// File suffix of an additional display
+pub fn display_file_suffix(display_id: u32) -> String {
+    format!("display-{}", display_id)
+}
+
+/// A recorded video and the files that share its coordinate space
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoSource {
+    /// Channel that recorded the video
+    pub channel_id: String,
+    /// Display the video shows, None for sources without one (synthetic)
+    pub display_id: Option<u32>,
+    /// Appended to `recording-N` in the video, frames and input file names;
+    /// None for the main video
+    pub file_suffix: Option<String>,
+    /// Size of the captured frames, which input events are relative to;
+    /// the video is smaller when it was scaled down
+    #[serde(default)]
+    pub capture_size: Option<Resolution>,
+}
+
+impl VideoSource {
+    /// Whether this is the main video (`recording-N.mp4`)
+    pub fn is_primary(&self) -> bool {
+        self.file_suffix.is_none()
+    }
+
+    /// Start of this source's file names in a session
+    pub fn basename(&self, session_index: usize) -> String {
+        file_basename(session_index, self.file_suffix.as_deref())
+    }
+}
+
+/// Contents of `sources.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcesManifest {
+    pub sources: Vec<VideoSource>,
+}
+
+impl SourcesManifest {
+    /// Write the manifest as `sources.json` in the recording directory
+    pub fn write(&self, recording_dir: &Path) -> std::io::Result<String> {
+        let path = recording_dir.join("sources.json");
+        let data = serde_json::to_vec_pretty(self).map_err(std::io::Error::other)?;
+        std::fs::write(&path, data)?;
+        Ok(path.to_string_lossy().to_string())
+    }
+
+    /// Read `sources.json`, None for recordings made before it existed
+    pub fn read(recording_dir: &Path) -> std::io::Result<Option<Self>> {
+        let path = recording_dir.join("sources.json");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let data = std::fs::read(path)?;
+        serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(std::io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_basenames() {
+        let main = VideoSource {
+            channel_id: "display-0".to_string(),
+            display_id: Some(0),
+            file_suffix: None,
+            capture_size: None,
+        };
+        let second = VideoSource {
+            channel_id: "display-1".to_string(),
+            display_id: Some(1),
+            file_suffix: Some(display_file_suffix(1)),
+            capture_size: None,
+        };
+
+        assert!(main.is_primary());
+        assert_eq!(main.basename(2), "recording-2");
+        assert!(!second.is_primary());
+        assert_eq!(second.basename(0), "recording-0-display-1");
+    }
+}
@@ -2,6 +2,14 @@
 //!
 //! Defines the recording state machine and session tracking.
 
+use crate::capture::audio::AudioFormat;
+use crate::capture::audio_devices::MicrophoneFallback;
+use crate::capture::input::KeystrokeMode;
+use crate::capture::synthetic::TestSourceConfig;
+use crate::capture::traits::CaptureTarget;
+use crate::capture::video::VideoSettings;
+use super::channel::{ChannelOperation, RecordingError};
+use super::disk_space::DiskSpaceSettings;
 use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
@@ -79,15 +87,43 @@ pub struct RecordingConfig {
     /// Display ID to capture
     pub display_id: u32,
     
+    /// Other displays to record at the same time, each to its own files
+    #[serde(default)]
+    pub additional_display_ids: Vec<u32>,
+    
+    /// Window or region to capture instead of the whole display
+    #[serde(default)]
+    pub target: Option<CaptureTarget>,
+    
     /// Whether to capture system audio
     pub capture_system_audio: bool,
     
+    /// System audio output device to capture (Linux: sink name, defaults to the default sink)
+    #[serde(default)]
+    pub system_audio_device_id: Option<String>,
+    
     /// Whether to capture microphone
     pub capture_microphone: bool,
     
     /// Microphone device ID (if capturing)
     pub microphone_device_id: Option<String>,
     
+    /// What to record if the microphone is unplugged (defaults to silence)
+    #[serde(default)]
+    pub microphone_fallback: MicrophoneFallback,
+    
+    /// Microphone track format (defaults to lossless FLAC)
+    #[serde(default = "AudioFormat::lossless")]
+    pub microphone_format: AudioFormat,
+    
+    /// System audio track format (defaults to AAC)
+    #[serde(default)]
+    pub system_audio_format: AudioFormat,
+    
+    /// Frame rate, scale and encoding of display recordings
+    #[serde(default)]
+    pub video: VideoSettings,
+    
     /// Whether to capture webcam
     pub capture_webcam: bool,
     
@@ -97,8 +133,47 @@ pub struct RecordingConfig {
     /// Whether to track mouse/keyboard input
     pub track_input: bool,
     
+    /// Which keystrokes to record when tracking input (defaults to privacy mode)
+    #[serde(default)]
+    pub keystroke_mode: KeystrokeMode,
+    
     /// Output directory for the recording
     pub output_dir: String,
+    
+    /// Free space to keep on the output volume and when to warn about it
+    #[serde(default)]
+    pub disk_space: DiskSpaceSettings,
+    
+    /// Record synthetic test sources instead of real devices (headless CI, demos)
+    #[serde(default)]
+    pub test_sources: Option<TestSourceConfig>,
+}
+
+impl RecordingConfig {
+    /// What to capture, defaulting to the whole display
+    pub fn capture_target(&self) -> CaptureTarget {
+        self.target.clone().unwrap_or(CaptureTarget::Display {
+            display_id: self.display_id,
+        })
+    }
+
+    /// Additional displays to record besides `primary`, without duplicates
+    pub fn additional_displays(&self, primary: u32) -> Vec<u32> {
+        let mut displays: Vec<u32> = Vec::new();
+        for &display_id in &self.additional_display_ids {
+            if display_id != primary && !displays.contains(&display_id) {
+                displays.push(display_id);
+            }
+        }
+        displays
+    }
+
+    /// Check settings that would otherwise only fail once encoders start
+    pub fn validate(&self) -> Result<(), RecordingError> {
+        self.video.validate()?;
+        self.microphone_format.validate()?;
+        self.system_audio_format.validate()
+    }
 }
 
 /// Result of a completed recording
@@ -116,4 +191,27 @@ pub struct RecordingResult {
     
     /// List of output files created
     pub output_files: Vec<String>,
+    
+    /// Channels that failed while stopping (their files may be incomplete)
+    #[serde(default)]
+    pub failed_channels: Vec<ChannelFailure>,
+}
+
+/// A channel operation that failed without aborting the recording
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelFailure {
+    pub channel_id: String,
+    pub operation: ChannelOperation,
+    pub message: String,
+}
+
+impl ChannelFailure {
+    pub fn new(channel_id: &str, operation: ChannelOperation, error: &RecordingError) -> Self {
+        Self {
+            channel_id: channel_id.to_string(),
+            operation,
+            message: error.to_string(),
+        }
+    }
 }
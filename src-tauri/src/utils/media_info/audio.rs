@@ -0,0 +1,110 @@
+//! FLAC, WAV and CAF headers
+//!
+//! FFmpeg writes these with placeholder sizes and fills them in once the
+//! encoder is closed: the total sample count in FLAC's `STREAMINFO`, the
+//! `RIFF` size of a WAV file, the `data` chunk size of a CAF file. A file
+//! still holding the placeholder was never closed.
+
+use std::io::{Read, Seek, SeekFrom};
+
+/// Whether the file is a FLAC, WAV or CAF file that was closed properly
+pub(super) fn is_complete<R: Read + Seek>(reader: &mut R) -> std::io::Result<bool> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut header = [0u8; 26];
+    let read = super::read_up_to(reader, &mut header)?;
+    let header = &header[..read];
+
+    if header.starts_with(b"fLaC") && read == 26 {
+        // STREAMINFO comes first; 36 bits of total samples from its 14th byte
+        let streaminfo = &header[8..];
+        let mut bytes = [0u8; 8];
+        bytes[3..].copy_from_slice(&streaminfo[13..18]);
+        let total_samples = u64::from_be_bytes(bytes) & 0x0F_FFFF_FFFF;
+        Ok(total_samples > 0)
+    } else if header.starts_with(b"RIFF") && header.get(8..12) == Some(b"WAVE") {
+        let riff_size = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        Ok(riff_size as u64 + 8 == file_len)
+    } else if header.starts_with(b"caff") {
+        caf_data_is_sized(reader, file_len)
+    } else {
+        Ok(false)
+    }
+}
+
+/// Whether the `data` chunk of a CAF file has its size, and all of it is there
+fn caf_data_is_sized<R: Read + Seek>(reader: &mut R, file_len: u64) -> std::io::Result<bool> {
+    // Chunks follow the 8-byte file header
+    let mut pos = 8;
+    while pos + 12 <= file_len {
+        reader.seek(SeekFrom::Start(pos))?;
+        let mut chunk = [0u8; 12];
+        reader.read_exact(&mut chunk)?;
+        let size = i64::from_be_bytes(chunk[4..12].try_into().unwrap());
+        if &chunk[..4] == b"data" {
+            return Ok(size >= 0 && pos + 12 + size as u64 <= file_len);
+        }
+        let Some(end) = u64::try_from(size)
+            .ok()
+            .and_then(|size| (pos + 12).checked_add(size))
+        else {
+            return Ok(false);
+        };
+        pos = end;
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn wav(riff_size: u32, data_len: usize) -> Vec<u8> {
+        let mut data = b"RIFF".to_vec();
+        data.extend(riff_size.to_le_bytes());
+        data.extend(b"WAVEfmt ");
+        data.resize(data_len, 0);
+        data
+    }
+
+    fn caf(data_size: i64) -> Vec<u8> {
+        let mut data = b"caff\x00\x01\x00\x00".to_vec();
+        data.extend(b"desc");
+        data.extend(32i64.to_be_bytes());
+        data.extend([0u8; 32]);
+        data.extend(b"data");
+        data.extend(data_size.to_be_bytes());
+        data.extend([0u8; 16]);
+        data
+    }
+
+    fn flac(total_samples: u64) -> Vec<u8> {
+        let mut data = b"fLaC\x80\x00\x00\x22".to_vec();
+        data.extend([0u8; 13]);
+        data.push((total_samples >> 32) as u8 & 0x0F);
+        data.extend((total_samples as u32).to_be_bytes());
+        data.resize(42, 0);
+        data
+    }
+
+    fn complete(data: &[u8]) -> bool {
+        is_complete(&mut Cursor::new(data)).unwrap()
+    }
+
+    #[test]
+    fn test_closed_files_are_complete() {
+        assert!(complete(&wav(92, 100)));
+        assert!(complete(&caf(16)));
+        assert!(complete(&flac(48_000)));
+    }
+
+    #[test]
+    fn test_placeholder_sizes_are_incomplete() {
+        assert!(!complete(&wav(u32::MAX, 100)));
+        assert!(!complete(&caf(-1)));
+        assert!(!complete(&flac(0)));
+        assert!(!complete(b"not audio"));
+    }
+}
@@ -0,0 +1,362 @@
+//! Matroska and WebM elements
+//!
+//! A file written live has a Segment and Clusters of unknown size, so the
+//! top level is scanned element by element, stepping into Segments and
+//! Clusters instead of skipping them. Info and Tracks are read whole; of a
+//! block only the header (track, timestamp, keyframe flag) is read.
+
+use super::{invalid_data, read_body, Parsed, Track, TrackKind};
+use std::io::{Read, Seek, SeekFrom};
+
+/// ID of the EBML header, the first bytes of every Matroska file
+pub(super) const EBML_MAGIC: [u8; 4] = [0x1A, 0x45, 0xDF, 0xA3];
+
+const EBML_HEADER: u32 = 0x1A45_DFA3;
+const DOC_TYPE: u32 = 0x4282;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const TRACKS: u32 = 0x1654_AE6B;
+const TRACK_ENTRY: u32 = 0xAE;
+const TRACK_NUMBER: u32 = 0xD7;
+const TRACK_TYPE: u32 = 0x83;
+const CODEC_ID: u32 = 0x86;
+const DEFAULT_DURATION: u32 = 0x23_E383;
+const VIDEO: u32 = 0xE0;
+const PIXEL_WIDTH: u32 = 0xB0;
+const PIXEL_HEIGHT: u32 = 0xBA;
+const AUDIO: u32 = 0xE1;
+const SAMPLING_FREQUENCY: u32 = 0xB5;
+const CHANNELS: u32 = 0x9F;
+const CLUSTER: u32 = 0x1F43_B675;
+const TIMESTAMP: u32 = 0xE7;
+const SIMPLE_BLOCK: u32 = 0xA3;
+const BLOCK_GROUP: u32 = 0xA0;
+const BLOCK: u32 = 0xA1;
+const REFERENCE_BLOCK: u32 = 0xFB;
+
+/// Keyframe bit in the flags of a SimpleBlock
+const KEYFRAME_FLAG: u8 = 0x80;
+
+/// Nanoseconds per timestamp tick unless Info says otherwise
+const DEFAULT_TIMESTAMP_SCALE: u64 = 1_000_000;
+
+/// Block timestamps of a track, in ticks, and its frame duration
+#[derive(Debug, Default)]
+struct BlockTimes {
+    first: Option<i64>,
+    last: i64,
+    default_duration_ns: u64,
+}
+
+pub(super) fn read<R: Read + Seek>(reader: &mut R) -> std::io::Result<Parsed> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut container = "matroska";
+    let mut timestamp_scale = DEFAULT_TIMESTAMP_SCALE;
+    let mut duration_ticks = None;
+    let mut tracks: Vec<Track> = Vec::new();
+    let mut times: Vec<BlockTimes> = Vec::new();
+    let mut cluster_timestamp = 0i64;
+
+    let mut pos = 0;
+    while pos < file_len {
+        let Some((id, size)) = read_element_header(reader)? else {
+            break;
+        };
+        let body_start = reader.stream_position()?;
+
+        // Segments and Clusters are stepped into, their children read in turn
+        if id == SEGMENT || id == CLUSTER {
+            pos = body_start;
+            continue;
+        }
+        let Some(size) = size else {
+            return Err(invalid_data("Matroska element of unknown size"));
+        };
+        let end = body_start + size;
+        if end > file_len {
+            // Cut short; anything from here on is incomplete
+            break;
+        }
+
+        match id {
+            EBML_HEADER | INFO | TRACKS | TIMESTAMP | BLOCK_GROUP => {
+                let Some(body) = read_body(reader, size)? else {
+                    break;
+                };
+                match id {
+                    EBML_HEADER => {
+                        if let Some(doc_type) = find(&body, DOC_TYPE) {
+                            if doc_type == b"webm" {
+                                container = "webm";
+                            }
+                        }
+                    }
+                    INFO => {
+                        for (child_id, child) in elements(&body) {
+                            match child_id {
+                                TIMESTAMP_SCALE => timestamp_scale = read_uint(child),
+                                DURATION => duration_ticks = read_float(child),
+                                _ => {}
+                            }
+                        }
+                    }
+                    TRACKS => {
+                        for (_, entry) in elements(&body).filter(|(i, _)| *i == TRACK_ENTRY) {
+                            let (track, default_duration_ns) = read_track_entry(entry);
+                            tracks.push(track);
+                            times.push(BlockTimes {
+                                default_duration_ns,
+                                ..Default::default()
+                            });
+                        }
+                    }
+                    TIMESTAMP => cluster_timestamp = read_uint(&body) as i64,
+                    _ => {
+                        // A block that other blocks depend on has no references
+                        let keyframe = find(&body, REFERENCE_BLOCK).is_none();
+                        if let Some(block) = find(&body, BLOCK) {
+                            let (number, timestamp, _) = parse_block_header(block)?;
+                            let timestamp = cluster_timestamp + timestamp as i64;
+                            count_block(&mut tracks, &mut times, number, timestamp, keyframe);
+                        }
+                    }
+                }
+            }
+            SIMPLE_BLOCK => {
+                let mut header = [0u8; 12];
+                let len = (size as usize).min(header.len());
+                reader.read_exact(&mut header[..len])?;
+                let (number, timestamp, flags) = parse_block_header(&header[..len])?;
+                let timestamp = cluster_timestamp + timestamp as i64;
+                let keyframe = flags & KEYFRAME_FLAG != 0;
+                count_block(&mut tracks, &mut times, number, timestamp, keyframe);
+            }
+            _ => {}
+        }
+
+        pos = end;
+        reader.seek(SeekFrom::Start(pos))?;
+    }
+
+    if timestamp_scale == 0 {
+        return Err(invalid_data("Invalid Matroska timestamp scale"));
+    }
+    let timescale = (1_000_000_000 / timestamp_scale) as u32;
+    for (track, times) in tracks.iter_mut().zip(&times) {
+        track.timescale = timescale;
+        if let Some(first) = times.first {
+            // The last block lasts one frame, where the frame duration is known
+            let last_duration = (times.default_duration_ns as f64 / timestamp_scale as f64).round();
+            track.sample_duration = (times.last - first).max(0) as u64 + last_duration as u64;
+        }
+    }
+    let duration_ms = duration_ticks.map(|ticks| ticks * timestamp_scale as f64 / 1_000_000.0);
+
+    Ok(Parsed {
+        container,
+        fragmented: false,
+        tracks,
+        duration_ms,
+        truncated: pos < file_len,
+    })
+}
+
+/// Count a block towards its track, `timestamp` being in ticks
+fn count_block(
+    tracks: &mut [Track],
+    times: &mut [BlockTimes],
+    number: u64,
+    timestamp: i64,
+    keyframe: bool,
+) {
+    let Some(index) = tracks.iter().position(|t| t.id == number) else {
+        return;
+    };
+    let track = &mut tracks[index];
+    track.sample_count += 1;
+    if keyframe {
+        track.keyframe_count += 1;
+    }
+
+    let times = &mut times[index];
+    times.first = Some(times.first.map_or(timestamp, |first| first.min(timestamp)));
+    times.last = times.last.max(timestamp);
+}
+
+/// Track number, timestamp relative to the cluster and flags of a block
+fn parse_block_header(data: &[u8]) -> std::io::Result<(u64, i16, u8)> {
+    let (number, len) =
+        read_vint(data, true).ok_or_else(|| invalid_data("Invalid Matroska block"))?;
+    match data.get(len..len + 3) {
+        Some(rest) => Ok((number, i16::from_be_bytes([rest[0], rest[1]]), rest[2])),
+        None => Err(invalid_data("Matroska block is too short")),
+    }
+}
+
+/// A track and its default frame duration in nanoseconds
+fn read_track_entry(body: &[u8]) -> (Track, u64) {
+    let mut track = Track::default();
+    let mut default_duration_ns = 0;
+    for (id, child) in elements(body) {
+        match id {
+            TRACK_NUMBER => track.id = read_uint(child),
+            TRACK_TYPE => {
+                track.kind = match read_uint(child) {
+                    1 => TrackKind::Video,
+                    2 => TrackKind::Audio,
+                    _ => TrackKind::Other,
+                }
+            }
+            CODEC_ID => track.codec = codec_name(&String::from_utf8_lossy(child)),
+            DEFAULT_DURATION => default_duration_ns = read_uint(child),
+            VIDEO => {
+                for (id, setting) in elements(child) {
+                    match id {
+                        PIXEL_WIDTH => track.width = read_uint(setting) as u32,
+                        PIXEL_HEIGHT => track.height = read_uint(setting) as u32,
+                        _ => {}
+                    }
+                }
+            }
+            AUDIO => {
+                for (id, setting) in elements(child) {
+                    match id {
+                        SAMPLING_FREQUENCY => {
+                            track.sample_rate = read_float(setting).unwrap_or(0.0) as u32
+                        }
+                        CHANNELS => track.channels = read_uint(setting) as u16,
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    (track, default_duration_ns)
+}
+
+/// FFmpeg's name for a Matroska codec ID
+fn codec_name(codec_id: &str) -> String {
+    match codec_id.trim_end_matches('\0') {
+        "V_MPEG4/ISO/AVC" => "h264",
+        "V_MPEGH/ISO/HEVC" => "hevc",
+        "V_VP8" => "vp8",
+        "V_VP9" => "vp9",
+        "V_AV1" => "av1",
+        "V_FFV1" => "ffv1",
+        "A_AAC" => "aac",
+        "A_OPUS" => "opus",
+        "A_FLAC" => "flac",
+        "A_VORBIS" => "vorbis",
+        "A_PCM/FLOAT/IEEE" => "pcm_f32le",
+        "A_PCM/INT/LIT" => "pcm_s16le",
+        other => return other.to_lowercase(),
+    }
+    .to_string()
+}
+
+/// ID and size of the element at the reader's position, None at the end of
+/// the file. The size is None when unknown.
+fn read_element_header<R: Read>(reader: &mut R) -> std::io::Result<Option<(u32, Option<u64>)>> {
+    let Some(Some(id)) = read_reader_vint(reader, false)? else {
+        return Ok(None);
+    };
+    let Some(size) = read_reader_vint(reader, true)? else {
+        return Ok(None);
+    };
+    Ok(Some((id as u32, size)))
+}
+
+/// A variable-length integer from a reader, None at the end of the file.
+/// The inner option is None for the reserved "unknown" value.
+fn read_reader_vint<R: Read>(
+    reader: &mut R,
+    strip_marker: bool,
+) -> std::io::Result<Option<Option<u64>>> {
+    let mut bytes = [0u8; 8];
+    if reader.read(&mut bytes[..1])? == 0 {
+        return Ok(None);
+    }
+    let len = bytes[0].leading_zeros() as usize + 1;
+    if len > 8 {
+        return Err(invalid_data("Invalid Matroska element"));
+    }
+    if let Err(e) = reader.read_exact(&mut bytes[1..len]) {
+        return match e.kind() {
+            std::io::ErrorKind::UnexpectedEof => Ok(None),
+            _ => Err(e),
+        };
+    }
+    let (value, _) = read_vint(&bytes[..len], strip_marker).unwrap_or_default();
+    let all_ones = (1u64 << (7 * len)) - 1;
+    Ok(Some((!strip_marker || value != all_ones).then_some(value)))
+}
+
+/// A variable-length integer and its length in bytes
+///
+/// Element IDs keep their length marker, sizes and track numbers don't.
+fn read_vint(data: &[u8], strip_marker: bool) -> Option<(u64, usize)> {
+    let first = *data.first()?;
+    let len = first.leading_zeros() as usize + 1;
+    if len > 8 || data.len() < len {
+        return None;
+    }
+    let mut value = if strip_marker {
+        (first as u64) & (0xFF >> len)
+    } else {
+        first as u64
+    };
+    for byte in &data[1..len] {
+        value = (value << 8) | *byte as u64;
+    }
+    Some((value, len))
+}
+
+/// Iterator over the child elements in an element body
+struct Elements<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Elements<'a> {
+    type Item = (u32, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (id, id_len) = read_vint(self.data, false)?;
+        let (size, size_len) = read_vint(&self.data[id_len..], true)?;
+        let start = id_len + size_len;
+        let end = start.checked_add(usize::try_from(size).ok()?)?;
+        if end > self.data.len() {
+            self.data = &[];
+            return None;
+        }
+        let body = &self.data[start..end];
+        self.data = &self.data[end..];
+        Some((id as u32, body))
+    }
+}
+
+fn elements(data: &[u8]) -> Elements<'_> {
+    Elements { data }
+}
+
+/// Body of the first child element with an ID
+fn find(data: &[u8], id: u32) -> Option<&[u8]> {
+    elements(data).find(|(i, _)| *i == id).map(|(_, body)| body)
+}
+
+fn read_uint(data: &[u8]) -> u64 {
+    data.iter()
+        .fold(0, |value, byte| (value << 8) | *byte as u64)
+}
+
+fn read_float(data: &[u8]) -> Option<f64> {
+    match data.len() {
+        4 => Some(f32::from_be_bytes(data.try_into().ok()?) as f64),
+        8 => Some(f64::from_be_bytes(data.try_into().ok()?)),
+        _ => None,
+    }
+}
@@ -0,0 +1,339 @@
+//! Video file metadata
+//!
+//! Reads dimensions, frame rate, duration, codecs and frame counts straight
+//! from the container, so the editor doesn't depend on FFprobe being
+//! installed. MP4/MOV files (including the fragmented MP4 written while
+//! recording) are read by `mp4`, Matroska/WebM by `matroska`. Neither decodes
+//! any media: sample tables, fragment headers and block headers hold
+//! everything needed, and the sample data is skipped.
+//!
+//! A file cut short by a crash is read up to its last complete element.
+//! `is_complete` tells such files apart from ones that were closed properly,
+//! including the FLAC, WAV and CAF files audio channels write.
+
+mod audio;
+mod matroska;
+mod mp4;
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Metadata of a recorded video
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VideoMetadata {
+    pub width: u32,
+    pub height: u32,
+    /// Average frame rate
+    pub fps: f64,
+    pub duration_ms: f64,
+    /// Codec name as FFmpeg reports it, e.g. "h264", "hevc" or "vp9"
+    pub codec: String,
+    /// "mp4", "mov", "matroska" or "webm"
+    pub container: String,
+    /// Ticks per second of the video track's timestamps
+    pub timescale: u32,
+    pub frame_count: u64,
+    pub keyframe_count: u64,
+    /// Written as fragments, as recordings are until they're remuxed
+    pub fragmented: bool,
+    pub audio_tracks: Vec<AudioTrackInfo>,
+}
+
+/// An audio track of a video file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioTrackInfo {
+    pub codec: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration_ms: f64,
+}
+
+/// Read the metadata of an MP4, MOV, MKV or WebM file
+pub fn read_video_metadata(path: &Path) -> std::io::Result<VideoMetadata> {
+    let mut reader = BufReader::new(File::open(path)?);
+    read_metadata(&mut reader)
+}
+
+/// Read metadata from a reader, detecting the container from its first bytes
+pub fn read_metadata<R: Read + Seek>(reader: &mut R) -> std::io::Result<VideoMetadata> {
+    match parse(reader)? {
+        Some(parsed) => build_metadata(&parsed),
+        None => Err(invalid_data("Not an MP4, MOV, MKV or WebM file")),
+    }
+}
+
+/// Whether a media file was closed properly rather than cut off
+///
+/// MP4 and Matroska files are complete when they end on a box or element
+/// boundary; FLAC, WAV and CAF files when their header has the sizes written
+/// at close. Files in any other format never count as complete.
+pub fn is_complete(path: &Path) -> std::io::Result<bool> {
+    let mut reader = BufReader::new(File::open(path)?);
+    if let Some(parsed) = parse(&mut reader)? {
+        return Ok(!parsed.truncated);
+    }
+    reader.seek(SeekFrom::Start(0))?;
+    audio::is_complete(&mut reader)
+}
+
+/// Parse an MP4 or Matroska file, None for any other format
+fn parse<R: Read + Seek>(reader: &mut R) -> std::io::Result<Option<Parsed>> {
+    let mut magic = [0u8; 8];
+    let read = read_up_to(reader, &mut magic)?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    if read >= 4 && magic[..4] == matroska::EBML_MAGIC {
+        matroska::read(reader).map(Some)
+    } else if read == 8 && mp4::is_box_type(&magic[4..8]) {
+        mp4::read(reader).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// What a container parser found
+#[derive(Debug)]
+struct Parsed {
+    container: &'static str,
+    fragmented: bool,
+    tracks: Vec<Track>,
+    /// Duration from the container, used when the video track has none
+    duration_ms: Option<f64>,
+    /// The file ends partway through a box or element
+    truncated: bool,
+}
+
+/// What kind of media a track holds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum TrackKind {
+    Video,
+    Audio,
+    #[default]
+    Other,
+}
+
+/// A track as read by a container parser
+#[derive(Debug, Default)]
+struct Track {
+    id: u64,
+    kind: TrackKind,
+    codec: String,
+    width: u32,
+    height: u32,
+    sample_rate: u32,
+    channels: u16,
+    /// Ticks per second of the track's timestamps
+    timescale: u32,
+    /// Duration from the track header, in ticks
+    header_duration: u64,
+    /// Samples (frames for video) found in sample tables or fragments
+    sample_count: u64,
+    keyframe_count: u64,
+    /// Total duration of the counted samples, in ticks
+    sample_duration: u64,
+}
+
+impl Track {
+    fn duration_ms(&self) -> f64 {
+        if self.timescale == 0 {
+            return 0.0;
+        }
+        let ticks = if self.sample_duration > 0 {
+            self.sample_duration
+        } else {
+            self.header_duration
+        };
+        ticks as f64 * 1000.0 / self.timescale as f64
+    }
+
+    fn fps(&self) -> f64 {
+        if self.sample_duration == 0 {
+            return 0.0;
+        }
+        self.sample_count as f64 * self.timescale as f64 / self.sample_duration as f64
+    }
+}
+
+/// Metadata of the first video track and every audio track
+fn build_metadata(parsed: &Parsed) -> std::io::Result<VideoMetadata> {
+    let tracks = &parsed.tracks;
+    let video = tracks
+        .iter()
+        .find(|t| t.kind == TrackKind::Video)
+        .ok_or_else(|| invalid_data("No video track found"))?;
+
+    let duration_ms = match video.duration_ms() {
+        ms if ms > 0.0 => ms,
+        _ => parsed.duration_ms.unwrap_or(0.0),
+    };
+    let fps = match video.fps() {
+        fps if fps > 0.0 => fps,
+        _ if duration_ms > 0.0 => video.sample_count as f64 * 1000.0 / duration_ms,
+        _ => 0.0,
+    };
+
+    Ok(VideoMetadata {
+        width: video.width,
+        height: video.height,
+        fps,
+        duration_ms,
+        codec: video.codec.clone(),
+        container: parsed.container.to_string(),
+        timescale: video.timescale,
+        frame_count: video.sample_count,
+        keyframe_count: video.keyframe_count,
+        fragmented: parsed.fragmented,
+        audio_tracks: tracks
+            .iter()
+            .filter(|t| t.kind == TrackKind::Audio)
+            .map(|t| AudioTrackInfo {
+                codec: t.codec.clone(),
+                sample_rate: t.sample_rate,
+                channels: t.channels,
+                duration_ms: t.duration_ms(),
+            })
+            .collect(),
+    })
+}
+
+fn invalid_data(message: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message.to_string())
+}
+
+/// Fill as much of `buf` as the reader has left
+fn read_up_to<R: Read>(reader: &mut R, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..])? {
+            0 => break,
+            n => filled += n,
+        }
+    }
+    Ok(filled)
+}
+
+/// Read exactly `len` bytes, None if the file ends first
+fn read_body<R: Read>(reader: &mut R, len: u64) -> std::io::Result<Option<Vec<u8>>> {
+    let mut body = Vec::new();
+    reader.take(len).read_to_end(&mut body)?;
+    Ok((body.len() as u64 == len).then_some(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const FRAGMENTED_MP4: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/fragmented-h264-aac.mp4"
+    ));
+    const PROGRESSIVE_MOV: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/progressive-hevc.mov"
+    ));
+    const WEBM: &[u8] = include_bytes!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/vp9-opus.webm"
+    ));
+
+    fn read(data: &[u8]) -> VideoMetadata {
+        read_metadata(&mut Cursor::new(data)).unwrap()
+    }
+
+    #[test]
+    fn test_fragmented_mp4() {
+        let metadata = read(FRAGMENTED_MP4);
+        assert_eq!((metadata.width, metadata.height), (320, 180));
+        assert_eq!(metadata.codec, "h264");
+        assert_eq!(metadata.container, "mp4");
+        assert!(metadata.fragmented);
+        assert_eq!(metadata.timescale, 15360);
+        assert_eq!(metadata.frame_count, 60);
+        assert_eq!(metadata.keyframe_count, 2);
+        assert_eq!(metadata.fps, 30.0);
+        assert_eq!(metadata.duration_ms, 2000.0);
+
+        let audio = &metadata.audio_tracks[0];
+        assert_eq!(audio.codec, "aac");
+        assert_eq!((audio.sample_rate, audio.channels), (48000, 2));
+        assert!((audio.duration_ms - 2005.33).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_truncated_fragmented_mp4() {
+        // Cut into the second fragment, as a crash would
+        let metadata = read(&FRAGMENTED_MP4[..FRAGMENTED_MP4.len() - 700]);
+        assert_eq!(metadata.frame_count, 30);
+        assert_eq!(metadata.keyframe_count, 1);
+        assert_eq!(metadata.duration_ms, 1000.0);
+    }
+
+    #[test]
+    fn test_corrupt_box_size() {
+        // A 64-bit size that would run past the end of any file
+        let mut data = FRAGMENTED_MP4.to_vec();
+        data.extend([0, 0, 0, 1]);
+        data.extend(b"free");
+        data.extend(u64::MAX.to_be_bytes());
+        let metadata = read(&data);
+        assert_eq!(metadata.frame_count, 60);
+    }
+
+    #[test]
+    fn test_complete_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording-0.mp4");
+        std::fs::write(&path, FRAGMENTED_MP4).unwrap();
+        assert!(is_complete(&path).unwrap());
+        std::fs::write(&path, &FRAGMENTED_MP4[..FRAGMENTED_MP4.len() - 700]).unwrap();
+        assert!(!is_complete(&path).unwrap());
+
+        let path = dir.path().join("recording-0.webm");
+        std::fs::write(&path, WEBM).unwrap();
+        assert!(is_complete(&path).unwrap());
+        std::fs::write(&path, &WEBM[..WEBM.len() - 10]).unwrap();
+        assert!(!is_complete(&path).unwrap());
+    }
+
+    #[test]
+    fn test_progressive_mov() {
+        let metadata = read(PROGRESSIVE_MOV);
+        assert_eq!((metadata.width, metadata.height), (640, 360));
+        assert_eq!(metadata.codec, "hevc");
+        assert_eq!(metadata.container, "mov");
+        assert!(!metadata.fragmented);
+        assert_eq!(metadata.frame_count, 50);
+        assert_eq!(metadata.keyframe_count, 2);
+        assert_eq!(metadata.fps, 25.0);
+        assert_eq!(metadata.duration_ms, 2000.0);
+        assert!(metadata.audio_tracks.is_empty());
+    }
+
+    #[test]
+    fn test_webm() {
+        let metadata = read(WEBM);
+        assert_eq!((metadata.width, metadata.height), (320, 240));
+        assert_eq!(metadata.codec, "vp9");
+        assert_eq!(metadata.container, "webm");
+        assert_eq!(metadata.timescale, 1000);
+        assert_eq!(metadata.frame_count, 30);
+        assert_eq!(metadata.keyframe_count, 2);
+        assert!((metadata.fps - 30.0).abs() < 0.001);
+        assert_eq!(metadata.duration_ms, 1000.0);
+
+        let audio = &metadata.audio_tracks[0];
+        assert_eq!(audio.codec, "opus");
+        assert_eq!((audio.sample_rate, audio.channels), (48000, 2));
+    }
+
+    #[test]
+    fn test_unknown_format() {
+        let error = read_metadata(&mut Cursor::new(b"not a video file")).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
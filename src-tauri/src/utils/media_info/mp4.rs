@@ -0,0 +1,459 @@
+//! MP4 and QuickTime boxes
+//!
+//! Track details come from `moov`. A progressive file has every sample in
+//! its sample tables (`stts`, `stss`); a fragmented one has empty tables and
+//! describes its samples in each `moof` instead, with defaults from `trex`
+//! and `tfhd`. A fragment only counts once its `mdat` is complete.
+
+use super::{invalid_data, read_body, Parsed, Track, TrackKind};
+use std::io::{Read, Seek, SeekFrom};
+
+/// `sample_is_non_sync_sample` in the sample flags
+const NON_SYNC_SAMPLE: u32 = 0x0001_0000;
+
+/// Whether four bytes are a box type that can start an MP4 or MOV file
+pub(super) fn is_box_type(bytes: &[u8]) -> bool {
+    matches!(
+        bytes,
+        b"ftyp" | b"moov" | b"mdat" | b"free" | b"skip" | b"wide" | b"styp"
+    )
+}
+
+/// Sample defaults of a track from `trex`
+#[derive(Debug, Default, Clone, Copy)]
+struct SampleDefaults {
+    duration: u32,
+    flags: u32,
+}
+
+/// Samples of one track in a fragment
+#[derive(Debug, Default)]
+struct FragmentSamples {
+    track_id: u64,
+    count: u64,
+    keyframes: u64,
+    duration: u64,
+}
+
+pub(super) fn read<R: Read + Seek>(reader: &mut R) -> std::io::Result<Parsed> {
+    let file_len = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let mut container = "mp4";
+    let mut tracks: Vec<Track> = Vec::new();
+    let mut defaults: Vec<(u64, SampleDefaults)> = Vec::new();
+    let mut movie_duration_ms = None;
+    let mut fragmented = false;
+    let mut pending: Vec<FragmentSamples> = Vec::new();
+
+    let mut pos = 0;
+    while pos + 8 <= file_len {
+        let (box_type, header_len, size) = read_box_header(reader, file_len - pos)?;
+        let end = match pos.checked_add(size) {
+            Some(end) if end > pos && end <= file_len => end,
+            // Cut short or corrupt; anything from here on is unusable
+            _ => break,
+        };
+        let body_len = size - header_len;
+
+        match &box_type {
+            b"ftyp" | b"moov" | b"moof" => {
+                let Some(body) = read_body(reader, body_len)? else {
+                    break;
+                };
+                match &box_type {
+                    b"ftyp" if body.starts_with(b"qt  ") => container = "mov",
+                    b"moov" => {
+                        movie_duration_ms = read_moov(&body, &mut tracks, &mut defaults)?;
+                    }
+                    b"moof" => {
+                        fragmented = true;
+                        pending.extend(read_moof(&body, &defaults)?);
+                    }
+                    _ => {}
+                }
+            }
+            b"mdat" => {
+                // The fragment's samples are all there
+                for samples in pending.drain(..) {
+                    if let Some(track) = tracks.iter_mut().find(|t| t.id == samples.track_id) {
+                        track.sample_count += samples.count;
+                        track.keyframe_count += samples.keyframes;
+                        track.sample_duration += samples.duration;
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        pos = end;
+        reader.seek(SeekFrom::Start(pos))?;
+    }
+
+    Ok(Parsed {
+        container,
+        fragmented,
+        tracks,
+        duration_ms: movie_duration_ms,
+        truncated: pos < file_len,
+    })
+}
+
+/// Type, header length and total size of the box at the reader's position
+fn read_box_header<R: Read>(
+    reader: &mut R,
+    remaining: u64,
+) -> std::io::Result<([u8; 4], u64, u64)> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    let box_type = [header[4], header[5], header[6], header[7]];
+    match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+        // Extends to the end of the file
+        0 => Ok((box_type, 8, remaining)),
+        1 => {
+            let mut large = [0u8; 8];
+            reader.read_exact(&mut large)?;
+            let size = u64::from_be_bytes(large);
+            if size < 16 {
+                return Err(invalid_data("Invalid MP4 box size"));
+            }
+            Ok((box_type, 16, size))
+        }
+        size if size < 8 => Err(invalid_data("Invalid MP4 box size")),
+        size => Ok((box_type, 8, size as u64)),
+    }
+}
+
+/// Iterator over the child boxes in a box body
+struct Boxes<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Iterator for Boxes<'a> {
+    type Item = ([u8; 4], &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < 8 {
+            return None;
+        }
+        let size = u32::from_be_bytes(self.data[..4].try_into().ok()?) as usize;
+        let box_type: [u8; 4] = self.data[4..8].try_into().ok()?;
+        let (header_len, size) = match size {
+            0 => (8, self.data.len()),
+            1 => {
+                let large = u64::from_be_bytes(self.data.get(8..16)?.try_into().ok()?);
+                (16, usize::try_from(large).ok()?)
+            }
+            size => (8, size),
+        };
+        if size < header_len || size > self.data.len() {
+            self.data = &[];
+            return None;
+        }
+        let body = &self.data[header_len..size];
+        self.data = &self.data[size..];
+        Some((box_type, body))
+    }
+}
+
+fn boxes(data: &[u8]) -> Boxes<'_> {
+    Boxes { data }
+}
+
+/// Big-endian reads from a box body, failing on short data
+struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn bytes(&mut self, len: usize) -> std::io::Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(invalid_data("MP4 box is too short"));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> std::io::Result<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u16(&mut self) -> std::io::Result<u16> {
+        Ok(u16::from_be_bytes(self.bytes(2)?.try_into().unwrap()))
+    }
+
+    fn u32(&mut self) -> std::io::Result<u32> {
+        Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> std::io::Result<u64> {
+        Ok(u64::from_be_bytes(self.bytes(8)?.try_into().unwrap()))
+    }
+
+    /// Version and flags of a full box
+    fn version_flags(&mut self) -> std::io::Result<(u8, u32)> {
+        let value = self.u32()?;
+        Ok(((value >> 24) as u8, value & 0x00ff_ffff))
+    }
+
+    /// A 32-bit field in version 0 boxes, 64-bit in version 1
+    fn versioned(&mut self, version: u8) -> std::io::Result<u64> {
+        if version == 1 {
+            self.u64()
+        } else {
+            self.u32().map(u64::from)
+        }
+    }
+}
+
+/// Read the tracks and sample defaults, returning the movie duration
+fn read_moov(
+    body: &[u8],
+    tracks: &mut Vec<Track>,
+    defaults: &mut Vec<(u64, SampleDefaults)>,
+) -> std::io::Result<Option<f64>> {
+    let mut movie_duration_ms = None;
+    for (box_type, child) in boxes(body) {
+        match &box_type {
+            b"mvhd" => {
+                let mut fields = Fields::new(child);
+                let (version, _) = fields.version_flags()?;
+                fields.skip(if version == 1 { 16 } else { 8 })?;
+                let timescale = fields.u32()?;
+                let duration = fields.versioned(version)?;
+                if timescale > 0 && duration > 0 {
+                    movie_duration_ms = Some(duration as f64 * 1000.0 / timescale as f64);
+                }
+            }
+            b"trak" => tracks.push(read_trak(child)?),
+            b"mvex" => {
+                for (_, trex) in boxes(child).filter(|(t, _)| t == b"trex") {
+                    let mut fields = Fields::new(trex);
+                    fields.version_flags()?;
+                    let track_id = fields.u32()? as u64;
+                    fields.skip(4)?;
+                    let duration = fields.u32()?;
+                    fields.skip(4)?;
+                    let flags = fields.u32()?;
+                    defaults.push((track_id, SampleDefaults { duration, flags }));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(movie_duration_ms)
+}
+
+fn read_trak(body: &[u8]) -> std::io::Result<Track> {
+    let mut track = Track::default();
+    for (box_type, child) in boxes(body) {
+        match &box_type {
+            b"tkhd" => {
+                let mut fields = Fields::new(child);
+                let (version, _) = fields.version_flags()?;
+                fields.skip(if version == 1 { 16 } else { 8 })?;
+                track.id = fields.u32()? as u64;
+                // Reserved, duration, reserved, layer, group, volume, reserved, matrix
+                fields.skip(4)?;
+                fields.versioned(version)?;
+                fields.skip(8 + 8 + 36)?;
+                track.width = fields.u32()? >> 16;
+                track.height = fields.u32()? >> 16;
+            }
+            b"mdia" => read_mdia(child, &mut track)?,
+            _ => {}
+        }
+    }
+    Ok(track)
+}
+
+fn read_mdia(body: &[u8], track: &mut Track) -> std::io::Result<()> {
+    for (box_type, child) in boxes(body) {
+        match &box_type {
+            b"mdhd" => {
+                let mut fields = Fields::new(child);
+                let (version, _) = fields.version_flags()?;
+                fields.skip(if version == 1 { 16 } else { 8 })?;
+                track.timescale = fields.u32()?;
+                track.header_duration = fields.versioned(version)?;
+            }
+            b"hdlr" => {
+                let mut fields = Fields::new(child);
+                fields.skip(8)?;
+                track.kind = match fields.bytes(4)? {
+                    b"vide" => TrackKind::Video,
+                    b"soun" => TrackKind::Audio,
+                    _ => TrackKind::Other,
+                };
+            }
+            b"minf" => {
+                for (_, stbl) in boxes(child).filter(|(t, _)| t == b"stbl") {
+                    read_stbl(stbl, track)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn read_stbl(body: &[u8], track: &mut Track) -> std::io::Result<()> {
+    let mut sync_samples = None;
+    for (box_type, child) in boxes(body) {
+        let mut fields = Fields::new(child);
+        match &box_type {
+            b"stsd" => {
+                fields.skip(8)?;
+                if let Some((format, entry)) = boxes(fields.data).next() {
+                    read_sample_entry(&format, entry, track)?;
+                }
+            }
+            b"stts" => {
+                fields.version_flags()?;
+                for _ in 0..fields.u32()? {
+                    let count = fields.u32()? as u64;
+                    let delta = fields.u32()? as u64;
+                    track.sample_count += count;
+                    track.sample_duration += count * delta;
+                }
+            }
+            b"stss" => {
+                fields.version_flags()?;
+                sync_samples = Some(fields.u32()? as u64);
+            }
+            _ => {}
+        }
+    }
+    // Without a sync sample table every sample is a keyframe
+    track.keyframe_count = sync_samples.unwrap_or(track.sample_count);
+    Ok(())
+}
+
+/// Codec and format from the first sample description
+fn read_sample_entry(format: &[u8; 4], entry: &[u8], track: &mut Track) -> std::io::Result<()> {
+    track.codec = codec_name(format);
+    let mut fields = Fields::new(entry);
+    // Reserved and data reference index
+    fields.skip(8)?;
+    match track.kind {
+        TrackKind::Video => {
+            fields.skip(16)?;
+            track.width = fields.u16()? as u32;
+            track.height = fields.u16()? as u32;
+        }
+        TrackKind::Audio => {
+            fields.skip(8)?;
+            track.channels = fields.u16()?;
+            fields.skip(6)?;
+            track.sample_rate = fields.u32()? >> 16;
+        }
+        TrackKind::Other => {}
+    }
+    Ok(())
+}
+
+/// FFmpeg's name for a sample entry format
+fn codec_name(format: &[u8; 4]) -> String {
+    match format {
+        b"avc1" | b"avc3" => "h264",
+        b"hvc1" | b"hev1" => "hevc",
+        b"vp08" => "vp8",
+        b"vp09" => "vp9",
+        b"av01" => "av1",
+        b"mp4v" => "mpeg4",
+        b"apch" | b"apcn" | b"apcs" | b"apco" | b"ap4h" => "prores",
+        b"mp4a" => "aac",
+        b"Opus" => "opus",
+        b"fLaC" => "flac",
+        b"ipcm" | b"fpcm" | b"lpcm" | b"sowt" | b"twos" => "pcm",
+        other => return String::from_utf8_lossy(other).trim().to_string(),
+    }
+    .to_string()
+}
+
+/// Samples per track in a movie fragment
+fn read_moof(
+    body: &[u8],
+    defaults: &[(u64, SampleDefaults)],
+) -> std::io::Result<Vec<FragmentSamples>> {
+    let mut fragments = Vec::new();
+    for (_, traf) in boxes(body).filter(|(t, _)| t == b"traf") {
+        let mut samples = FragmentSamples::default();
+        let mut track_defaults = SampleDefaults::default();
+
+        for (box_type, child) in boxes(traf) {
+            let mut fields = Fields::new(child);
+            match &box_type {
+                b"tfhd" => {
+                    let (_, flags) = fields.version_flags()?;
+                    samples.track_id = fields.u32()? as u64;
+                    track_defaults = defaults
+                        .iter()
+                        .find(|(id, _)| *id == samples.track_id)
+                        .map(|(_, d)| *d)
+                        .unwrap_or_default();
+                    if flags & 0x01 != 0 {
+                        fields.skip(8)?;
+                    }
+                    if flags & 0x02 != 0 {
+                        fields.skip(4)?;
+                    }
+                    if flags & 0x08 != 0 {
+                        track_defaults.duration = fields.u32()?;
+                    }
+                    if flags & 0x10 != 0 {
+                        fields.skip(4)?;
+                    }
+                    if flags & 0x20 != 0 {
+                        track_defaults.flags = fields.u32()?;
+                    }
+                }
+                b"trun" => {
+                    let (_, flags) = fields.version_flags()?;
+                    let count = fields.u32()?;
+                    if flags & 0x01 != 0 {
+                        fields.skip(4)?;
+                    }
+                    let first_flags = if flags & 0x04 != 0 {
+                        Some(fields.u32()?)
+                    } else {
+                        None
+                    };
+                    for i in 0..count {
+                        let mut duration = track_defaults.duration;
+                        let mut sample_flags = track_defaults.flags;
+                        if flags & 0x100 != 0 {
+                            duration = fields.u32()?;
+                        }
+                        if flags & 0x200 != 0 {
+                            fields.skip(4)?;
+                        }
+                        if flags & 0x400 != 0 {
+                            sample_flags = fields.u32()?;
+                        }
+                        if flags & 0x800 != 0 {
+                            fields.skip(4)?;
+                        }
+                        if let (0, Some(first)) = (i, first_flags) {
+                            sample_flags = first;
+                        }
+
+                        samples.count += 1;
+                        samples.duration += duration as u64;
+                        if sample_flags & NON_SYNC_SAMPLE == 0 {
+                            samples.keyframes += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        fragments.push(samples);
+    }
+    Ok(fragments)
+}
@@ -3,3 +3,4 @@
 //! Common utilities used across the application.
 
 pub mod error;
+pub mod media_info;
@@ -1,5 +1,6 @@
-import { useState, useEffect, useRef } from "react";
+import { useState, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { getCurrentWindow } from "@tauri-apps/api/window";
 import {
   Monitor,
@@ -22,7 +23,14 @@ import {
   Check,
 } from "lucide-react";
 import PostRecordingPopup from "./PostRecordingPopup";
-import type { RecordingResult } from "../../types/recording";
+import {
+  AUDIO_DEVICES_CHANGED_EVENT,
+  AUDIO_LEVELS_EVENT,
+  RECORDING_EVENT,
+  type AudioLevels,
+  type RecordingEvent,
+  type RecordingResult,
+} from "../../types/recording";
 
 type RecordingState = "idle" | "recording" | "paused";
 type SourceType = "display" | "window" | "area" | "device";
@@ -67,6 +75,7 @@ export default function RecordingToolbar() {
   // Recording state
   const [recordingState, setRecordingState] = useState<RecordingState>("idle");
   const [recordingTime, setRecordingTime] = useState(0);
+  const [micLevel, setMicLevel] = useState(0);
   const [isLoading, setIsLoading] = useState(false);
 
   // Post-recording popup state
@@ -74,9 +83,6 @@ export default function RecordingToolbar() {
   const [recordingResult, setRecordingResult] =
     useState<RecordingResult | null>(null);
 
-  const timerRef = useRef<number | null>(null);
-  const recordingStartTime = useRef<number>(0);
-
   // Load displays and audio devices
   useEffect(() => {
     const init = async () => {
@@ -110,31 +116,83 @@ export default function RecordingToolbar() {
     init();
   }, []);
 
-  // Timer for recording duration
+  // Keep the mic menu in sync with plugged-in devices
   useEffect(() => {
-    if (recordingState === "recording") {
-      if (recordingStartTime.current === 0) {
-        recordingStartTime.current = Date.now();
-      }
-      timerRef.current = window.setInterval(() => {
-        setRecordingTime(Date.now() - recordingStartTime.current);
-      }, 100);
-    } else {
-      if (timerRef.current) {
-        clearInterval(timerRef.current);
-        timerRef.current = null;
-      }
-      if (recordingState === "idle") {
-        recordingStartTime.current = 0;
+    const unlisten = listen<AudioDeviceInfo[]>(
+      AUDIO_DEVICES_CHANGED_EVENT,
+      (event) => {
+        const devices = event.payload;
+        setAudioDevices(devices);
+        setSelectedMicId((current) => {
+          if (current && devices.some((d) => d.id === current)) {
+            return current;
+          }
+          const fallback = devices.find((d) => d.isDefault) ?? devices[0];
+          return fallback ? fallback.id : null;
+        });
+      },
+    );
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  // Recording duration and channel errors pushed by the backend
+  useEffect(() => {
+    const unlisten = listen<RecordingEvent>(RECORDING_EVENT, (event) => {
+      const payload = event.payload;
+      if (payload.type === "progress") {
+        setRecordingTime(payload.durationMs);
+      } else if (payload.type === "channelError") {
+        console.error(
+          `Recording channel ${payload.channelId} failed:`,
+          payload.message,
+        );
+      } else if (payload.type === "error") {
+        console.error("Recording error:", payload.message);
       }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
+  useEffect(() => {
+    if (recordingState === "idle") {
+      setRecordingTime(0);
     }
+  }, [recordingState]);
 
+  // Meter the selected mic before recording; while recording the
+  // microphone channel publishes its own levels
+  useEffect(() => {
+    if (recordingState !== "idle" || !micEnabled || !selectedMicId) {
+      return;
+    }
+
+    invoke("start_audio_monitor", { deviceId: selectedMicId }).catch((err) =>
+      console.error("Failed to start audio monitor:", err),
+    );
     return () => {
-      if (timerRef.current) {
-        clearInterval(timerRef.current);
+      invoke("stop_audio_monitor").catch(() => {});
+      setMicLevel(0);
+    };
+  }, [recordingState, micEnabled, selectedMicId]);
+
+  useEffect(() => {
+    const unlisten = listen<AudioLevels>(AUDIO_LEVELS_EVENT, (event) => {
+      const { sourceId, peak } = event.payload;
+      if (sourceId === "monitor" || sourceId === "microphone") {
+        setMicLevel(Math.max(0, ...peak));
       }
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
     };
-  }, [recordingState]);
+  }, []);
 
   // Close dropdowns when clicking outside
   useEffect(() => {
@@ -415,6 +473,14 @@ export default function RecordingToolbar() {
               <MicOff className="w-4 h-4" />
             )}
             <span className="text-xs">Mic</span>
+            {micEnabled && (
+              <span className="absolute left-1 right-1 bottom-0.5 h-0.5 rounded bg-white/10 overflow-hidden">
+                <span
+                  className="block h-full bg-green-400"
+                  style={{ width: `${Math.min(micLevel, 1) * 100}%` }}
+                />
+              </span>
+            )}
             <button
               type="button"
               onClick={(e) => {
@@ -5,14 +5,189 @@ export interface RecordingResult {
   totalDurationMs: number;
   sessionCount: number;
   outputFiles: string[];
+  failedChannels: ChannelFailure[];
 }
 
+// A channel that failed without aborting the recording
+export interface ChannelFailure {
+  channelId: string;
+  operation: "initialize" | "start" | "stop" | "pause" | "resume";
+  message: string;
+}
+
+// Tauri event carrying recording events from the backend
+export const RECORDING_EVENT = "recording-event";
+
+// Events broadcast by the recording coordinator
+export type RecordingEvent =
+  | { type: "started" }
+  | { type: "stopped" }
+  | { type: "paused" }
+  | { type: "resumed" }
+  | { type: "error"; message: string }
+  | { type: "channelError"; channelId: string; message: string }
+  | { type: "progress"; durationMs: number }
+  | ({ type: "health" } & RecordingHealth)
+  | { type: "replaySaved"; bundlePath: string }
+  | { type: "diskSpaceLow"; freeBytes: number; remainingSecs: number }
+  | { type: "autoStopped"; reason: StopReason; bundlePath: string };
+
+// Why a recording ended (sessions.json stopReason, autoStopped events)
+export type StopReason = "requested" | "lowDiskSpace";
+
+// Free space limits of a recording (RecordingConfig.diskSpace, all optional)
+export interface DiskSpaceSettings {
+  // Stop before less than this is left on the output volume
+  reserveMb?: number;
+  // Send diskSpaceLow once less recording time than this is left
+  warnRemainingSecs?: number;
+}
+
+// Capture and encoder statistics of one channel
+export interface ChannelHealthReport {
+  channelId: string;
+  channelType: string;
+  targetFps: number | null;
+  framesCaptured: number;
+  framesDropped: number;
+  framesRepeated: number;
+  achievedFps: number;
+  bytesWritten: number;
+  sampleRate: number | null;
+  audioFramesWritten: number;
+  audioFramesExpected: number;
+  writeCount: number;
+  writeFailures: number;
+  avgWriteLatencyMs: number;
+  maxWriteLatencyMs: number;
+}
+
+// Result of get_recording_health, also stored as health.json
+export interface RecordingHealth {
+  durationMs: number;
+  channels: ChannelHealthReport[];
+}
+
+// Tauri event carrying audio levels of metered sources
+export const AUDIO_LEVELS_EVENT = "audio-levels";
+
+// Linear (0-1) RMS and peak of each audio channel over ~50ms
+export interface AudioLevels {
+  // "microphone", "system-audio" or "monitor" (pre-recording)
+  sourceId: string;
+  rms: number[];
+  peak: number[];
+}
+
+// Tauri event carrying the input device list after devices are plugged in or removed
+export const AUDIO_DEVICES_CHANGED_EVENT = "audio-devices-changed";
+
+// What the microphone records if its device is unplugged (microphoneFallback)
+export type MicrophoneFallback = "silence" | "defaultDevice";
+
+// Codec of a recorded audio track (microphoneFormat, systemAudioFormat)
+export type AudioFormat =
+  | { codec: "aac"; bitrateKbps: number }
+  | { codec: "opus"; bitrateKbps: number }
+  | { codec: "flac" }
+  | { codec: "wav" }
+  | { codec: "caf" };
+
+// Frame rate and encoding of display recordings (RecordingConfig.video, all optional)
+export interface VideoSettings {
+  fps?: number;
+  // Downscale so the shorter side is at most 1080 or 720 pixels
+  scale?: "native" | "1080p" | "720p";
+  // ffv1 is lossless and recorded to .mkv
+  codec?: "h264" | "hevc" | "vp9" | "av1" | "ffv1";
+  quality?: { mode: "crf"; crf: number } | { mode: "bitrate"; kbps: number };
+  preset?: "ultrafast" | "veryfast" | "fast" | "medium" | "slow";
+}
+
+// Replay buffer settings for start_replay_buffer (all optional)
+export interface ReplayConfig {
+  durationSecs?: number;
+  // A saved replay can be up to one segment longer than durationSecs
+  segmentSecs?: number;
+  // Directory the buffer creates its own scratch folder in
+  bufferDir?: string | null;
+}
+
+// Files one channel wrote in a session, relative to the recording directory
+export interface ChannelFiles {
+  channelId: string;
+  channelType: string;
+  files: string[];
+}
+
+// One session of sessions.json, written at stop
+export interface SessionRecord {
+  index: number;
+  durationMs: number;
+  processTimeStartMs: number;
+  processTimeEndMs: number;
+  unixStartMs: number;
+  unixEndMs: number;
+  channels: ChannelFiles[];
+}
+
+export interface SessionsManifest {
+  sessions: SessionRecord[];
+  stopReason: StopReason;
+}
+
+// Result of recover_recording
+export interface RecoveryReport {
+  bundlePath: string;
+  sessionCount: number;
+  recoveredFiles: string[];
+  failedFiles: string[];
+}
+
+// A media tool found by get_media_toolchain
+export interface MediaToolInfo {
+  path: string;
+  source: "configured" | "sidecar" | "path";
+  version: string | null;
+}
+
+// Result of get_media_toolchain and set_media_tool_paths
+export interface MediaToolchain {
+  ffmpeg: MediaToolInfo | null;
+  ffprobe: MediaToolInfo | null;
+  encoders: string[];
+  muxers: string[];
+  filters: string[];
+}
+
+// Argument of set_media_tool_paths; null uses the bundled or PATH tool
+export interface MediaToolPaths {
+  ffmpeg?: string | null;
+  ffprobe?: string | null;
+}
+
+// Result of get_video_metadata, read from the container without FFprobe
 export interface VideoMetadata {
   width: number;
   height: number;
   fps: number;
   durationMs: number;
   codec: string;
+  container: "mp4" | "mov" | "matroska" | "webm";
+  // Ticks per second of the video track's timestamps
+  timescale: number;
+  frameCount: number;
+  keyframeCount: number;
+  // Still in the fragmented form written while recording
+  fragmented: boolean;
+  audioTracks: AudioTrackInfo[];
+}
+
+export interface AudioTrackInfo {
+  codec: string;
+  sampleRate: number;
+  channels: number;
+  durationMs: number;
 }
 
 export interface RecordingInfo {
@@ -45,6 +220,43 @@ export interface MouseClickEvent {
   unixTimeMs: number;
 }
 
+// Scroll event from recording (deltas follow the DOM WheelEvent convention)
+export interface MouseScrollEvent {
+  x: number;
+  y: number;
+  deltaX: number;
+  deltaY: number;
+  deltaMode: "pixel" | "line";
+  phase: "mayBegin" | "began" | "changed" | "ended" | "cancelled" | "momentum" | null;
+  activeModifiers: string[];
+  processTimeMs: number;
+  unixTimeMs: number;
+}
+
+// Drag event from recording
+export interface MouseDragEvent {
+  x: number;
+  y: number;
+  startX: number;
+  startY: number;
+  button: "left" | "right" | "middle";
+  phase: "begin" | "update" | "end";
+  activeModifiers: string[];
+  processTimeMs: number;
+  unixTimeMs: number;
+}
+
+// Keystroke event from recording (printable keys are masked as "•" in privacy mode)
+export interface KeystrokeEvent {
+  keyCode: number;
+  key: string;
+  character: string | null;
+  eventType: "down" | "up";
+  activeModifiers: string[];
+  processTimeMs: number;
+  unixTimeMs: number;
+}
+
 // Cursor image info from recording
 export interface CursorInfo {
   id: string;
@@ -55,6 +267,39 @@ export interface CursorInfo {
   height: number;
 }
 
+// Where one channel's file starts relative to its session (sync.json)
+export interface ChannelSync {
+  channelId: string;
+  channelType: string;
+  firstSampleMs: number | null;
+  offsetMs: number | null;
+}
+
+export interface SessionSync {
+  index: number;
+  startMs: number;
+  endMs: number;
+  channels: ChannelSync[];
+}
+
+export interface SyncManifest {
+  clockStartUnixMs: number;
+  sessions: SessionSync[];
+}
+
+// One recorded display and the input files in its coordinates (sources.json)
+export interface BundleVideoSource {
+  channelId: string;
+  displayId: number | null;
+  primary: boolean;
+  videoPath: string;
+  videoMetadata: VideoMetadata;
+  // Captured frame size the input files use, when the video was downscaled
+  captureSize: { width: number; height: number } | null;
+  mouseMovesPath: string | null;
+  mouseClicksPath: string | null;
+}
+
 // Complete recording bundle data
 export interface RecordingBundle {
   // Paths
@@ -62,12 +307,20 @@ export interface RecordingBundle {
   videoPath: string;
   micAudioPath: string | null;
   systemAudioPath: string | null;
+  webcamPath: string | null;
 
   // Data
   mouseMoves: MouseMoveEvent[];
   mouseClicks: MouseClickEvent[];
+  mouseScrolls: MouseScrollEvent[];
+  mouseDrags: MouseDragEvent[];
+  keystrokes: KeystrokeEvent[];
   cursors: Record<string, CursorInfo>;
 
   // Metadata
   videoMetadata: VideoMetadata;
+  sync: SyncManifest | null;
+  health: RecordingHealth | null;
+  // Every recorded display, the main video first
+  videoSources: BundleVideoSource[];
 }